@@ -0,0 +1,99 @@
+//! Benchmarks of the simulation hot paths: one `run_trajectory` at the default 600 steps and a
+//! small fixed `run_sweep`, each against several intensity models. The `exponential_dyn` case
+//! runs the exponential model through `&dyn IntensityModel` to compare with generic dispatch.
+//!
+//! Run with `cargo bench`; criterion keeps the previous run as a baseline for regressions.
+
+use avellaneda_stoikov_rs::analysis::{SweepConfig, run_sweep};
+use avellaneda_stoikov_rs::model::{
+    ExponentialIntensity, IntensityModel, LinearIntensity, Parameters, PowerLawIntensity,
+    QuotingModel,
+};
+use avellaneda_stoikov_rs::sim::{SimConfig, run_trajectory_with_rng};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::hint::black_box;
+
+fn base_params() -> Parameters {
+    Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov)
+        .expect("benchmark parameters are valid")
+}
+
+const EXPONENTIAL: ExponentialIntensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+const POWER_LAW: PowerLawIntensity = PowerLawIntensity {
+    a: 140.0,
+    k: 1.5,
+    beta: 2.0,
+};
+const LINEAR: LinearIntensity = LinearIntensity {
+    a: 140.0,
+    slope: 100.0,
+};
+
+fn bench_trajectory<M: IntensityModel + ?Sized>(
+    c: &mut Criterion,
+    name: &str,
+    model: &M,
+    config: &SimConfig,
+) {
+    let params = base_params();
+    c.benchmark_group("run_trajectory")
+        .bench_function(BenchmarkId::from_parameter(name), |b| {
+            // A fresh seeded RNG per iteration keeps every sample on the same path.
+            b.iter(|| {
+                let mut rng = StdRng::seed_from_u64(7);
+                black_box(run_trajectory_with_rng(&params, config, model, &mut rng))
+            })
+        });
+}
+
+fn trajectory_benches(c: &mut Criterion) {
+    let config = SimConfig::builder()
+        .build()
+        .expect("default config is valid");
+    bench_trajectory(c, "exponential", &EXPONENTIAL, &config);
+    bench_trajectory(
+        c,
+        "exponential_dyn",
+        &EXPONENTIAL as &dyn IntensityModel,
+        &config,
+    );
+    bench_trajectory(c, "power_law", &POWER_LAW, &config);
+    bench_trajectory(c, "linear", &LINEAR, &config);
+}
+
+fn sweep_config() -> SweepConfig {
+    SweepConfig {
+        gammas: vec![0.05, 0.5],
+        sigmas: vec![0.1, 0.3],
+        ks: vec![1.5],
+        drifts: vec![0.0],
+        sim_config: SimConfig::builder()
+            .num_steps(200)
+            .build()
+            .expect("sweep config is valid"),
+        iterations_per_param: 50,
+        ..SweepConfig::default()
+    }
+}
+
+fn bench_sweep<M: IntensityModel + ?Sized>(c: &mut Criterion, name: &str, model: &M) {
+    let sweep = sweep_config();
+    let mut group = c.benchmark_group("run_sweep");
+    group.sample_size(20);
+    group.bench_function(BenchmarkId::from_parameter(name), |b| {
+        b.iter(|| black_box(run_sweep(base_params(), &sweep, model).unwrap()))
+    });
+    group.finish();
+}
+
+fn sweep_benches(c: &mut Criterion) {
+    bench_sweep(c, "exponential", &EXPONENTIAL);
+    bench_sweep(c, "exponential_dyn", &EXPONENTIAL as &dyn IntensityModel);
+    bench_sweep(c, "power_law", &POWER_LAW);
+    bench_sweep(c, "linear", &LINEAR);
+}
+
+criterion_group!(benches, trajectory_benches, sweep_benches);
+criterion_main!(benches);
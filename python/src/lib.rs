@@ -0,0 +1,307 @@
+//! Python bindings for the simulator and the parameter sweep.
+//!
+//! Results cross into Python through their serde representation, so every field of `SimResult`
+//! and `SweepResult` arrives as a plain `dict` key, NaNs as `None`. A sweep's list of dicts and a
+//! trajectory's list of step dicts both load straight into `pandas.DataFrame`.
+
+use avellaneda_stoikov_rs::analysis::{SweepConfig, run_sweep as sweep};
+use avellaneda_stoikov_rs::model::{ExponentialIntensity, QuotingModel};
+use avellaneda_stoikov_rs::sim::run_trajectory_with_rng;
+use avellaneda_stoikov_rs::{model, sim};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde_json::Value;
+
+/// Strategy parameters, quoting with the Avellaneda-Stoikov reservation price and spread.
+#[pyclass(name = "Parameters", frozen, skip_from_py_object)]
+#[derive(Clone, Copy)]
+struct Parameters(model::Parameters);
+
+#[pymethods]
+impl Parameters {
+    #[new]
+    #[pyo3(signature = (*, gamma = 0.1, sigma = 0.2, t_horizon = 1.0, k = 1.5, a = 140.0))]
+    fn new(gamma: f64, sigma: f64, t_horizon: f64, k: f64, a: f64) -> PyResult<Self> {
+        model::Parameters::new(
+            gamma,
+            sigma,
+            t_horizon,
+            k,
+            a,
+            QuotingModel::AvellanedaStoikov,
+        )
+        .map(Parameters)
+        .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    #[getter]
+    fn gamma(&self) -> f64 {
+        self.0.gamma
+    }
+
+    #[getter]
+    fn sigma(&self) -> f64 {
+        self.0.sigma
+    }
+
+    #[getter]
+    fn t_horizon(&self) -> f64 {
+        self.0.t_horizon
+    }
+
+    #[getter]
+    fn k(&self) -> f64 {
+        self.0.k
+    }
+
+    #[getter]
+    fn a(&self) -> f64 {
+        self.0.a
+    }
+
+    fn __repr__(&self) -> String {
+        let p = &self.0;
+        format!(
+            "Parameters(gamma={}, sigma={}, t_horizon={}, k={}, a={})",
+            p.gamma, p.sigma, p.t_horizon, p.k, p.a
+        )
+    }
+}
+
+/// Simulation settings; omitted keywords keep the `SimConfig::builder` defaults.
+#[pyclass(name = "SimConfig", frozen, skip_from_py_object)]
+#[derive(Clone)]
+struct SimConfig(sim::SimConfig);
+
+#[pymethods]
+impl SimConfig {
+    #[new]
+    #[pyo3(signature = (
+        *,
+        dt = None,
+        num_steps = None,
+        s_0 = None,
+        drift = None,
+        latency_steps = None,
+        order_size = None,
+        adverse_selection = None,
+        max_drawdown_stop = None,
+        requote_interval = None,
+        min_half_spread = None,
+        max_half_spread = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        dt: Option<f64>,
+        num_steps: Option<usize>,
+        s_0: Option<f64>,
+        drift: Option<f64>,
+        latency_steps: Option<usize>,
+        order_size: Option<f64>,
+        adverse_selection: Option<f64>,
+        max_drawdown_stop: Option<f64>,
+        requote_interval: Option<usize>,
+        min_half_spread: Option<f64>,
+        max_half_spread: Option<f64>,
+    ) -> PyResult<Self> {
+        let mut builder = sim::SimConfig::builder();
+        if let Some(dt) = dt {
+            builder = builder.dt(dt);
+        }
+        if let Some(num_steps) = num_steps {
+            builder = builder.num_steps(num_steps);
+        }
+        if let Some(s_0) = s_0 {
+            builder = builder.s_0(s_0);
+        }
+        if let Some(drift) = drift {
+            builder = builder.drift(drift);
+        }
+        if let Some(latency_steps) = latency_steps {
+            builder = builder.latency_steps(latency_steps);
+        }
+        if let Some(order_size) = order_size {
+            builder = builder.order_size(order_size);
+        }
+        if let Some(adverse_selection) = adverse_selection {
+            builder = builder.adverse_selection(adverse_selection);
+        }
+        if let Some(max_drawdown) = max_drawdown_stop {
+            builder = builder.max_drawdown_stop(max_drawdown);
+        }
+        if let Some(requote_interval) = requote_interval {
+            builder = builder.requote_interval(requote_interval);
+        }
+        if let Some(min_half_spread) = min_half_spread {
+            builder = builder.min_half_spread(min_half_spread);
+        }
+        if let Some(max_half_spread) = max_half_spread {
+            builder = builder.max_half_spread(max_half_spread);
+        }
+        builder
+            .build()
+            .map(SimConfig)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    #[getter]
+    fn dt(&self) -> f64 {
+        self.0.dt
+    }
+
+    #[getter]
+    fn num_steps(&self) -> usize {
+        self.0.num_steps
+    }
+
+    #[getter]
+    fn s_0(&self) -> f64 {
+        self.0.s_0
+    }
+
+    #[getter]
+    fn drift(&self) -> f64 {
+        self.0.drift
+    }
+
+    fn __repr__(&self) -> String {
+        let c = &self.0;
+        format!(
+            "SimConfig(dt={}, num_steps={}, s_0={}, drift={}, ...)",
+            c.dt, c.num_steps, c.s_0, c.drift
+        )
+    }
+}
+
+/// Converts a serde value into the matching Python object: objects become dicts, arrays lists,
+/// and nulls `None`.
+fn to_python<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(b) => b.into_pyobject(py)?.to_owned().into_any(),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => i.into_pyobject(py)?.into_any(),
+            (None, Some(u)) => u.into_pyobject(py)?.into_any(),
+            _ => n.as_f64().unwrap_or(f64::NAN).into_pyobject(py)?.into_any(),
+        },
+        Value::String(s) => s.into_pyobject(py)?.into_any(),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(to_python(py, item)?)?;
+            }
+            list.into_any()
+        }
+        Value::Object(fields) => {
+            let dict = PyDict::new(py);
+            for (key, field) in fields {
+                dict.set_item(key, to_python(py, field)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+fn serialize<'py, T: serde::Serialize>(py: Python<'py>, value: &T) -> PyResult<Bound<'py, PyAny>> {
+    let value =
+        serde_json::to_value(value).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    to_python(py, &value)
+}
+
+/// Simulates one trajectory against an exponential market intensity with the strategy's `a` and
+/// `k`, returning the `SimResult` as a dict; `"trajectory"` holds one dict per step.
+#[pyfunction]
+#[pyo3(signature = (params, sim_config, *, seed = None))]
+fn run_trajectory<'py>(
+    py: Python<'py>,
+    params: &Parameters,
+    sim_config: &SimConfig,
+    seed: Option<u64>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let (params, config) = (params.0, sim_config.0.clone());
+    let result = py.detach(move || {
+        let intensity = ExponentialIntensity {
+            k: params.k,
+            a: params.a,
+        };
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        run_trajectory_with_rng(&params, &config, &intensity, &mut rng)
+    });
+    serialize(py, &result)
+}
+
+/// Runs the Monte Carlo grid sweep around `params` and `sim_config` against an exponential market
+/// intensity, returning one dict per combination. An omitted axis keeps its base value.
+///
+/// The sweep runs on the rayon pool with the GIL released, so other Python threads keep running.
+#[pyfunction]
+#[pyo3(signature = (
+    params,
+    sim_config,
+    *,
+    gammas = None,
+    sigmas = None,
+    ks = None,
+    drifts = None,
+    iterations = 1000,
+    antithetic = false,
+    bootstrap_resamples = 0,
+    seed = None,
+))]
+#[allow(clippy::too_many_arguments)]
+fn run_sweep<'py>(
+    py: Python<'py>,
+    params: &Parameters,
+    sim_config: &SimConfig,
+    gammas: Option<Vec<f64>>,
+    sigmas: Option<Vec<f64>>,
+    ks: Option<Vec<f64>>,
+    drifts: Option<Vec<f64>>,
+    iterations: usize,
+    antithetic: bool,
+    bootstrap_resamples: usize,
+    seed: Option<u64>,
+) -> PyResult<Bound<'py, PyList>> {
+    let base_params = params.0;
+    let sweep_config = SweepConfig {
+        gammas: gammas.unwrap_or(vec![base_params.gamma]),
+        sigmas: sigmas.unwrap_or(vec![base_params.sigma]),
+        ks: ks.unwrap_or(vec![base_params.k]),
+        drifts: drifts.unwrap_or(vec![sim_config.0.drift]),
+        sim_config: sim_config.0.clone(),
+        iterations_per_param: iterations,
+        antithetic,
+        bootstrap_resamples,
+        seed,
+        ..SweepConfig::default()
+    };
+    let results = py
+        .detach(move || {
+            let intensity = ExponentialIntensity {
+                k: base_params.k,
+                a: base_params.a,
+            };
+            sweep(base_params, &sweep_config, &intensity)
+        })
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let rows = PyList::empty(py);
+    for result in &results {
+        rows.append(serialize(py, result)?)?;
+    }
+    Ok(rows)
+}
+
+#[pymodule]
+fn avellaneda_stoikov(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Parameters>()?;
+    m.add_class::<SimConfig>()?;
+    m.add_function(wrap_pyfunction!(run_trajectory, m)?)?;
+    m.add_function(wrap_pyfunction!(run_sweep, m)?)?;
+    Ok(())
+}
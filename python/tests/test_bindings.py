@@ -0,0 +1,55 @@
+import threading
+
+import pytest
+
+import avellaneda_stoikov as ast
+
+
+def test_two_cell_sweep():
+    params = ast.Parameters(gamma=0.1, sigma=0.2)
+    sim_config = ast.SimConfig(num_steps=100)
+    rows = ast.run_sweep(params, sim_config, gammas=[0.1, 1.0], iterations=20, seed=7)
+
+    assert [row["gamma"] for row in rows] == [0.1, 1.0]
+    assert all(row["sigma"] == 0.2 and row["k"] == 1.5 for row in rows)
+    assert all(isinstance(row["mean_pnl"], float) for row in rows)
+    # The seed fixes every run, and the bootstrap is off, so its intervals come back as None.
+    assert rows == ast.run_sweep(params, sim_config, gammas=[0.1, 1.0], iterations=20, seed=7)
+    assert rows[0]["mean_pnl_ci_low"] is None
+
+
+def test_sweep_releases_the_gil():
+    params = ast.Parameters()
+    sim_config = ast.SimConfig(num_steps=200)
+    ticks = []
+    done = threading.Event()
+
+    def tick():
+        while not done.is_set():
+            ticks.append(None)
+            done.wait(0.001)
+
+    ticker = threading.Thread(target=tick)
+    ticker.start()
+    try:
+        ast.run_sweep(params, sim_config, gammas=[0.01, 0.1, 1.0], iterations=300)
+    finally:
+        done.set()
+        ticker.join()
+    assert len(ticks) > 1
+
+
+def test_trajectory_steps():
+    result = ast.run_trajectory(ast.Parameters(), ast.SimConfig(num_steps=50), seed=3)
+    steps = result["trajectory"]
+
+    assert len(steps) == 50
+    assert {"time", "mid_price", "bid_price", "ask_price", "inventory"} <= steps[0].keys()
+    assert result == ast.run_trajectory(ast.Parameters(), ast.SimConfig(num_steps=50), seed=3)
+
+
+def test_invalid_inputs_raise_value_error():
+    with pytest.raises(ValueError):
+        ast.Parameters(gamma=-1.0)
+    with pytest.raises(ValueError):
+        ast.SimConfig(dt=0.0)
@@ -1,64 +1,1489 @@
-use crate::model::{IntensityModel, Parameters};
-use crate::sim::{SimConfig, run_trajectory};
-use rayon::prelude::*;
+use crate::error::ConfigError;
+use crate::model::{
+    IntensityModel, IntensitySpec, ParamError, Parameters, QuotingModel, mean_optimal_spread,
+};
+use crate::par::*;
+use crate::sim::{
+    Side, SimConfig, SimError, SimResult, StepRecord, TrajectoryWorkspace, expected_spread_capture,
+    run_antithetic_pair_into, run_trajectory, run_trajectory_into, run_trajectory_with_rng,
+    unseeded_rng,
+};
+use crate::sizes::EmpiricalSizeDistribution;
+use crate::stats::{
+    Histogram, Welford, calmar_ratio, correlation, max_drawdown, mean, percentile, pnl_histogram,
+    sample_variance, sharpe_ratio, sortino_ratio, student_t_cdf, student_t_quantile,
+};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
 pub struct SweepConfig {
     pub gammas: Vec<f64>,
     pub sigmas: Vec<f64>,
     pub ks: Vec<f64>,
     pub drifts: Vec<f64>,
+    /// Arrival rates to sweep; empty sweeps only `base_params.a`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub as_: Vec<f64>,
+    /// Horizons to sweep; empty sweeps only `base_params.t_horizon`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub t_horizons: Vec<f64>,
+    /// Latencies to sweep; empty sweeps only `sim_config.latency_steps`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub latency_steps: Vec<usize>,
     pub sim_config: SimConfig,
     pub iterations_per_param: usize,
+    /// Run `iterations_per_param` trajectories as antithetic pairs (see
+    /// `sim::run_antithetic_pair`), rounding an odd count up. `pnl_sem` is then estimated from
+    /// the pair averages; every other statistic still treats each trajectory as one run. The
+    /// reduction is only large when PnL is close to linear in the price shocks; when inventory
+    /// risk dominates, the pair PnLs are positively correlated and the error can grow.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub antithetic: bool,
+    /// Bootstrap resamples behind the `*_ci_low`/`*_ci_high` fields of each result (see
+    /// `bootstrap_ci`); zero skips the bootstrap and leaves them NaN.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bootstrap_resamples: usize,
+    /// Confidence level of the tail statistics `pnl_p05`, `pnl_p95`, `var_95`, and `cvar_95`,
+    /// whose names assume the default `DEFAULT_TAIL_CONFIDENCE`.
+    #[cfg_attr(feature = "serde", serde(default = "default_tail_confidence"))]
+    pub tail_confidence: f64,
+    /// Minimum acceptable PnL of a run for `sortino_ratio`; only shortfalls below it count as
+    /// downside.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sortino_mar: f64,
+    /// Keep every trajectory of a combination long enough to fill `SweepResult::profile`. This
+    /// holds `iterations_per_param` full paths in memory per combination in flight.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub keep_profiles: bool,
+    /// Base seed for the Monte Carlo runs. Each run is seeded from it and its combination and
+    /// iteration indices (see `derive_seed`), as is each combination's bootstrap, so the results do
+    /// not depend on how rayon schedules the work. `None` draws every run from the thread RNG.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: Option<u64>,
+    /// Model time units in a year, for `SweepResult::sharpe_annualized`; the default
+    /// `DEFAULT_PERIODS_PER_YEAR` reads one unit as a trading day.
+    #[cfg_attr(feature = "serde", serde(default = "default_periods_per_year"))]
+    pub periods_per_year: f64,
+    /// Runs per year to annualise with, overriding the factor derived from `periods_per_year`
+    /// (see `annualization_factor`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub annualization_factor: Option<f64>,
+    /// Also run every combination as a `QuotingModel::FixedSpread` baseline quoting
+    /// `mean_optimal_spread` over the simulated `num_steps * dt` around the mid, on the same
+    /// random paths, for
+    /// `SweepResult::baseline_mean_pnl` and `pnl_uplift`. Doubles the Monte Carlo work.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub baseline: bool,
+    /// Bins of each combination's `SweepResult::pnl_histogram`; 0 leaves it out.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub histogram_bins: usize,
 }
 
-#[derive(Debug, Clone)]
+/// Default `SweepConfig::tail_confidence`.
+pub const DEFAULT_TAIL_CONFIDENCE: f64 = 0.95;
+
+#[cfg(feature = "serde")]
+fn default_tail_confidence() -> f64 {
+    DEFAULT_TAIL_CONFIDENCE
+}
+
+/// Default `SweepConfig::periods_per_year`: trading days in a year.
+pub const DEFAULT_PERIODS_PER_YEAR: f64 = 252.0;
+
+#[cfg(feature = "serde")]
+fn default_periods_per_year() -> f64 {
+    DEFAULT_PERIODS_PER_YEAR
+}
+
+/// Number of independent runs of `sim_config` that fit in a year of `periods_per_year` model time
+/// units: `periods_per_year / (num_steps * dt)`.
+///
+/// The Sharpe ratio of a sweep result is per run, the mean over the standard deviation of a
+/// whole run's PnL. Treating runs as independent, `n` of them back to back add `n` times the mean
+/// and `sqrt(n)` times the standard deviation, so the annual Sharpe is the per-run value times
+/// `sqrt(factor)`. The run length is the simulated `num_steps * dt`, not `Parameters::t_horizon`,
+/// which only sets the strategy's own horizon and need not match it.
+pub fn annualization_factor(sim_config: &SimConfig, periods_per_year: f64) -> f64 {
+    periods_per_year / (sim_config.num_steps as f64 * sim_config.dt)
+}
+
+/// An empty grid of 100 runs per combination over the default `SimConfig`, with every optional
+/// statistic off. The axes must be filled in before the config passes `validate`.
+impl Default for SweepConfig {
+    fn default() -> Self {
+        SweepConfig {
+            gammas: Vec::new(),
+            sigmas: Vec::new(),
+            ks: Vec::new(),
+            drifts: Vec::new(),
+            as_: Vec::new(),
+            t_horizons: Vec::new(),
+            latency_steps: Vec::new(),
+            sim_config: SimConfig::default(),
+            iterations_per_param: 100,
+            antithetic: false,
+            bootstrap_resamples: 0,
+            tail_confidence: DEFAULT_TAIL_CONFIDENCE,
+            sortino_mar: 0.0,
+            keep_profiles: false,
+            seed: None,
+            periods_per_year: DEFAULT_PERIODS_PER_YEAR,
+            annualization_factor: None,
+            baseline: false,
+            histogram_bins: 0,
+        }
+    }
+}
+
+impl SweepConfig {
+    /// Checks what `run_sweep` needs before it starts: valid `base_params` and `sim_config`, a
+    /// fill probability of at most one per step at the opening quotes of `base_params` under
+    /// `intensity_model` (`SimConfig::validate_fill_rate`), no empty `gammas`,
+    /// `sigmas`, `ks`, or `drifts`, `iterations_per_param > 0`, and a `tail_confidence` in
+    /// `[0.5, 1)`, below which the VaR tail would be the upper one.
+    ///
+    /// This covers the base configuration only. Each combination's own parameters are checked
+    /// before it runs, and a swept value outside the model's domain, such as a negative gamma,
+    /// fails just that combination with `SweepErrorKind::InvalidParams` (see `try_run_sweep`).
+    pub fn validate<M: IntensityModel + ?Sized>(
+        &self,
+        base_params: &Parameters,
+        intensity_model: &M,
+    ) -> Result<(), ConfigError> {
+        base_params.validate()?;
+        self.sim_config.validate()?;
+        self.sim_config
+            .validate_fill_rate(base_params, intensity_model)?;
+        for (axis, len) in [
+            ("gammas", self.gammas.len()),
+            ("sigmas", self.sigmas.len()),
+            ("ks", self.ks.len()),
+            ("drifts", self.drifts.len()),
+        ] {
+            if len == 0 {
+                return Err(ConfigError::EmptyAxis(axis));
+            }
+        }
+        if self.iterations_per_param == 0 {
+            return Err(ConfigError::NoIterations);
+        }
+        if !(0.5..1.0).contains(&self.tail_confidence) {
+            return Err(ConfigError::OutOfRange {
+                name: "tail_confidence",
+                value: self.tail_confidence,
+                expected: "in [0.5, 1)",
+            });
+        }
+        Ok(())
+    }
+
+    /// Number of parameter combinations `run_sweep` will evaluate.
+    pub fn num_combinations(&self) -> usize {
+        [
+            self.gammas.len(),
+            self.sigmas.len(),
+            self.ks.len(),
+            self.drifts.len(),
+            self.as_.len().max(1),
+            self.t_horizons.len().max(1),
+            self.latency_steps.len().max(1),
+        ]
+        .iter()
+        .product()
+    }
+}
+
+/// One dimension of a generic sweep: the parameter to vary and the values it takes.
+///
+/// With the `serde` feature an axis is written as `{"gamma": [0.01, 0.1]}`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SweepAxis {
+    Gamma(Vec<f64>),
+    Sigma(Vec<f64>),
+    K(Vec<f64>),
+    A(Vec<f64>),
+    THorizon(Vec<f64>),
+    Drift(Vec<f64>),
+    LatencySteps(Vec<usize>),
+    /// Fixed size of every fill, replacing the configured `size_distribution`.
+    OrderSize(Vec<u32>),
+    AdverseSelection(Vec<f64>),
+}
+
+impl SweepAxis {
+    /// Name under which the axis is recorded in a `ParamPoint`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SweepAxis::Gamma(_) => "gamma",
+            SweepAxis::Sigma(_) => "sigma",
+            SweepAxis::K(_) => "k",
+            SweepAxis::A(_) => "a",
+            SweepAxis::THorizon(_) => "t_horizon",
+            SweepAxis::Drift(_) => "drift",
+            SweepAxis::LatencySteps(_) => "latency_steps",
+            SweepAxis::OrderSize(_) => "order_size",
+            SweepAxis::AdverseSelection(_) => "adverse_selection",
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            SweepAxis::Gamma(v)
+            | SweepAxis::Sigma(v)
+            | SweepAxis::K(v)
+            | SweepAxis::A(v)
+            | SweepAxis::THorizon(v)
+            | SweepAxis::Drift(v)
+            | SweepAxis::AdverseSelection(v) => v.len(),
+            SweepAxis::LatencySteps(v) => v.len(),
+            SweepAxis::OrderSize(v) => v.len(),
+        }
+    }
+
+    /// Sets the axis to its `i`-th value and returns that value as recorded in a `ParamPoint`.
+    fn apply(
+        &self,
+        i: usize,
+        params: &mut Parameters,
+        sim_config: &mut SimConfig,
+    ) -> Result<f64, SweepErrorKind> {
+        let value = match self {
+            SweepAxis::Gamma(v) => {
+                params.gamma = v[i];
+                v[i]
+            }
+            SweepAxis::Sigma(v) => {
+                params.sigma = v[i];
+                v[i]
+            }
+            SweepAxis::K(v) => {
+                params.k = v[i];
+                v[i]
+            }
+            SweepAxis::A(v) => {
+                params.a = v[i];
+                v[i]
+            }
+            SweepAxis::THorizon(v) => {
+                params.t_horizon = v[i];
+                v[i]
+            }
+            SweepAxis::Drift(v) => {
+                sim_config.drift = v[i];
+                v[i]
+            }
+            SweepAxis::LatencySteps(v) => {
+                sim_config.latency_steps = v[i];
+                v[i] as f64
+            }
+            SweepAxis::OrderSize(v) => {
+                let table = EmpiricalSizeDistribution::from_table(&[(v[i], 1.0)]).map_err(|e| {
+                    SweepErrorKind::InvalidAxis(format!("order_size {}: {e}", v[i]))
+                })?;
+                sim_config.size_distribution = Some(Arc::new(table));
+                v[i] as f64
+            }
+            SweepAxis::AdverseSelection(v) => {
+                sim_config.adverse_selection = v[i];
+                v[i]
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// Coordinates of one generic sweep point, as `(axis name, value)` pairs in axis order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParamPoint {
+    pub coords: Vec<(&'static str, f64)>,
+}
+
+impl ParamPoint {
+    /// Value of the axis called `name`, if this point has one.
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.coords
+            .iter()
+            .find(|(axis, _)| *axis == name)
+            .map(|&(_, value)| value)
+    }
+}
+
+/// A complete, self-describing sweep: base parameters, grid, and market intensity model.
+///
+/// This is the unit a config file describes, e.g. in JSON:
+/// `{"base_params": {...}, "sweep": {...}, "intensity": {"type": "exponential", "a": 140, "k": 1.5}}`.
+/// Unknown keys are rejected, so a misspelt setting is an error rather than silently left at its
+/// default.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct SweepSpec {
+    pub base_params: Parameters,
+    pub sweep: SweepConfig,
+    pub intensity: IntensitySpec,
+}
+
+impl SweepSpec {
+    pub fn run(&self) -> Result<Vec<SweepResult>, ConfigError> {
+        run_sweep(self.base_params, &self.sweep, &self.intensity)
+    }
+
+    /// Parses a TOML config, see `examples/sweep.toml`, and validates its base parameters and
+    /// simulation settings.
+    #[cfg(feature = "serde")]
+    pub fn from_toml(text: &str) -> Result<Self, SpecError> {
+        let spec: SweepSpec =
+            toml::from_str(text).map_err(|err| SpecError::Parse(err.to_string()))?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Checks `base_params`, `sweep.sim_config`, and `intensity`, then the rest of the sweep
+    /// with `SweepConfig::validate`; the swept values are checked per point as the sweep runs.
+    pub fn validate(&self) -> Result<(), SpecError> {
+        self.base_params.validate().map_err(SpecError::Params)?;
+        self.sweep.sim_config.validate().map_err(SpecError::Sim)?;
+        self.intensity.validate().map_err(SpecError::Intensity)?;
+        self.sweep
+            .validate(&self.base_params, &self.intensity)
+            .map_err(SpecError::Sweep)
+    }
+}
+
+/// Error returned when a `SweepSpec` config cannot be used.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecError {
+    /// The text is not a valid spec; the message names the offending key and its location.
+    Parse(String),
+    /// `base_params` is outside the model's domain.
+    Params(ParamError),
+    /// `sweep.sim_config` is invalid.
+    Sim(SimError),
+    /// `intensity` is outside its model's domain.
+    Intensity(ParamError),
+    /// The rest of `sweep` is rejected by `SweepConfig::validate`.
+    Sweep(ConfigError),
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecError::Parse(message) => write!(f, "invalid config: {message}"),
+            SpecError::Params(err) => write!(f, "invalid `base_params`: {err}"),
+            SpecError::Sim(err) => write!(f, "invalid `sweep.sim_config`: {err}"),
+            SpecError::Intensity(err) => write!(f, "invalid `intensity`: {err}"),
+            SpecError::Sweep(err) => write!(f, "invalid `sweep`: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+/// Inclusive `[min, max]` bounds for each dimension of a random parameter search.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchRanges {
+    pub gamma: (f64, f64),
+    pub sigma: (f64, f64),
+    pub k: (f64, f64),
+    pub drift: (f64, f64),
+    /// Sample `gamma` uniformly in log space, giving each decade equal weight. Needs positive bounds.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub log_gamma: bool,
+    /// Sample `k` uniformly in log space. Needs positive bounds.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub log_k: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sampling: SearchSampling,
+    /// Seeds where the points are placed; `None` uses the thread RNG. The Monte Carlo runs at each
+    /// point are not seeded by it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: Option<u64>,
+    pub sim_config: SimConfig,
+    pub iterations_per_param: usize,
+}
+
+/// How `run_random_search` places its points in the unit hypercube before scaling to the ranges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SearchSampling {
+    /// Exactly one point in each of the `n_samples` strata along every axis.
+    #[default]
+    LatinHypercube,
+    /// Independent uniform draws.
+    Uniform,
+    /// The low-discrepancy Halton sequence in bases 2, 3, 5 and 7, from index 1. It is
+    /// deterministic, so `SearchRanges::seed` has no effect.
+    Halton,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SweepResult {
+    /// Name of the intensity model the result was simulated under in `run_sweep_models`; empty
+    /// for the single-model sweeps.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub model_name: String,
     pub gamma: f64,
     pub sigma: f64,
     pub k: f64,
     pub drift: f64,
+    pub a: f64,
+    pub t_horizon: f64,
+    pub latency_steps: usize,
     pub mean_pnl: f64,
     pub std_pnl: f64,
+    pub pnl_sem: f64, // Standard error of mean_pnl: std_pnl / sqrt(iterations)
+    /// `mean_pnl` adjusted by the spread-capture control variate (see `control_variate_mean`), and
+    /// its standard error.
+    pub cv_mean_pnl: f64,
+    pub cv_pnl_sem: f64,
+    pub median_pnl: f64,
+    pub iqr_pnl: f64, // 75th minus 25th percentile of PnL, see `percentile`
+    /// Bootstrap 95% interval for `mean_pnl`; NaN unless `SweepConfig::bootstrap_resamples > 0`.
+    #[cfg_attr(feature = "serde", serde(with = "crate::sim::nan_as_null"))]
+    pub mean_pnl_ci_low: f64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::sim::nan_as_null"))]
+    pub mean_pnl_ci_high: f64,
     pub sharpe_ratio: f64,
-    pub max_drawdown: f64,
+    /// `sharpe_ratio * sqrt(factor)`, with the factor of `SweepConfig::annualization_factor` or
+    /// else from the free function `annualization_factor`, whose docs spell out the convention.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sharpe_annualized: f64,
+    /// Bootstrap 95% interval for `sharpe_ratio`, likewise.
+    #[cfg_attr(feature = "serde", serde(with = "crate::sim::nan_as_null"))]
+    pub sharpe_ci_low: f64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::sim::nan_as_null"))]
+    pub sharpe_ci_high: f64,
+    /// PnL percentiles at `1 - tail_confidence` and `tail_confidence` (5% and 95% by default).
+    pub pnl_p05: f64,
+    pub pnl_p95: f64,
+    pub var_95: f64,  // Value at risk: the loss `-pnl_p05`
+    pub cvar_95: f64, // Expected shortfall: the mean loss over runs at or below `pnl_p05`
+    /// Excess of mean PnL over `SweepConfig::sortino_mar`, per unit of downside deviation (see
+    /// `stats::sortino_ratio`). Infinite when no run falls below the MAR, which JSON writes as
+    /// null.
+    #[cfg_attr(feature = "serde", serde(with = "crate::sim::nan_as_null"))]
+    pub sortino_ratio: f64,
+    /// `mean_pnl / max_drawdown`, zero when no run ever draws down (see `stats::calmar_ratio`).
+    #[cfg_attr(feature = "serde", serde(with = "crate::sim::nan_as_null"))]
+    pub calmar_ratio: f64,
+    pub max_drawdown: f64, // Mean over runs of the largest peak-to-trough fall in wealth
     pub mean_abs_inventory: f64,
+    pub time_weighted_abs_inventory: f64, // Integral of |q(t)| dt over the horizon, per unit time
+    /// Mean over runs of `inventory_variance`: how much inventory churns within a run, which
+    /// `mean_abs_inventory` cannot tell apart from holding a steady position.
+    pub mean_inventory_variance: f64,
+    /// Mean over runs of `inventory_autocorr_lag1`: near zero when inventory mean-reverts quickly,
+    /// near one when offside positions stick.
+    pub mean_inventory_autocorr_lag1: f64,
     pub max_inventory: f64,
+    /// Mean and standard deviation across runs of `SimResult::final_inventory`, the position held
+    /// after the last step's fills and any kill-switch flattening.
     pub terminal_inventory_mean: f64,
     pub terminal_inventory_std: f64,
+    pub mean_running_penalty: f64,
+    pub mean_objective: f64, // mean_pnl - mean_running_penalty
+    /// Mean `SimResult::carry_paid` per run, already included in the PnL figures.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mean_carry_paid: f64,
+    pub quote_staleness_mean: f64,
+    pub quote_staleness_exceed_frac: f64,
+    pub stressed_fraction_mean: f64, // Mean share of steps in the stressed regime
+    pub mean_calm_pnl: f64,          // Mean PnL earned during calm steps
+    pub mean_stressed_pnl: f64,      // Mean PnL earned during stressed steps
+    pub mean_spread_pnl: f64,        // Mean edge captured against the mid at fill time
+    pub mean_inventory_pnl: f64,     // Mean mark-to-market PnL of held inventory
+    pub mean_liquidity: f64,         // Mean market `k` multiplier
+    pub pnl_liquidity_corr: f64,     // Correlation of PnL with a run's mean `k` multiplier
+    pub stopped_frac: f64,           // Fraction of runs halted by the drawdown kill-switch
+    pub mean_fills_per_run: f64,     // Mean number of bid and ask fills per trajectory
+    pub mean_traded_notional: f64,   // Mean `SimResult::traded_notional` per trajectory
+    /// `SimResult::mean_spread_capture` pooled over every fill of every run, so runs without
+    /// fills do not count. Zero when no run filled.
+    pub mean_spread_capture: f64,
+    /// Spread PnL per fill, pooled over all runs: the average edge captured against the mid.
+    /// Zero when no run filled.
+    pub mean_realized_half_spread: f64,
+    /// PnL mean and standard deviation of the `SweepConfig::baseline` fixed-spread strategy on the
+    /// same paths; NaN unless it is enabled.
+    #[cfg_attr(feature = "serde", serde(default, with = "crate::sim::nan_as_null"))]
+    pub baseline_mean_pnl: f64,
+    #[cfg_attr(feature = "serde", serde(default, with = "crate::sim::nan_as_null"))]
+    pub baseline_std_pnl: f64,
+    /// `mean_pnl - baseline_mean_pnl`: what the quoting model earns over quoting its average
+    /// spread symmetrically. NaN without the baseline.
+    #[cfg_attr(feature = "serde", serde(default, with = "crate::sim::nan_as_null"))]
+    pub pnl_uplift: f64,
+    /// Cross-run `trajectory_profile`, present only with `SweepConfig::keep_profiles`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub profile: Option<Profile>,
+    /// `pnl_histogram` of the runs' final PnLs, present only with `SweepConfig::histogram_bins`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub pnl_histogram: Option<Histogram>,
 }
 
-fn calculate_sharpe(pnls: &[f64]) -> f64 {
-    let n = pnls.len() as f64;
-    if n < 2.0 {
+/// A scalar `SweepResult` field, or a score derived from one, that results can be ranked by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SweepMetric {
+    MeanPnl,
+    MedianPnl,
+    StdPnl,
+    Var95,
+    Cvar95,
+    SharpeRatio,
+    MaxDrawdown,
+    MeanAbsInventory,
+    MaxInventory,
+    TerminalInventoryStd,
+    MeanObjective,
+    QuoteStalenessMean,
+    MeanStressedPnl,
+    /// `inventory_penalized_score` with the given `inventory_penalty`.
+    InventoryPenalized {
+        inventory_penalty: f64,
+    },
+}
+
+impl SweepMetric {
+    /// Name of the `SweepResult` field the metric reads.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SweepMetric::MeanPnl => "mean_pnl",
+            SweepMetric::MedianPnl => "median_pnl",
+            SweepMetric::StdPnl => "std_pnl",
+            SweepMetric::Var95 => "var_95",
+            SweepMetric::Cvar95 => "cvar_95",
+            SweepMetric::SharpeRatio => "sharpe_ratio",
+            SweepMetric::MaxDrawdown => "max_drawdown",
+            SweepMetric::MeanAbsInventory => "mean_abs_inventory",
+            SweepMetric::MaxInventory => "max_inventory",
+            SweepMetric::TerminalInventoryStd => "terminal_inventory_std",
+            SweepMetric::MeanObjective => "mean_objective",
+            SweepMetric::QuoteStalenessMean => "quote_staleness_mean",
+            SweepMetric::MeanStressedPnl => "mean_stressed_pnl",
+            SweepMetric::InventoryPenalized { .. } => "inventory_penalized_score",
+        }
+    }
+
+    pub fn value(&self, result: &SweepResult) -> f64 {
+        match self {
+            SweepMetric::MeanPnl => result.mean_pnl,
+            SweepMetric::MedianPnl => result.median_pnl,
+            SweepMetric::StdPnl => result.std_pnl,
+            SweepMetric::Var95 => result.var_95,
+            SweepMetric::Cvar95 => result.cvar_95,
+            SweepMetric::SharpeRatio => result.sharpe_ratio,
+            SweepMetric::MaxDrawdown => result.max_drawdown,
+            SweepMetric::MeanAbsInventory => result.mean_abs_inventory,
+            SweepMetric::MaxInventory => result.max_inventory,
+            SweepMetric::TerminalInventoryStd => result.terminal_inventory_std,
+            SweepMetric::MeanObjective => result.mean_objective,
+            SweepMetric::QuoteStalenessMean => result.quote_staleness_mean,
+            SweepMetric::MeanStressedPnl => result.mean_stressed_pnl,
+            SweepMetric::InventoryPenalized { inventory_penalty } => {
+                inventory_penalized_score(result, *inventory_penalty)
+            }
+        }
+    }
+}
+
+/// Mean PnL less `inventory_penalty` times the variance of terminal inventory across runs:
+/// `mean_pnl - inventory_penalty * terminal_inventory_std^2`.
+///
+/// This stands in for the mean-variance utility the model's quotes are derived from,
+/// `E[PnL] - gamma / 2 * Var[wealth]`. Inventory left at the horizon is the part of the wealth risk
+/// a maker controls, so ranking by it (`SweepMetric::InventoryPenalized`, or
+/// `optimize::Objective::InventoryPenalized` for the optimiser) favours parameters that end flat
+/// over ones that earn slightly more by carrying a position. A penalty of zero is `mean_pnl`.
+pub fn inventory_penalized_score(result: &SweepResult, inventory_penalty: f64) -> f64 {
+    result.mean_pnl - inventory_penalty * result.terminal_inventory_std.powi(2)
+}
+
+/// `inventory_penalized_score` under the name it was introduced with.
+pub fn objective(result: &SweepResult, inventory_penalty: f64) -> f64 {
+    inventory_penalized_score(result, inventory_penalty)
+}
+
+/// Returns the `n` best results by `metric`, highest first if `maximize`, lowest first otherwise.
+///
+/// Results whose metric is NaN always rank after every finite value, in either direction.
+pub fn top_n(
+    results: &[SweepResult],
+    n: usize,
+    metric: SweepMetric,
+    maximize: bool,
+) -> Vec<SweepResult> {
+    let mut ranked: Vec<&SweepResult> = results.iter().collect();
+    ranked.sort_by(|a, b| {
+        let (x, y) = (metric.value(a), metric.value(b));
+        match (x.is_nan(), y.is_nan()) {
+            (true, true) => std::cmp::Ordering::Equal,
+            (true, false) => std::cmp::Ordering::Greater,
+            (false, true) => std::cmp::Ordering::Less,
+            (false, false) if maximize => y.total_cmp(&x),
+            (false, false) => x.total_cmp(&y),
+        }
+    });
+    ranked.into_iter().take(n).cloned().collect()
+}
+
+/// Returns the results not dominated in (lower `risk`, higher `ret`), in input order; see
+/// `sort_frontier_by_risk` to order them for plotting.
+///
+/// A result is dominated if another is at least as good on both axes and strictly better on one.
+/// Exact duplicates therefore do not knock each other out, while a result that merely ties on one
+/// axis and loses on the other is removed. Results with a NaN in either metric cannot be compared
+/// and are left out; `unranked_count` counts them.
+pub fn pareto_frontier(
+    results: &[SweepResult],
+    risk: SweepMetric,
+    ret: SweepMetric,
+) -> Vec<&SweepResult> {
+    let mut ranked: Vec<(f64, f64, usize)> = results
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (risk.value(r), ret.value(r), i))
+        .filter(|(x, y, _)| !x.is_nan() && !y.is_nan())
+        .collect();
+    // Least risk first, and the best return first among equal risks.
+    ranked.sort_by(|a, b| a.0.total_cmp(&b.0).then(b.1.total_cmp(&a.1)));
+
+    // A result survives if it beats the return of everything less risky, or exactly ties the
+    // last survivor.
+    let mut on_frontier = vec![false; results.len()];
+    let mut last: Option<(f64, f64)> = None;
+    for (x, y, i) in ranked {
+        if last.is_none_or(|(lx, ly)| y > ly || (y == ly && x == lx)) {
+            on_frontier[i] = true;
+            last = Some((x, y));
+        }
+    }
+
+    results
+        .iter()
+        .zip(on_frontier)
+        .filter_map(|(r, keep)| keep.then_some(r))
+        .collect()
+}
+
+/// Number of `results` that `pareto_frontier` leaves out because `risk` or `ret` is NaN.
+pub fn unranked_count(results: &[SweepResult], risk: SweepMetric, ret: SweepMetric) -> usize {
+    results
+        .iter()
+        .filter(|r| risk.value(r).is_nan() || ret.value(r).is_nan())
+        .count()
+}
+
+/// Sorts a frontier by ascending `risk`, which along the frontier also orders the returns.
+pub fn sort_frontier_by_risk(frontier: &mut [&SweepResult], risk: SweepMetric) {
+    frontier.sort_by(|a, b| risk.value(a).total_cmp(&risk.value(b)));
+}
+
+/// Why a single sweep combination produced no result.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SweepErrorKind {
+    /// `iterations_per_param` was zero, so there is nothing to average.
+    NoIterations,
+    /// `num_steps` was zero, so trajectories have no inventory path.
+    EmptyTrajectory,
+    /// The Monte Carlo panicked; the payload message is kept for diagnosis.
+    Panicked(String),
+    /// A `SweepAxis` value could not be applied to the configuration.
+    InvalidAxis(String),
+    /// The swept values put the strategy parameters outside the model's domain.
+    InvalidParams(ParamError),
+}
+
+/// A failed sweep combination together with the coordinates that identify it.
+///
+/// `point` holds every parameter a sweep can move, `gamma`, `sigma`, `k`, `a`, `t_horizon`,
+/// `drift`, and `latency_steps`, followed by any other axis of a `run_sweep_axes` point, so two
+/// combinations that differ only off the main grid still tell apart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepError {
+    pub point: ParamPoint,
+    pub kind: SweepErrorKind,
+}
+
+impl fmt::Display for SweepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "combination ")?;
+        for (i, (name, value)) in self.point.coords.iter().enumerate() {
+            let separator = if i == 0 { "" } else { ", " };
+            write!(f, "{separator}{name}={value}")?;
+        }
+        write!(f, " failed: ")?;
+        match &self.kind {
+            SweepErrorKind::NoIterations => write!(f, "iterations_per_param is zero"),
+            SweepErrorKind::EmptyTrajectory => write!(f, "num_steps is zero"),
+            SweepErrorKind::Panicked(message) => write!(f, "panicked: {message}"),
+            SweepErrorKind::InvalidAxis(message) => write!(f, "invalid axis value {message}"),
+            SweepErrorKind::InvalidParams(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for SweepError {}
+
+impl SweepError {
+    fn new(params: &Parameters, sim_config: &SimConfig, kind: SweepErrorKind) -> Self {
+        let coords = vec![
+            ("gamma", params.gamma),
+            ("sigma", params.sigma),
+            ("k", params.k),
+            ("a", params.a),
+            ("t_horizon", params.t_horizon),
+            ("drift", sim_config.drift),
+            ("latency_steps", sim_config.latency_steps as f64),
+        ];
+        Self {
+            point: ParamPoint { coords },
+            kind,
+        }
+    }
+
+    /// Adds the coordinates of `point` that the error does not already carry.
+    fn with_point(mut self, point: &ParamPoint) -> Self {
+        for &(name, value) in &point.coords {
+            if self.point.get(name).is_none() {
+                self.point.coords.push((name, value));
+            }
+        }
+        self
+    }
+}
+
+/// Per-combination results of `try_run_sweep`, in Cartesian-product order.
+#[derive(Debug, Clone)]
+pub struct SweepOutcome {
+    pub rows: Vec<Result<SweepResult, SweepError>>,
+}
+
+impl SweepOutcome {
+    pub fn results(&self) -> impl Iterator<Item = &SweepResult> {
+        self.rows.iter().filter_map(|row| row.as_ref().ok())
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &SweepError> {
+        self.rows.iter().filter_map(|row| row.as_ref().err())
+    }
+
+    /// Drops the failed combinations and keeps the successful results.
+    pub fn into_results(self) -> Vec<SweepResult> {
+        self.rows.into_iter().filter_map(Result::ok).collect()
+    }
+}
+
+/// Time average of `|q(t)|` over `[trajectory[0].time, end_time)`.
+///
+/// Each record's inventory is held until the next record's `time` (the last one until `end_time`),
+/// so steps of different lengths are weighted by how long the inventory was carried.
+pub fn time_weighted_abs_inventory(trajectory: &[StepRecord], end_time: f64) -> f64 {
+    let Some(first) = trajectory.first() else {
+        return 0.0;
+    };
+    let span = end_time - first.time;
+    if span <= 0.0 {
+        return 0.0;
+    }
+
+    let ends = trajectory.iter().skip(1).map(|s| s.time).chain([end_time]);
+    trajectory
+        .iter()
+        .zip(ends)
+        .map(|(step, end)| step.inventory.abs() * (end - step.time))
+        .sum::<f64>()
+        / span
+}
+
+/// Sample variance of `q(t)` across the steps of one trajectory; zero with fewer than two steps.
+///
+/// A run that swings between long and short has a high variance even when its mean `|q|` matches
+/// one that sits on a constant position.
+pub fn inventory_variance(trajectory: &[StepRecord]) -> f64 {
+    let mut acc = Welford::default();
+    for step in trajectory {
+        acc.push(step.inventory);
+    }
+    acc.sample_variance()
+}
+
+/// Lag-1 autocorrelation of `q(t)` across the steps of one trajectory.
+///
+/// Uses the standard estimator, the lagged cross products of deviations from the path's mean over
+/// the sum of squared deviations. Zero when inventory never changes or the path has fewer than two
+/// steps, as for the other ratios with a zero denominator.
+pub fn inventory_autocorr_lag1(trajectory: &[StepRecord]) -> f64 {
+    if trajectory.len() < 2 {
         return 0.0;
     }
-    let mean = pnls.iter().sum::<f64>() / n;
-    let variance = pnls.iter().map(|&x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
-    let std_dev = variance.sqrt();
+    let q_mean = trajectory.iter().map(|s| s.inventory).sum::<f64>() / trajectory.len() as f64;
+    let dev = |s: &StepRecord| s.inventory - q_mean;
+    let denom: f64 = trajectory.iter().map(|s| dev(s).powi(2)).sum();
+    if denom == 0.0 {
+        return 0.0;
+    }
+    trajectory
+        .windows(2)
+        .map(|w| dev(&w[0]) * dev(&w[1]))
+        .sum::<f64>()
+        / denom
+}
+
+/// Percentile bootstrap 95% confidence interval for `statistic` evaluated on `samples`.
+///
+/// Draws `b` resamples of `samples` with replacement from an RNG seeded with `seed`, evaluates
+/// `statistic` on each, and returns the 2.5th and 97.5th percentiles of those values (see
+/// `percentile`). Both bounds are NaN when `samples` is empty or `b` is zero.
+pub fn bootstrap_ci<F: Fn(&[f64]) -> f64>(
+    samples: &[f64],
+    statistic: F,
+    b: usize,
+    seed: u64,
+) -> (f64, f64) {
+    block_bootstrap_ci(samples, 1, statistic, b, seed)
+}
+
+/// `bootstrap_ci` resampling consecutive blocks of `block` samples as units, for samples that are
+/// only independent between blocks. The last block may be shorter.
+fn block_bootstrap_ci<F: Fn(&[f64]) -> f64>(
+    samples: &[f64],
+    block: usize,
+    statistic: F,
+    b: usize,
+    seed: u64,
+) -> (f64, f64) {
+    if samples.is_empty() || b == 0 {
+        return (f64::NAN, f64::NAN);
+    }
+
+    let blocks: Vec<&[f64]> = samples.chunks(block).collect();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut resample = Vec::with_capacity(samples.len());
+    let mut values: Vec<f64> = (0..b)
+        .map(|_| {
+            resample.clear();
+            for _ in 0..blocks.len() {
+                resample.extend_from_slice(blocks[rng.random_range(0..blocks.len())]);
+            }
+            statistic(&resample)
+        })
+        .collect();
+    values.sort_by(f64::total_cmp);
+    (percentile(&values, 0.025), percentile(&values, 0.975))
+}
 
-    if std_dev == 0.0 { 0.0 } else { mean / std_dev }
+/// Tail statistics of a PnL sample at one confidence level.
+struct TailRisk {
+    lower: f64,
+    upper: f64,
+    var: f64,
+    cvar: f64,
 }
 
-pub fn run_sweep(
+impl TailRisk {
+    /// Computes the statistics from ascending `sorted` PnLs at `confidence` in `[0, 1]`.
+    ///
+    /// The percentiles interpolate as in `percentile`. The shortfall averages every run at or
+    /// below the lower percentile, which always includes the worst run, so even a handful of runs
+    /// gives a finite value (with one run, all four statistics come from it). All zero when empty.
+    fn from_sorted(sorted: &[f64], confidence: f64) -> Self {
+        let lower = percentile(sorted, 1.0 - confidence);
+        let tail: Vec<f64> = sorted.iter().copied().take_while(|&x| x <= lower).collect();
+        let cvar = if tail.is_empty() {
+            0.0
+        } else {
+            -tail.iter().sum::<f64>() / tail.len() as f64
+        };
+        TailRisk {
+            lower,
+            upper: percentile(sorted, confidence),
+            var: -lower,
+            cvar,
+        }
+    }
+}
+
+/// Runs the Cartesian sweep, omitting any combination that fails.
+///
+/// The inputs are checked up front with `SweepConfig::validate`, so a config that could only
+/// produce an empty or meaningless grid is an error rather than an empty result. Use
+/// `try_run_sweep` to see which combinations failed and why.
+pub fn run_sweep<M: IntensityModel + ?Sized>(
     base_params: Parameters,
     sweep_config: &SweepConfig,
-    intensity_model: &dyn IntensityModel,
-) -> Vec<SweepResult> {
-    // Generate all combinations of parameters
-    let combinations: Vec<_> = itertools::iproduct!(
-        &sweep_config.gammas,
-        &sweep_config.sigmas,
-        &sweep_config.ks,
-        &sweep_config.drifts
+    intensity_model: &M,
+) -> Result<Vec<SweepResult>, ConfigError> {
+    sweep_config.validate(&base_params, intensity_model)?;
+    Ok(try_run_sweep(base_params, sweep_config, intensity_model).into_results())
+}
+
+/// Runs the same Cartesian sweep under each of the named intensity `models`, omitting any
+/// combination that fails, and tags every result with the `model_name` it ran under.
+///
+/// Results come grouped by model, in the order of `models`, and in `run_sweep` order within each
+/// group. With `SweepConfig::seed` set, a combination gets the same seeds under every model, so
+/// the models are compared on identical price paths and fill draws. Every model is validated
+/// before any of them runs.
+pub fn run_sweep_models(
+    base_params: Parameters,
+    sweep_config: &SweepConfig,
+    models: &[(&str, &dyn IntensityModel)],
+) -> Result<Vec<SweepResult>, ConfigError> {
+    for &(_, model) in models {
+        sweep_config.validate(&base_params, model)?;
+    }
+    Ok(models
+        .iter()
+        .flat_map(|&(name, model)| {
+            try_run_sweep(base_params, sweep_config, model)
+                .into_results()
+                .into_iter()
+                .map(move |result| SweepResult {
+                    model_name: name.to_string(),
+                    ..result
+                })
+        })
+        .collect())
+}
+
+/// Like `try_run_sweep`, but calls `on_progress(completed, total)` after each combination
+/// finishes.
+///
+/// The inputs are checked up front with `SweepConfig::validate`, as in `run_sweep`.
+///
+/// Combinations run on rayon worker threads, so the callback may be invoked concurrently and
+/// `completed` values can arrive slightly out of order. Each value from 1 to `total` is reported
+/// exactly once.
+pub fn run_sweep_with_progress<M, F>(
+    base_params: Parameters,
+    sweep_config: &SweepConfig,
+    intensity_model: &M,
+    on_progress: F,
+) -> Result<SweepOutcome, ConfigError>
+where
+    M: IntensityModel + ?Sized,
+    F: Fn(usize, usize) + Send + Sync,
+{
+    sweep_config.validate(&base_params, intensity_model)?;
+    Ok(sweep_grid(
+        base_params,
+        sweep_config,
+        intensity_model,
+        &|_, _| None,
+        &|_, done, total| on_progress(done, total),
+        &AtomicBool::new(false),
+    ))
+}
+
+/// Runs the Cartesian sweep and sends each successful result on `sender` as soon as its
+/// combination finishes, returning the failures.
+///
+/// Results arrive in completion order, not the Cartesian-product order of `run_sweep`; each
+/// `SweepResult` carries its own coordinates, so the order does not matter for consumers that
+/// key by them. If the receiver is dropped early, the sweep still runs to completion and the
+/// remaining results are discarded. A config rejected by `SweepConfig::validate` sends nothing.
+pub fn run_sweep_streaming<M: IntensityModel + ?Sized>(
+    base_params: Parameters,
+    sweep_config: &SweepConfig,
+    intensity_model: &M,
+    sender: Sender<SweepResult>,
+) -> Result<Vec<SweepError>, ConfigError> {
+    sweep_config.validate(&base_params, intensity_model)?;
+    let outcome = sweep_grid(
+        base_params,
+        sweep_config,
+        intensity_model,
+        &|_, _| None,
+        &|row, _, _| {
+            if let Ok(result) = row {
+                // A disconnected receiver only means nobody is listening any more.
+                let _ = sender.send(result.clone());
+            }
+        },
+        &AtomicBool::new(false),
+    );
+    Ok(outcome.errors().cloned().collect())
+}
+
+/// Outcome of `run_sweep_until`.
+#[derive(Debug, Clone, Default)]
+pub struct EarlyStop {
+    /// First result in `run_sweep` order among `results` that satisfies the predicate; `None`
+    /// if no combination did, in which case the whole grid was evaluated.
+    pub hit: Option<SweepResult>,
+    /// Every combination that completed, successfully and in `run_sweep` order, including `hit`.
+    pub results: Vec<SweepResult>,
+}
+
+/// Runs the Cartesian sweep until a result satisfies `predicate`, then abandons the combinations
+/// not yet started.
+///
+/// Workers check a shared cancellation flag before each combination, so the ones already in
+/// flight when the flag is raised still finish and are returned; more than one may satisfy
+/// `predicate`, and which combinations completed depends on how rayon scheduled them.
+/// `predicate` may be called more than once per result. The inputs are checked up front with
+/// `SweepConfig::validate`, as in `run_sweep`.
+pub fn run_sweep_until<M, P>(
+    base_params: Parameters,
+    sweep_config: &SweepConfig,
+    intensity_model: &M,
+    predicate: P,
+) -> Result<EarlyStop, ConfigError>
+where
+    M: IntensityModel + ?Sized,
+    P: Fn(&SweepResult) -> bool + Sync,
+{
+    sweep_config.validate(&base_params, intensity_model)?;
+    let stop = AtomicBool::new(false);
+    let results = sweep_grid(
+        base_params,
+        sweep_config,
+        intensity_model,
+        &|_, _| None,
+        &|row, _, _| {
+            if matches!(row, Ok(result) if predicate(result)) {
+                stop.store(true, Ordering::Relaxed);
+            }
+        },
+        &stop,
+    )
+    .into_results();
+    Ok(EarlyStop {
+        hit: results.iter().find(|r| predicate(r)).cloned(),
+        results,
+    })
+}
+
+/// Runs the Cartesian sweep, returning one `Result` per combination.
+///
+/// Invalid inputs and panics are contained at the combination boundary, so one failing
+/// combination never discards the work completed by the others.
+pub fn try_run_sweep<M: IntensityModel + ?Sized>(
+    base_params: Parameters,
+    sweep_config: &SweepConfig,
+    intensity_model: &M,
+) -> SweepOutcome {
+    sweep_grid(
+        base_params,
+        sweep_config,
+        intensity_model,
+        &|_, _| None,
+        &|_, _, _| {},
+        &AtomicBool::new(false),
+    )
+}
+
+/// Called from worker threads with each finished combination and the `(completed, total)` count.
+type CompletionHook<'a> = dyn Fn(&Result<SweepResult, SweepError>, usize, usize) + Sync + 'a;
+
+/// Looks up an already known result for a combination, which is then used as is: it is neither
+/// evaluated nor passed to the `CompletionHook`, though it still counts toward `total`.
+type ResultCache<'a> = dyn Fn(&Parameters, &SimConfig) -> Option<SweepResult> + Sync + 'a;
+
+/// Runs the Cartesian sweep, appending each successful result to the checkpoint file at
+/// `checkpoint_path` as it finishes and skipping every combination the file already holds.
+///
+/// Rerunning an interrupted sweep with the same path therefore only computes what is missing,
+/// and returns the recorded and new results together in `try_run_sweep` order. Records are
+/// matched to combinations by the grid coordinates stored in each `SweepResult`. Failed
+/// combinations are returned as errors but not recorded, so a resume retries them.
+///
+/// The file opens with the sweep's settings other than its axes (`sim_config`, the iteration
+/// count, the seed, and so on). A resume with different settings fails with
+/// `ErrorKind::InvalidInput` rather than mixing incomparable results, while adding values to an
+/// axis is allowed. With a seed, a grown grid numbers its combinations differently (see
+/// `derive_seed`), so the new cells are not the ones a single run of the larger grid would give.
+/// The quote signal is not serialisable and is not compared. A config rejected by
+/// `SweepConfig::validate` also fails with `ErrorKind::InvalidInput`, wrapping the `ConfigError`,
+/// before the checkpoint file is touched.
+///
+/// Each record is one line, `<length> <json>`, where the length counts the bytes of the JSON. A
+/// record cut short by a crash fails that check, so it and anything after it are dropped from the
+/// file before the sweep appends to it again; a complete record that no longer parses as a
+/// `SweepResult` is skipped and its combination recomputed.
+#[cfg(feature = "serde")]
+pub fn run_sweep_with_checkpoint<M: IntensityModel + ?Sized>(
+    base_params: Parameters,
+    sweep_config: &SweepConfig,
+    intensity_model: &M,
+    checkpoint_path: &std::path::Path,
+) -> std::io::Result<SweepOutcome> {
+    use std::collections::HashMap;
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+    use std::sync::{Mutex, PoisonError};
+
+    sweep_config
+        .validate(&base_params, intensity_model)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(checkpoint_path)?;
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents)?;
+    let (settings, records, valid_len) = read_checkpoint(&contents);
+    // In append mode every write lands at the (new) end of the file.
+    file.set_len(valid_len as u64)?;
+
+    let expected = checkpoint_settings(sweep_config)?;
+    match settings {
+        Some(settings) if settings == expected => {}
+        Some(_) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "checkpoint {} was written by a sweep with different settings",
+                    checkpoint_path.display()
+                ),
+            ));
+        }
+        None => {
+            // Nothing usable was recorded, so the file starts afresh with the settings.
+            file.set_len(0)?;
+            let json = serde_json::to_string(&CheckpointHeader { settings: expected })?;
+            file.write_all(format!("{} {json}\n", json.len()).as_bytes())?;
+        }
+    }
+
+    let done: HashMap<GridKey, SweepResult> = records
+        .into_iter()
+        .map(|result| (result_key(&result), result))
+        .collect();
+    let file = Mutex::new(file);
+    let write_error = Mutex::new(None);
+
+    let outcome = sweep_grid(
+        base_params,
+        sweep_config,
+        intensity_model,
+        &|params, sim_config| done.get(&point_key(params, sim_config)).cloned(),
+        &|row, _, _| {
+            let Ok(result) = row else { return };
+            let written = serde_json::to_string(result)
+                .map_err(std::io::Error::from)
+                .and_then(|json| {
+                    // One `write_all` per record; the length prefix catches a partial write.
+                    let record = format!("{} {json}\n", json.len());
+                    let mut file = file.lock().unwrap_or_else(PoisonError::into_inner);
+                    file.write_all(record.as_bytes())
+                });
+            if let Err(err) = written {
+                write_error
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .get_or_insert(err);
+            }
+        },
+        &AtomicBool::new(false),
+    );
+
+    match write_error
+        .into_inner()
+        .unwrap_or_else(PoisonError::into_inner)
+    {
+        Some(err) => Err(err),
+        None => Ok(outcome),
+    }
+}
+
+/// First record of a checkpoint file, see `run_sweep_with_checkpoint`.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CheckpointHeader {
+    settings: serde_json::Value,
+}
+
+/// `sweep_config` with its axes emptied, as JSON: what must match for checkpointed results to be
+/// reused.
+#[cfg(feature = "serde")]
+fn checkpoint_settings(sweep_config: &SweepConfig) -> serde_json::Result<serde_json::Value> {
+    serde_json::to_value(SweepConfig {
+        gammas: Vec::new(),
+        sigmas: Vec::new(),
+        ks: Vec::new(),
+        drifts: Vec::new(),
+        as_: Vec::new(),
+        t_horizons: Vec::new(),
+        latency_steps: Vec::new(),
+        ..sweep_config.clone()
+    })
+}
+
+/// Parses the leading run of complete `<length> <json>` records of a checkpoint file, returning
+/// the settings from its header, the results, and the byte length of that run. Without a header
+/// nothing in the file is used and the length is zero.
+#[cfg(feature = "serde")]
+fn read_checkpoint(contents: &[u8]) -> (Option<serde_json::Value>, Vec<SweepResult>, usize) {
+    let Some((header, mut pos)) = next_record(contents) else {
+        return (None, Vec::new(), 0);
+    };
+    let Ok(CheckpointHeader { settings }) = serde_json::from_slice(header) else {
+        return (None, Vec::new(), 0);
+    };
+    let mut results = Vec::new();
+    while let Some((json, next)) = next_record(&contents[pos..]) {
+        if let Ok(result) = serde_json::from_slice(json) {
+            results.push(result);
+        }
+        pos += next;
+    }
+    (Some(settings), results, pos)
+}
+
+/// Splits one framed record off the front of `bytes`: its JSON and the offset just past it.
+#[cfg(feature = "serde")]
+fn next_record(bytes: &[u8]) -> Option<(&[u8], usize)> {
+    let space = bytes.iter().position(|&b| b == b' ')?;
+    let len: usize = std::str::from_utf8(&bytes[..space]).ok()?.parse().ok()?;
+    let end = (space + 1).checked_add(len)?;
+    (bytes.get(end) == Some(&b'\n')).then(|| (&bytes[space + 1..end], end + 1))
+}
+
+/// Grid coordinates of a combination (`gamma`, `sigma`, `k`, `drift`, `a`, `t_horizon` as bits,
+/// then `latency_steps`), for exact matching.
+#[cfg(feature = "serde")]
+type GridKey = ([u64; 6], usize);
+
+#[cfg(feature = "serde")]
+fn point_key(params: &Parameters, sim_config: &SimConfig) -> GridKey {
+    (
+        [
+            params.gamma,
+            params.sigma,
+            params.k,
+            sim_config.drift,
+            params.a,
+            params.t_horizon,
+        ]
+        .map(f64::to_bits),
+        sim_config.latency_steps,
+    )
+}
+
+#[cfg(feature = "serde")]
+fn result_key(result: &SweepResult) -> GridKey {
+    (
+        [
+            result.gamma,
+            result.sigma,
+            result.k,
+            result.drift,
+            result.a,
+            result.t_horizon,
+        ]
+        .map(f64::to_bits),
+        result.latency_steps,
+    )
+}
+
+fn sweep_grid<M: IntensityModel + ?Sized>(
+    base_params: Parameters,
+    sweep_config: &SweepConfig,
+    intensity_model: &M,
+    cached: &ResultCache<'_>,
+    on_complete: &CompletionHook<'_>,
+    stop: &AtomicBool,
+) -> SweepOutcome {
+    let mut axes = vec![
+        SweepAxis::Gamma(sweep_config.gammas.clone()),
+        SweepAxis::Sigma(sweep_config.sigmas.clone()),
+        SweepAxis::K(sweep_config.ks.clone()),
+        SweepAxis::Drift(sweep_config.drifts.clone()),
+    ];
+    // The optional axes are left at their base values when empty.
+    if !sweep_config.as_.is_empty() {
+        axes.push(SweepAxis::A(sweep_config.as_.clone()));
+    }
+    if !sweep_config.t_horizons.is_empty() {
+        axes.push(SweepAxis::THorizon(sweep_config.t_horizons.clone()));
+    }
+    if !sweep_config.latency_steps.is_empty() {
+        axes.push(SweepAxis::LatencySteps(sweep_config.latency_steps.clone()));
+    }
+
+    let rows = sweep_axes(
+        base_params,
+        &sweep_config.sim_config,
+        &axes,
+        PointOptions::from_sweep(sweep_config),
+        intensity_model,
+        cached,
+        on_complete,
+        stop,
     )
-    .map(|(&g, &s, &k, &d)| (g, s, k, d))
+    .into_iter()
+    .map(|(_, row)| row)
     .collect();
 
+    SweepOutcome { rows }
+}
+
+/// Runs the Cartesian product of `axes` over the base configuration, omitting failed points.
+///
+/// Parameters without an axis keep their base value. Use `try_run_sweep_axes` to see which
+/// points failed and why.
+pub fn run_sweep_axes<M: IntensityModel + ?Sized>(
+    base_params: Parameters,
+    base_sim_config: &SimConfig,
+    axes: &[SweepAxis],
+    iterations_per_param: usize,
+    intensity_model: &M,
+) -> Vec<(ParamPoint, SweepResult)> {
+    try_run_sweep_axes(
+        base_params,
+        base_sim_config,
+        axes,
+        iterations_per_param,
+        intensity_model,
+    )
+    .into_iter()
+    .filter_map(|(point, row)| row.ok().map(|res| (point, res)))
+    .collect()
+}
+
+/// Runs the Cartesian product of `axes`, returning one `Result` per point.
+///
+/// Points are ordered with the first axis varying slowest, as in `itertools::iproduct!`.
+pub fn try_run_sweep_axes<M: IntensityModel + ?Sized>(
+    base_params: Parameters,
+    base_sim_config: &SimConfig,
+    axes: &[SweepAxis],
+    iterations_per_param: usize,
+    intensity_model: &M,
+) -> Vec<(ParamPoint, Result<SweepResult, SweepError>)> {
+    sweep_axes(
+        base_params,
+        base_sim_config,
+        axes,
+        PointOptions::plain(iterations_per_param),
+        intensity_model,
+        &|_, _| None,
+        &|_, _, _| {},
+        &AtomicBool::new(false),
+    )
+}
+
+/// Evaluates every point of the product of `axes`, in order. Once `stop` is set, points not yet
+/// started are skipped and left out of the returned rows.
+#[allow(clippy::too_many_arguments)]
+fn sweep_axes<M: IntensityModel + ?Sized>(
+    base_params: Parameters,
+    base_sim_config: &SimConfig,
+    axes: &[SweepAxis],
+    options: PointOptions,
+    intensity_model: &M,
+    cached: &ResultCache<'_>,
+    on_complete: &CompletionHook<'_>,
+    stop: &AtomicBool,
+) -> Vec<(ParamPoint, Result<SweepResult, SweepError>)> {
+    // Generate the value indices of every combination
+    let combinations: Vec<Vec<usize>> = axes.iter().fold(vec![Vec::new()], |acc, axis| {
+        acc.into_iter()
+            .flat_map(|prefix| {
+                (0..axis.len()).map(move |i| {
+                    let mut indices = prefix.clone();
+                    indices.push(i);
+                    indices
+                })
+            })
+            .collect()
+    });
+
+    let total = combinations.len();
+    let completed = AtomicUsize::new(0);
+
     // Run simulations in parallel
-    let results: Vec<SweepResult> = combinations
+    combinations
+        .par_iter()
+        .enumerate()
+        .filter_map(|(index, indices)| {
+            if stop.load(Ordering::Relaxed) {
+                return None;
+            }
+            let options = PointOptions {
+                seed: options.seed.map(|base| derive_seed(base, index as u64)),
+                ..options
+            };
+            let mut params = base_params;
+            let mut sim_config = base_sim_config.clone();
+            let mut point = ParamPoint::default();
+            let mut invalid = None;
+
+            for (axis, &i) in axes.iter().zip(indices) {
+                match axis.apply(i, &mut params, &mut sim_config) {
+                    Ok(value) => point.coords.push((axis.name(), value)),
+                    Err(kind) => {
+                        invalid.get_or_insert(kind);
+                    }
+                }
+            }
+
+            if invalid.is_none()
+                && let Some(result) = cached(&params, &sim_config)
+            {
+                return Some((point, Ok(result)));
+            }
+            let row = match invalid {
+                Some(kind) => Err(SweepError::new(&params, &sim_config, kind)),
+                None => evaluate_point_checked(&params, &sim_config, options, intensity_model),
+            }
+            .map_err(|err| err.with_point(&point));
+            on_complete(&row, completed.fetch_add(1, Ordering::Relaxed) + 1, total);
+            Some((point, row))
+        })
+        .collect()
+}
+
+/// Evaluates `n_samples` parameter points drawn over `ranges` with `ranges.sampling`.
+///
+/// Unlike the Cartesian grid of `run_sweep`, the cost grows linearly with `n_samples` however many
+/// dimensions are searched, and each point gets its own value along every axis. The default Latin
+/// hypercube covers each one-dimensional projection evenly.
+pub fn run_random_search<M: IntensityModel + ?Sized>(
+    base_params: Parameters,
+    ranges: &SearchRanges,
+    n_samples: usize,
+    intensity_model: &M,
+) -> Vec<SweepResult> {
+    search_points(ranges, n_samples)
         .par_iter()
-        .map(|&(gamma, sigma, k, drift)| {
+        .filter_map(|&[gamma, sigma, k, drift]| {
             let params = Parameters {
                 gamma,
                 sigma,
@@ -66,94 +1491,1139 @@ pub fn run_sweep(
                 ..base_params
             };
 
-            let mut current_sim_config = sweep_config.sim_config.clone();
+            let mut current_sim_config = ranges.sim_config.clone();
             current_sim_config.drift = drift;
 
-            struct RunStats {
-                pnl: f64,
-                mean_abs_q: f64,
-                max_q: f64,
-                final_q: f64,
-            }
+            evaluate_point_checked(
+                &params,
+                &current_sim_config,
+                PointOptions::plain(ranges.iterations_per_param),
+                intensity_model,
+            )
+            .ok()
+        })
+        .collect()
+}
 
-            // Run Monte Carlo for this parameter set
-            let run_stats: Vec<RunStats> = (0..sweep_config.iterations_per_param)
-                .map(|_| {
-                    let res = run_trajectory(&params, &current_sim_config, intensity_model);
-
-                    let final_q = res
-                        .trajectory
-                        .last()
-                        .map(|s| s.inventory as f64)
-                        .unwrap_or(0.0);
-                    let max_q = res
-                        .trajectory
-                        .iter()
-                        .map(|s| s.inventory.abs())
-                        .max()
-                        .unwrap_or(0) as f64;
-                    let mean_abs_q = res
-                        .trajectory
-                        .iter()
-                        .map(|s| s.inventory.abs() as f64)
-                        .sum::<f64>()
-                        / res.trajectory.len() as f64;
-
-                    RunStats {
-                        pnl: res.final_pnl,
-                        mean_abs_q,
-                        max_q,
-                        final_q,
-                    }
-                })
-                .collect();
+/// The `[gamma, sigma, k, drift]` values `run_random_search` evaluates.
+fn search_points(ranges: &SearchRanges, n_samples: usize) -> Vec<[f64; 4]> {
+    let mut rng = match ranges.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => unseeded_rng(),
+    };
+    let unit_points = match ranges.sampling {
+        SearchSampling::LatinHypercube => latin_hypercube(n_samples, 4, &mut rng),
+        SearchSampling::Uniform => (0..n_samples)
+            .map(|_| (0..4).map(|_| rng.random()).collect())
+            .collect(),
+        SearchSampling::Halton => halton_points(n_samples, 4),
+    };
 
-            let n = run_stats.len() as f64;
-            let pnls: Vec<f64> = run_stats.iter().map(|s| s.pnl).collect();
-            let final_qs: Vec<f64> = run_stats.iter().map(|s| s.final_q).collect();
+    let lerp = |(lo, hi): (f64, f64), u: f64| lo + (hi - lo) * u;
+    let scaled = |(lo, hi): (f64, f64), u: f64, log: bool| {
+        if log {
+            lerp((lo.ln(), hi.ln()), u).exp()
+        } else {
+            lerp((lo, hi), u)
+        }
+    };
 
-            let mean_pnl = pnls.iter().sum::<f64>() / n;
-            let pnl_variance =
-                pnls.iter().map(|&x| (x - mean_pnl).powi(2)).sum::<f64>() / (n - 1.0);
-            let std_pnl = pnl_variance.sqrt();
-            let sharpe = calculate_sharpe(&pnls);
+    unit_points
+        .iter()
+        .map(|u| {
+            [
+                scaled(ranges.gamma, u[0], ranges.log_gamma),
+                lerp(ranges.sigma, u[1]),
+                scaled(ranges.k, u[2], ranges.log_k),
+                lerp(ranges.drift, u[3]),
+            ]
+        })
+        .collect()
+}
 
-            let mean_abs_inventory = run_stats.iter().map(|s| s.mean_abs_q).sum::<f64>() / n;
-            let max_inventory = run_stats.iter().map(|s| s.max_q).sum::<f64>() / n;
+/// Bases of the Halton sequence, one prime per dimension.
+const HALTON_BASES: [u64; 4] = [2, 3, 5, 7];
 
-            let terminal_inv_mean = final_qs.iter().sum::<f64>() / n;
-            let terminal_inv_var = final_qs
+/// The first `n` points of the Halton sequence in `[0, 1)^dims`, skipping the all-zero point at
+/// index 0. `dims` must not exceed `HALTON_BASES.len()`.
+fn halton_points(n: usize, dims: usize) -> Vec<Vec<f64>> {
+    (1..=n as u64)
+        .map(|index| {
+            HALTON_BASES[..dims]
                 .iter()
-                .map(|&x| (x - terminal_inv_mean).powi(2))
-                .sum::<f64>()
-                / (n - 1.0);
-            let terminal_inventory_std = terminal_inv_var.sqrt();
-
-            SweepResult {
-                gamma,
-                sigma,
-                k,
-                drift,
-                mean_pnl,
-                std_pnl,
-                sharpe_ratio: sharpe,
-                max_drawdown: 0.0,
-                mean_abs_inventory,
-                max_inventory,
-                terminal_inventory_mean: terminal_inv_mean,
-                terminal_inventory_std: terminal_inventory_std,
-            }
+                .map(|&base| radical_inverse(index, base))
+                .collect()
         })
-        .collect();
+        .collect()
+}
 
-    results
+/// Reflects the base-`base` digits of `index` about the radix point: 6 = 110b becomes 0.011b.
+fn radical_inverse(mut index: u64, base: u64) -> f64 {
+    let (mut value, mut scale) = (0.0, 1.0 / base as f64);
+    while index > 0 {
+        value += (index % base) as f64 * scale;
+        index /= base;
+        scale /= base as f64;
+    }
+    value
+}
+
+/// Draws `n` points in `[0, 1)^dims` with exactly one point in each of the `n` equal-width strata
+/// along every dimension.
+fn latin_hypercube<R: Rng + ?Sized>(n: usize, dims: usize, rng: &mut R) -> Vec<Vec<f64>> {
+    let mut points = vec![vec![0.0; dims]; n];
+    let mut strata: Vec<usize> = (0..n).collect();
+
+    for d in 0..dims {
+        strata.shuffle(rng);
+        for (point, &stratum) in points.iter_mut().zip(&strata) {
+            point[d] = (stratum as f64 + rng.random::<f64>()) / n as f64;
+        }
+    }
+
+    points
+}
+
+/// How `evaluate_point` samples and summarises one parameter point.
+#[derive(Debug, Clone, Copy)]
+struct PointOptions {
+    iterations: usize,
+    antithetic: bool,
+    bootstrap_resamples: usize,
+    tail_confidence: f64,
+    sortino_mar: f64,
+    keep_profiles: bool,
+    /// Seed of the point itself; `sweep_axes` derives it per combination from the sweep's base
+    /// seed.
+    seed: Option<u64>,
+    periods_per_year: f64,
+    annualization_factor: Option<f64>,
+    baseline: bool,
+    histogram_bins: usize,
+}
+
+impl PointOptions {
+    fn from_sweep(sweep_config: &SweepConfig) -> Self {
+        PointOptions {
+            iterations: sweep_config.iterations_per_param,
+            antithetic: sweep_config.antithetic,
+            bootstrap_resamples: sweep_config.bootstrap_resamples,
+            tail_confidence: sweep_config.tail_confidence,
+            sortino_mar: sweep_config.sortino_mar,
+            keep_profiles: sweep_config.keep_profiles,
+            seed: sweep_config.seed,
+            periods_per_year: sweep_config.periods_per_year,
+            annualization_factor: sweep_config.annualization_factor,
+            baseline: sweep_config.baseline,
+            histogram_bins: sweep_config.histogram_bins,
+        }
+    }
+
+    /// Independent runs and no optional statistics.
+    fn plain(iterations: usize) -> Self {
+        PointOptions {
+            iterations,
+            antithetic: false,
+            bootstrap_resamples: 0,
+            tail_confidence: DEFAULT_TAIL_CONFIDENCE,
+            sortino_mar: 0.0,
+            keep_profiles: false,
+            seed: None,
+            periods_per_year: DEFAULT_PERIODS_PER_YEAR,
+            annualization_factor: None,
+            baseline: false,
+            histogram_bins: 0,
+        }
+    }
+}
+
+/// Validates the inputs of `evaluate_point` and turns a panic inside it into an error entry.
+fn evaluate_point_checked<M: IntensityModel + ?Sized>(
+    params: &Parameters,
+    sim_config: &SimConfig,
+    options: PointOptions,
+    intensity_model: &M,
+) -> Result<SweepResult, SweepError> {
+    let error = |kind| SweepError::new(params, sim_config, kind);
+
+    if options.iterations == 0 {
+        return Err(error(SweepErrorKind::NoIterations));
+    }
+    if sim_config.num_steps == 0 {
+        return Err(error(SweepErrorKind::EmptyTrajectory));
+    }
+    params
+        .validate()
+        .map_err(|err| error(SweepErrorKind::InvalidParams(err)))?;
+
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        evaluate_point(params, sim_config, options, intensity_model)
+    }))
+    .map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic payload".to_string());
+        error(SweepErrorKind::Panicked(message))
+    })
+}
+
+/// Evaluates one parameter point as a seeded sweep combination with `iterations` independent
+/// runs, for searches that pick their own points and seeds.
+pub(crate) fn evaluate_seeded_point<M: IntensityModel + ?Sized>(
+    params: &Parameters,
+    sim_config: &SimConfig,
+    iterations: usize,
+    seed: u64,
+    intensity_model: &M,
+) -> Result<SweepResult, SweepError> {
+    let options = PointOptions {
+        seed: Some(seed),
+        ..PointOptions::plain(iterations)
+    };
+    evaluate_point_checked(params, sim_config, options, intensity_model)
+}
+
+/// Seed of stream `index` under `base`: the SplitMix64 finaliser applied to their combination, so
+/// neighbouring indices and bases give unrelated seeds.
+pub(crate) fn derive_seed(base: u64, index: u64) -> u64 {
+    let mut z = base ^ index.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Single-pass aggregate of the runs at one parameter point.
+///
+/// PnL and terminal inventory feed Welford accumulators and every other per-run figure is only
+/// ever averaged, so it is kept as a running sum. Just three numbers per run are stored: PnL for
+/// the percentiles, bootstrap, Sortino ratio, and antithetic pairing, plus the control and mean
+/// liquidity that the control variate and the PnL-liquidity correlation pair with it. Each chunk
+/// of runs fills its own accumulator, and `merge` joins them in run order.
+#[derive(Default)]
+struct PointAccumulator {
+    pnl: Welford,
+    final_q: Welford,
+    pnls: Vec<f64>,
+    controls: Vec<f64>,
+    liquidities: Vec<f64>,
+    // Sums over runs
+    penalty: f64,
+    carry: f64,
+    staleness_mean: f64,
+    staleness_exceed_frac: f64,
+    stressed_fraction: f64,
+    calm_pnl: f64,
+    stressed_pnl: f64,
+    spread_pnl: f64,
+    inventory_pnl: f64,
+    liquidity: f64,
+    stopped: usize,
+    fills: usize,
+    notional: f64,
+    capture_sum: f64,
+    drawdown: f64,
+    abs_q: f64, // Of each run's mean |q|
+    time_weighted_abs_q: f64,
+    q_variance: f64,
+    q_autocorr: f64,
+    max_q: f64,
+}
+
+impl PointAccumulator {
+    fn push(&mut self, res: &SimResult, dt: f64) {
+        let trajectory = &res.trajectory;
+        let abs_q = || trajectory.iter().map(|s| s.inventory.abs());
+        self.pnl.push(res.final_pnl);
+        self.final_q.push(res.final_inventory);
+        self.pnls.push(res.final_pnl);
+        self.controls.push(res.spread_capture_control);
+        self.liquidities.push(res.mean_liquidity);
+
+        self.penalty += res.running_penalty;
+        self.carry += res.carry_paid;
+        self.staleness_mean += res.quote_staleness_mean;
+        self.staleness_exceed_frac += res.quote_staleness_exceed_frac;
+        self.stressed_fraction += res.stressed_fraction;
+        self.calm_pnl += res.calm_pnl;
+        self.stressed_pnl += res.stressed_pnl;
+        self.spread_pnl += res.spread_pnl;
+        self.inventory_pnl += res.inventory_pnl;
+        self.liquidity += res.mean_liquidity;
+        self.stopped += res.stopped_at.is_some() as usize;
+        self.fills += res.bid_fills + res.ask_fills;
+        self.notional += res.traded_notional();
+        self.capture_sum += res.mean_spread_capture() * res.fills.len() as f64;
+        let wealth = trajectory.iter().map(|s| &s.wealth);
+        self.drawdown += max_drawdown(wealth.chain([&res.final_pnl]));
+        self.abs_q += abs_q().sum::<f64>() / trajectory.len() as f64;
+        self.time_weighted_abs_q += trajectory.last().map_or(0.0, |last| {
+            time_weighted_abs_inventory(trajectory, last.time + dt)
+        });
+        self.q_variance += inventory_variance(trajectory);
+        self.q_autocorr += inventory_autocorr_lag1(trajectory);
+        self.max_q += abs_q().fold(0.0, f64::max);
+    }
+
+    /// Appends the runs of `other`, which must come after this accumulator's own in run order.
+    fn merge(&mut self, other: PointAccumulator) {
+        self.pnl.merge(&other.pnl);
+        self.final_q.merge(&other.final_q);
+        self.pnls.extend(other.pnls);
+        self.controls.extend(other.controls);
+        self.liquidities.extend(other.liquidities);
+
+        self.penalty += other.penalty;
+        self.carry += other.carry;
+        self.staleness_mean += other.staleness_mean;
+        self.staleness_exceed_frac += other.staleness_exceed_frac;
+        self.stressed_fraction += other.stressed_fraction;
+        self.calm_pnl += other.calm_pnl;
+        self.stressed_pnl += other.stressed_pnl;
+        self.spread_pnl += other.spread_pnl;
+        self.inventory_pnl += other.inventory_pnl;
+        self.liquidity += other.liquidity;
+        self.stopped += other.stopped;
+        self.fills += other.fills;
+        self.notional += other.notional;
+        self.capture_sum += other.capture_sum;
+        self.drawdown += other.drawdown;
+        self.abs_q += other.abs_q;
+        self.time_weighted_abs_q += other.time_weighted_abs_q;
+        self.q_variance += other.q_variance;
+        self.q_autocorr += other.q_autocorr;
+        self.max_q += other.max_q;
+    }
+}
+
+/// Draws (runs, or antithetic pairs) simulated together as one parallel task of a point.
+///
+/// The chunk boundaries are fixed rather than left to the thread pool, so the order in which the
+/// partial aggregates are merged, and with it every floating-point sum, is the same on any number
+/// of threads.
+const DRAWS_PER_CHUNK: usize = 64;
+
+/// A run's and its antithetic mirror's buffers, reused across the draws of a chunk.
+type Workspaces = [TrajectoryWorkspace; 2];
+
+/// Simulates `draws` in chunks of `DRAWS_PER_CHUNK` in parallel, each folded by `chunk` into one
+/// partial result, and returns the partials in draw order.
+///
+/// Workspaces are created once per rayon job with `map_init`, not per run, and handed from chunk
+/// to chunk.
+fn simulate_chunks<T: Send>(
+    draws: usize,
+    chunk: impl Fn(&mut Workspaces, Range<usize>) -> T + Send + Sync,
+) -> Vec<T> {
+    (0..draws.div_ceil(DRAWS_PER_CHUNK))
+        .into_par_iter()
+        .map_init(Workspaces::default, |workspaces, c| {
+            chunk(
+                workspaces,
+                c * DRAWS_PER_CHUNK..((c + 1) * DRAWS_PER_CHUNK).min(draws),
+            )
+        })
+        .collect()
+}
+
+/// Simulates draws `draws` of a point in order into `workspaces`, passing every run to `record`;
+/// an antithetic draw records its run and then its mirror.
+///
+/// Draw `i` gets its own stream, so seeded results never depend on which runs another
+/// combination, or another chunk of this one, happened to draw first.
+#[allow(clippy::too_many_arguments)]
+fn simulate_draws<M: IntensityModel + ?Sized>(
+    workspaces: &mut Workspaces,
+    params: &Parameters,
+    sim_config: &SimConfig,
+    intensity_model: &M,
+    antithetic: bool,
+    seed: Option<u64>,
+    draws: Range<usize>,
+    mut record: impl FnMut(&SimResult),
+) {
+    let [run, mirror] = workspaces;
+    for i in draws {
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(derive_seed(seed, i as u64)),
+            None => unseeded_rng(),
+        };
+        if antithetic {
+            let (run, mirror) = run_antithetic_pair_into(
+                run,
+                mirror,
+                params,
+                sim_config,
+                intensity_model,
+                &mut rng,
+            );
+            record(run);
+            record(mirror);
+        } else {
+            record(run_trajectory_into(
+                run,
+                params,
+                sim_config,
+                intensity_model,
+                &mut rng,
+            ));
+        }
+    }
+}
+
+/// Runs the Monte Carlo for a single parameter point and aggregates it into a `SweepResult`.
+///
+/// Expects `iterations > 0` and `num_steps > 0`; `evaluate_point_checked` enforces both.
+fn evaluate_point<M: IntensityModel + ?Sized>(
+    params: &Parameters,
+    sim_config: &SimConfig,
+    options: PointOptions,
+    intensity_model: &M,
+) -> SweepResult {
+    let PointOptions {
+        iterations,
+        antithetic,
+        bootstrap_resamples,
+        tail_confidence,
+        sortino_mar,
+        keep_profiles,
+        seed,
+        periods_per_year,
+        annualization_factor: annualization_override,
+        baseline,
+        histogram_bins,
+    } = options;
+    // The baseline replays the runs' streams, so an unseeded point still needs a seed to share.
+    let seed = seed.or_else(|| baseline.then(|| unseeded_rng().random()));
+    let draws = if antithetic {
+        iterations.div_ceil(2)
+    } else {
+        iterations
+    };
+    // Chunks run in parallel, so a small grid with many iterations still fills every core.
+    // Antithetic pairs are recorded back to back, so `acc.pnls.chunks(2)` recovers them.
+    let chunks = simulate_chunks(draws, |workspaces, range| {
+        let mut acc = PointAccumulator::default();
+        let mut kept = Vec::new();
+        simulate_draws(
+            workspaces,
+            params,
+            sim_config,
+            intensity_model,
+            antithetic,
+            seed,
+            range,
+            |res| {
+                acc.push(res, sim_config.dt);
+                if keep_profiles {
+                    kept.push(res.clone());
+                }
+            },
+        );
+        (acc, kept)
+    });
+    let mut acc = PointAccumulator::default();
+    let mut kept = Vec::new();
+    for (chunk_acc, chunk_kept) in chunks {
+        acc.merge(chunk_acc);
+        kept.extend(chunk_kept);
+    }
+    let profile = keep_profiles.then(|| trajectory_profile(&kept));
+
+    let mut baseline_pnl = Welford::default();
+    if baseline {
+        let baseline_params = Parameters {
+            quoting_model: QuotingModel::FixedSpread {
+                spread: mean_optimal_spread(params, sim_config.num_steps as f64 * sim_config.dt),
+            },
+            ..*params
+        };
+        let chunks = simulate_chunks(draws, |workspaces, range| {
+            let mut pnl = Welford::default();
+            simulate_draws(
+                workspaces,
+                &baseline_params,
+                sim_config,
+                intensity_model,
+                antithetic,
+                seed,
+                range,
+                |res| pnl.push(res.final_pnl),
+            );
+            pnl
+        });
+        for chunk in &chunks {
+            baseline_pnl.merge(chunk);
+        }
+    }
+    let (baseline_mean_pnl, baseline_std_pnl) = if baseline {
+        (baseline_pnl.mean(), baseline_pnl.std_dev())
+    } else {
+        (f64::NAN, f64::NAN)
+    };
+
+    let n = acc.pnl.count() as f64;
+    let mean_pnl = acc.pnl.mean();
+    let std_pnl = acc.pnl.std_dev();
+    let sharpe = acc.pnl.sharpe_ratio();
+    let sortino = sortino_ratio(&acc.pnls, sortino_mar);
+    let mean_max_drawdown = acc.drawdown / n;
+    // Mirrored runs are negatively correlated, so the errors of the means come from pair averages.
+    let pair_means = |xs: &[f64]| -> Vec<f64> {
+        xs.chunks(2)
+            .map(|pair| pair.iter().sum::<f64>() / 2.0)
+            .collect()
+    };
+    let control_expectation = expected_spread_capture(params, sim_config, intensity_model);
+    let (pnl_sem, cv) = if antithetic {
+        let pnl_pairs = pair_means(&acc.pnls);
+        (
+            (sample_variance(&pnl_pairs) / pnl_pairs.len() as f64).sqrt(),
+            control_variate_mean(&pnl_pairs, &pair_means(&acc.controls), control_expectation),
+        )
+    } else {
+        (
+            std_pnl / n.sqrt(),
+            control_variate_mean(&acc.pnls, &acc.controls, control_expectation),
+        )
+    };
+    // Resample whole antithetic pairs, since only distinct pairs are independent.
+    let (mean_pnl_ci, sharpe_ci) = if bootstrap_resamples == 0 {
+        ((f64::NAN, f64::NAN), (f64::NAN, f64::NAN))
+    } else {
+        let block = if antithetic { 2 } else { 1 };
+        // Stream `iterations` is past every run index.
+        let seed = seed.map_or_else(
+            || unseeded_rng().random(),
+            |s| derive_seed(s, iterations as u64),
+        );
+        (
+            block_bootstrap_ci(&acc.pnls, block, mean, bootstrap_resamples, seed),
+            block_bootstrap_ci(&acc.pnls, block, sharpe_ratio, bootstrap_resamples, seed),
+        )
+    };
+    let pnl_liquidity_corr = correlation(&acc.pnls, &acc.liquidities);
+
+    // Nothing below depends on run order any more, so the stored PnLs can be sorted in place.
+    let mut sorted_pnls = std::mem::take(&mut acc.pnls);
+    sorted_pnls.sort_by(f64::total_cmp);
+    let tail = TailRisk::from_sorted(&sorted_pnls, tail_confidence);
+    let histogram = (histogram_bins > 0).then(|| pnl_histogram(&sorted_pnls, histogram_bins));
+
+    let mean_running_penalty = acc.penalty / n;
+    let per_fill = |total: f64| {
+        if acc.fills == 0 {
+            0.0
+        } else {
+            total / acc.fills as f64
+        }
+    };
+
+    SweepResult {
+        model_name: String::new(),
+        gamma: params.gamma,
+        sigma: params.sigma,
+        k: params.k,
+        drift: sim_config.drift,
+        a: params.a,
+        t_horizon: params.t_horizon,
+        latency_steps: sim_config.latency_steps,
+        mean_pnl,
+        std_pnl,
+        pnl_sem,
+        cv_mean_pnl: cv.mean,
+        cv_pnl_sem: cv.std_error,
+        median_pnl: percentile(&sorted_pnls, 0.5),
+        iqr_pnl: percentile(&sorted_pnls, 0.75) - percentile(&sorted_pnls, 0.25),
+        mean_pnl_ci_low: mean_pnl_ci.0,
+        mean_pnl_ci_high: mean_pnl_ci.1,
+        sharpe_ratio: sharpe,
+        sharpe_annualized: sharpe
+            * annualization_override
+                .unwrap_or_else(|| annualization_factor(sim_config, periods_per_year))
+                .sqrt(),
+        sharpe_ci_low: sharpe_ci.0,
+        sharpe_ci_high: sharpe_ci.1,
+        pnl_p05: tail.lower,
+        pnl_p95: tail.upper,
+        var_95: tail.var,
+        cvar_95: tail.cvar,
+        sortino_ratio: sortino,
+        calmar_ratio: calmar_ratio(mean_pnl, mean_max_drawdown),
+        max_drawdown: mean_max_drawdown,
+        mean_abs_inventory: acc.abs_q / n,
+        time_weighted_abs_inventory: acc.time_weighted_abs_q / n,
+        mean_inventory_variance: acc.q_variance / n,
+        mean_inventory_autocorr_lag1: acc.q_autocorr / n,
+        max_inventory: acc.max_q / n,
+        terminal_inventory_mean: acc.final_q.mean(),
+        terminal_inventory_std: acc.final_q.std_dev(),
+        mean_running_penalty,
+        mean_objective: mean_pnl - mean_running_penalty,
+        mean_carry_paid: acc.carry / n,
+        quote_staleness_mean: acc.staleness_mean / n,
+        quote_staleness_exceed_frac: acc.staleness_exceed_frac / n,
+        stressed_fraction_mean: acc.stressed_fraction / n,
+        mean_calm_pnl: acc.calm_pnl / n,
+        mean_stressed_pnl: acc.stressed_pnl / n,
+        mean_spread_pnl: acc.spread_pnl / n,
+        mean_inventory_pnl: acc.inventory_pnl / n,
+        mean_liquidity: acc.liquidity / n,
+        pnl_liquidity_corr,
+        stopped_frac: acc.stopped as f64 / n,
+        mean_fills_per_run: acc.fills as f64 / n,
+        mean_traded_notional: acc.notional / n,
+        mean_spread_capture: per_fill(acc.capture_sum),
+        mean_realized_half_spread: per_fill(acc.spread_pnl),
+        baseline_mean_pnl,
+        baseline_std_pnl,
+        pnl_uplift: mean_pnl - baseline_mean_pnl,
+        profile,
+        pnl_histogram: histogram,
+    }
+}
+
+/// Sharpe ratio of the per-step wealth increments of one trajectory over a sliding window.
+///
+/// Increments run through every `StepRecord.wealth` and end at `final_pnl`. Entry `i` covers
+/// increments `i..i + window`; the ratio is per step (not annualised) and zero on a window with no
+/// variation, matching the cross-run Sharpe. Returns an empty vector if `window` is zero or longer
+/// than the path.
+pub fn rolling_sharpe(result: &SimResult, window: usize) -> Vec<f64> {
+    if window == 0 {
+        return Vec::new();
+    }
+
+    let wealth: Vec<f64> = result
+        .trajectory
+        .iter()
+        .map(|s| s.wealth)
+        .chain([result.final_pnl])
+        .collect();
+    let increments: Vec<f64> = wealth.windows(2).map(|w| w[1] - w[0]).collect();
+    increments.windows(window).map(sharpe_ratio).collect()
+}
+
+/// Post-fill mid-price move at one horizon, averaged over the fills of a run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Markout {
+    pub horizon: usize, // Steps after the fill
+    pub mean: f64,      // Per unit traded; positive when the mid moved in the agent's favour
+    pub fills: usize,   // Fills whose horizon ends inside the trajectory
+}
+
+/// Markouts of the fills in `result` at each of `horizons` steps.
+///
+/// A fill's markout is the mid `h` steps later minus `mid_at_fill` for a bid, and the reverse for
+/// an ask, so a quoter that is being picked off shows negative values. The later mid is
+/// `trajectory[fill.step + 1 + h].mid_price`; fills too close to the end to observe it are
+/// skipped, and a horizon with no observable fill has a zero mean.
+pub fn markouts(result: &SimResult, horizons: &[usize]) -> Vec<Markout> {
+    horizons
+        .iter()
+        .map(|&horizon| {
+            let (mut weighted, mut volume, mut fills) = (0.0, 0.0, 0);
+            for fill in &result.fills {
+                let Some(later) = result.trajectory.get(fill.step + 1 + horizon) else {
+                    continue;
+                };
+                let side_sign = match fill.side {
+                    Side::Bid => 1.0,
+                    Side::Ask => -1.0,
+                };
+                weighted += side_sign * (later.mid_price - fill.mid_at_fill) * fill.size;
+                volume += fill.size;
+                fills += 1;
+            }
+            Markout {
+                horizon,
+                mean: if volume > 0.0 { weighted / volume } else { 0.0 },
+                fills,
+            }
+        })
+        .collect()
+}
+
+/// Runs `n` independent trajectories in parallel.
+pub fn run_trajectories<M: IntensityModel + ?Sized>(
+    params: &Parameters,
+    sim_config: &SimConfig,
+    intensity_model: &M,
+    n: usize,
+) -> Vec<SimResult> {
+    (0..n)
+        .into_par_iter()
+        .map(|_| run_trajectory(params, sim_config, intensity_model))
+        .collect()
+}
+
+/// Average shape of a set of trajectories: the cross-run mean and standard deviation of
+/// inventory, wealth, and quoted spread (`ask_price - bid_price`) at each step.
+///
+/// Entry `i` of every vector covers the runs still active at step `i`, counted in `active_runs`.
+/// A run drops out after the step on which its drawdown kill-switch fired, since the flat,
+/// unquoted rows that follow would pull the averages toward zero, and past the end of a shorter
+/// path. Means are zero and deviations (Bessel-corrected) are zero where fewer than one and two
+/// runs are active respectively.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Profile {
+    pub time: Vec<f64>,
+    pub active_runs: Vec<usize>,
+    pub inventory_mean: Vec<f64>,
+    pub inventory_std: Vec<f64>,
+    pub wealth_mean: Vec<f64>,
+    pub wealth_std: Vec<f64>,
+    pub spread_mean: Vec<f64>,
+    pub spread_std: Vec<f64>,
+}
+
+/// Builds the `Profile` of `results`, for example the output of `run_trajectories`.
+pub fn trajectory_profile(results: &[SimResult]) -> Profile {
+    let steps = results
+        .iter()
+        .map(|r| r.trajectory.len())
+        .max()
+        .unwrap_or(0);
+    let mut profile = Profile::default();
+
+    for i in 0..steps {
+        let rows: Vec<&StepRecord> = results
+            .iter()
+            .filter(|r| r.stopped_at.is_none_or(|stop| i <= stop))
+            .filter_map(|r| r.trajectory.get(i))
+            .collect();
+        let summary = |field: fn(&StepRecord) -> f64| {
+            let xs: Vec<f64> = rows.iter().map(|&row| field(row)).collect();
+            (mean(&xs), sample_variance(&xs).sqrt())
+        };
+        let (inventory_mean, inventory_std) = summary(|row| row.inventory);
+        let (wealth_mean, wealth_std) = summary(|row| row.wealth);
+        let (spread_mean, spread_std) = summary(|row| row.ask_price - row.bid_price);
+
+        let time = results
+            .iter()
+            .find_map(|r| r.trajectory.get(i))
+            .map_or(0.0, |row| row.time);
+        profile.time.push(time);
+        profile.active_runs.push(rows.len());
+        profile.inventory_mean.push(inventory_mean);
+        profile.inventory_std.push(inventory_std);
+        profile.wealth_mean.push(wealth_mean);
+        profile.wealth_std.push(wealth_std);
+        profile.spread_mean.push(spread_mean);
+        profile.spread_std.push(spread_std);
+    }
+
+    profile
+}
+
+/// Central finite-difference estimate of `d(mean_pnl)/d(gamma)` at `gamma`, the gamma leg of
+/// `sensitivity` with an absolute step `h`.
+///
+/// Each iteration runs the `gamma + h` and `gamma - h` strategies on the same seed, so both legs
+/// see identical price shocks and fill uniforms (common random numbers). The market noise cancels
+/// in the paired difference and only the effect of the changed quotes remains, which keeps the
+/// estimator usable for small `h` where independent batches would be swamped by sampling noise.
+/// Path `i` is seeded from `seed` and `i`, so the estimate is reproducible.
+///
+/// Fails if `h` is not positive, either bumped strategy or `sim_config` is invalid, or
+/// `iterations` is zero.
+pub fn pnl_sensitivity_gamma<M: IntensityModel + ?Sized>(
+    base: &Parameters,
+    sim_config: &SimConfig,
+    intensity_model: &M,
+    gamma: f64,
+    h: f64,
+    iterations: usize,
+    seed: u64,
+) -> Result<f64, ConfigError> {
+    if h.is_nan() || h <= 0.0 {
+        return Err(ParamError::NotPositive {
+            name: "h",
+            value: h,
+        }
+        .into());
+    }
+    let params = Parameters { gamma, ..*base };
+    let legs: [(f64, Bump); 1] = [(h, |p, _, h| p.gamma += h)];
+    validate_legs(&params, sim_config, &legs, iterations)?;
+    let slopes = paired_slopes(
+        &params,
+        sim_config,
+        intensity_model,
+        &legs,
+        iterations,
+        seed,
+    );
+    Ok(summarize_slopes(&slopes, 0, h).estimate)
+}
+
+/// Moves one input of a finite difference by a signed step.
+type Bump = fn(&mut Parameters, &mut SimConfig, f64);
+
+/// Checks what `paired_slopes` needs: a valid `sim_config`, at least one path, and a valid
+/// strategy and simulation on both sides of every leg.
+fn validate_legs<const N: usize>(
+    params: &Parameters,
+    sim_config: &SimConfig,
+    legs: &[(f64, Bump); N],
+    n: usize,
+) -> Result<(), ConfigError> {
+    sim_config.validate()?;
+    if n == 0 {
+        return Err(ConfigError::NoIterations);
+    }
+    for &(step, bump) in legs {
+        for signed_step in [step, -step] {
+            let (mut params, mut config) = (*params, sim_config.clone());
+            bump(&mut params, &mut config, signed_step);
+            params.validate()?;
+            config.validate()?;
+        }
+    }
+    Ok(())
+}
+
+/// Per-path central differences `(pnl(+step) - pnl(-step)) / (2 * step)` of each `(step, bump)`
+/// leg around `params` and `sim_config`. Every run on path `i` is seeded from `seed` and `i`, so
+/// the legs share their market noise.
+fn paired_slopes<M: IntensityModel + ?Sized, const N: usize>(
+    params: &Parameters,
+    sim_config: &SimConfig,
+    intensity_model: &M,
+    legs: &[(f64, Bump); N],
+    n: usize,
+    seed: u64,
+) -> Vec<[f64; N]> {
+    (0..n as u64)
+        .into_par_iter()
+        .map(|i| {
+            let path_seed = derive_seed(seed, i);
+            let run = |step: f64, bump: Bump| {
+                let (mut params, mut config) = (*params, sim_config.clone());
+                bump(&mut params, &mut config, step);
+                let mut rng = StdRng::seed_from_u64(path_seed);
+                run_trajectory_with_rng(&params, &config, intensity_model, &mut rng).final_pnl
+            };
+            legs.map(|(step, bump)| (run(step, bump) - run(-step, bump)) / (2.0 * step))
+        })
+        .collect()
+}
+
+/// Mean and standard error of leg `j` of `paired_slopes`.
+fn summarize_slopes<const N: usize>(slopes: &[[f64; N]], j: usize, step: f64) -> Sensitivity {
+    let mut acc = Welford::default();
+    for path in slopes {
+        acc.push(path[j]);
+    }
+    Sensitivity {
+        step,
+        estimate: acc.mean(),
+        std_error: acc.std_dev() / (slopes.len() as f64).sqrt(),
+    }
+}
+
+/// Finite-difference steps for `sensitivity`. Each parameter is bumped up and down by its step,
+/// which for `gamma`, `sigma`, and `k` is the fraction given here of the base value and must lie
+/// in `(0, 1)`; drift usually sits at zero, so its step is absolute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensitivityBumps {
+    pub gamma: f64,
+    pub sigma: f64,
+    pub k: f64,
+    pub drift: f64,
+}
+
+impl Default for SensitivityBumps {
+    fn default() -> Self {
+        SensitivityBumps {
+            gamma: 0.05,
+            sigma: 0.05,
+            k: 0.05,
+            drift: 0.01,
+        }
+    }
+}
+
+/// Central finite-difference estimate of `d(mean_pnl)/d(parameter)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sensitivity {
+    pub step: f64, // Absolute bump applied in each direction
+    pub estimate: f64,
+    pub std_error: f64, // From the spread of the per-path differences
+}
+
+/// Local sensitivities of mean PnL, see `sensitivity`. `sigma` moves both the simulated
+/// volatility and the strategy's estimate of it; `k` is only the strategy's assumption, since
+/// the market follows the intensity model.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SensitivityReport {
+    pub n: usize,
+    pub gamma: Sensitivity,
+    pub sigma: Sensitivity,
+    pub k: Sensitivity,
+    pub drift: Sensitivity, // Of `SimConfig::drift`
+}
+
+/// Central finite-difference sensitivities of mean PnL to `gamma`, `sigma`, `k`, and drift around
+/// `base_params` and `sim_config`.
+///
+/// As in `pnl_sensitivity_gamma`, both legs of every difference run on the same seed, so the
+/// market noise they share cancels (common random numbers). Path `i` is seeded from `seed` and `i`,
+/// which makes the report reproducible; the standard errors come from the spread of the per-path
+/// differences over the `n` paths.
+///
+/// Fails if `base_params` is invalid, a relative bump lies outside `(0, 1)`, the drift bump is
+/// zero or not finite, a relative bump of a zero `sigma` leaves no step, either side of a bump is
+/// an invalid strategy or simulation, `sim_config` is invalid, or `n` is zero.
+pub fn sensitivity<M: IntensityModel + ?Sized>(
+    base_params: &Parameters,
+    sim_config: &SimConfig,
+    intensity_model: &M,
+    bumps: &SensitivityBumps,
+    n: usize,
+    seed: u64,
+) -> Result<SensitivityReport, ConfigError> {
+    base_params.validate()?;
+    for (name, bump) in [
+        ("bumps.gamma", bumps.gamma),
+        ("bumps.sigma", bumps.sigma),
+        ("bumps.k", bumps.k),
+    ] {
+        if !(bump > 0.0 && bump < 1.0) {
+            return Err(ConfigError::OutOfRange {
+                name,
+                value: bump,
+                expected: "in (0, 1)",
+            });
+        }
+    }
+    if bumps.drift == 0.0 || !bumps.drift.is_finite() {
+        return Err(ConfigError::OutOfRange {
+            name: "bumps.drift",
+            value: bumps.drift,
+            expected: "nonzero and finite",
+        });
+    }
+    let legs: [(f64, Bump); 4] = [
+        (bumps.gamma * base_params.gamma, |p, _, h| p.gamma += h),
+        (bumps.sigma * base_params.sigma, |p, _, h| p.sigma += h),
+        (bumps.k * base_params.k, |p, _, h| p.k += h),
+        (bumps.drift, |_, c, h| c.drift += h),
+    ];
+    // `gamma` and `k` are positive in valid parameters, but `sigma` may be zero.
+    if legs[1].0 == 0.0 {
+        return Err(ConfigError::OutOfRange {
+            name: "sigma",
+            value: base_params.sigma,
+            expected: "nonzero for a relative bump",
+        });
+    }
+    validate_legs(base_params, sim_config, &legs, n)?;
+
+    let slopes = paired_slopes(base_params, sim_config, intensity_model, &legs, n, seed);
+    let [gamma, sigma, k, drift] = std::array::from_fn(|j| summarize_slopes(&slopes, j, legs[j].0));
+    Ok(SensitivityReport {
+        n,
+        gamma,
+        sigma,
+        k,
+        drift,
+    })
+}
+
+/// Paired comparison of two strategies over the same simulated paths, see
+/// `compare_parameter_sets`. Differences are always A minus B.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparison {
+    pub n: usize, // Paths, each run under both strategies
+    pub mean_pnl_a: f64,
+    pub mean_pnl_b: f64,
+    pub mean_diff: f64,
+    pub diff_se: f64, // Standard error of `mean_diff` from the spread of the paired differences
+    /// 95% t-interval for the mean difference.
+    pub ci_low: f64,
+    pub ci_high: f64,
+    pub t_stat: f64,
+    /// Two-sided p-value of the paired t-test of a zero mean difference.
+    pub p_value: f64,
+    pub win_rate: f64, // Fraction of paths where A ends strictly ahead of B
+}
+
+/// Runs strategies `params_a` and `params_b` on the same `n` paths and tests whether their mean
+/// PnLs differ.
+///
+/// Path `i` is seeded from `seed` and `i` alone, so both strategies see identical price shocks and
+/// fill uniforms (common random numbers) and the result does not depend on thread scheduling. The
+/// market noise shared by both legs cancels in each paired difference, which makes the test far
+/// more sensitive than comparing two independent batches. With fewer than two paths the interval
+/// and p-value are NaN; when every difference is equal, the p-value is 1 for a zero difference
+/// and 0 otherwise.
+pub fn compare_parameter_sets<M: IntensityModel + ?Sized>(
+    params_a: &Parameters,
+    params_b: &Parameters,
+    sim_config: &SimConfig,
+    intensity_model: &M,
+    n: usize,
+    seed: u64,
+) -> Comparison {
+    let pairs: Vec<(f64, f64)> = (0..n as u64)
+        .into_par_iter()
+        .map(|i| {
+            let path_seed = derive_seed(seed, i);
+            let run = |params| {
+                let mut rng = StdRng::seed_from_u64(path_seed);
+                run_trajectory_with_rng(params, sim_config, intensity_model, &mut rng).final_pnl
+            };
+            (run(params_a), run(params_b))
+        })
+        .collect();
+
+    let (mut a, mut b, mut diff) = (Welford::default(), Welford::default(), Welford::default());
+    let mut wins = 0;
+    for &(pnl_a, pnl_b) in &pairs {
+        a.push(pnl_a);
+        b.push(pnl_b);
+        diff.push(pnl_a - pnl_b);
+        wins += (pnl_a > pnl_b) as usize;
+    }
+
+    let mean_diff = diff.mean();
+    let diff_se = diff.std_dev() / (n as f64).sqrt();
+    let df = n as f64 - 1.0;
+    let (t_stat, p_value, margin) = if n < 2 {
+        (f64::NAN, f64::NAN, f64::NAN)
+    } else if diff_se == 0.0 {
+        let t = if mean_diff == 0.0 {
+            0.0
+        } else {
+            mean_diff.signum() * f64::INFINITY
+        };
+        (t, if mean_diff == 0.0 { 1.0 } else { 0.0 }, 0.0)
+    } else {
+        let t = mean_diff / diff_se;
+        (
+            t,
+            2.0 * student_t_cdf(-t.abs(), df),
+            student_t_quantile(0.975, df) * diff_se,
+        )
+    };
+
+    Comparison {
+        n,
+        mean_pnl_a: a.mean(),
+        mean_pnl_b: b.mean(),
+        mean_diff,
+        diff_se,
+        ci_low: mean_diff - margin,
+        ci_high: mean_diff + margin,
+        t_stat,
+        p_value,
+        win_rate: if n == 0 { 0.0 } else { wins as f64 / n as f64 },
+    }
+}
+
+/// A mean estimated with a control variate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControlVariateEstimate {
+    pub mean: f64,
+    pub std_error: f64,
+    pub beta: f64, // Fitted coefficient on the control
+}
+
+/// Mean of `samples` corrected by paired `controls` whose true mean is `control_expectation`.
+///
+/// Fits the variance-minimising coefficient `beta = cov(Y, C) / var(C)` from the samples and
+/// returns `mean(Y) - beta * (mean(C) - control_expectation)`, with the standard error of the
+/// adjusted samples `Y - beta * C`. Fitting `beta` on the same samples biases the mean by only
+/// O(1/n). A constant control gives `beta = 0`, i.e. the plain mean; empty input gives zeros.
+pub fn control_variate_mean(
+    samples: &[f64],
+    controls: &[f64],
+    control_expectation: f64,
+) -> ControlVariateEstimate {
+    let n = samples.len().min(controls.len());
+    let (samples, controls) = (&samples[..n], &controls[..n]);
+    if n == 0 {
+        return ControlVariateEstimate {
+            mean: 0.0,
+            std_error: 0.0,
+            beta: 0.0,
+        };
+    }
+
+    let mean_y = samples.iter().sum::<f64>() / n as f64;
+    let mean_c = controls.iter().sum::<f64>() / n as f64;
+    let var_c = sample_variance(controls);
+    let beta = if var_c == 0.0 {
+        0.0
+    } else {
+        let cov = samples
+            .iter()
+            .zip(controls)
+            .map(|(&y, &c)| (y - mean_y) * (c - mean_c))
+            .sum::<f64>()
+            / (n as f64 - 1.0);
+        cov / var_c
+    };
+
+    let adjusted: Vec<f64> = samples
+        .iter()
+        .zip(controls)
+        .map(|(&y, &c)| y - beta * c)
+        .collect();
+    ControlVariateEstimate {
+        mean: mean_y - beta * (mean_c - control_expectation),
+        std_error: (sample_variance(&adjusted) / n as f64).sqrt(),
+        beta,
+    }
+}
+
+/// Monte Carlo estimate of `mean_pnl` after a given number of iterations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConvergencePoint {
+    pub iterations: usize,
+    pub mean_pnl: f64,
+    pub std_error: f64, // Sample standard deviation / sqrt(iterations); zero before two runs
+}
+
+/// Running mean and standard error of PnL after each of `iterations` sequential runs.
+///
+/// Runs one trajectory at a time from `rng` rather than in parallel, so entry `n - 1` is the
+/// estimate from the first `n` runs of the stream. Plotting `mean_pnl +/- 2 * std_error` against
+/// `iterations` shows when `iterations_per_param` is large enough.
+pub fn pnl_convergence<M: IntensityModel + ?Sized, R: Rng + ?Sized>(
+    params: &Parameters,
+    sim_config: &SimConfig,
+    intensity_model: &M,
+    iterations: usize,
+    rng: &mut R,
+) -> Vec<ConvergencePoint> {
+    let mut pnl = Welford::default();
+
+    (1..=iterations)
+        .map(|n| {
+            pnl.push(run_trajectory_with_rng(params, sim_config, intensity_model, rng).final_pnl);
+            ConvergencePoint {
+                iterations: n,
+                mean_pnl: pnl.mean(),
+                std_error: (pnl.sample_variance() / n as f64).sqrt(),
+            }
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{ExponentialIntensity, Parameters};
-    use crate::sim::SimConfig;
+    use crate::model::{
+        ExponentialIntensity, IntensityModel, LinearIntensity, MarketIntensity, Parameters,
+        PowerLawIntensity, QuotingModel, optimal_spread,
+    };
+    use crate::sim::{FillModel, PriceProcess, SimConfig};
 
     #[test]
     fn test_sweep_basic() {
@@ -163,15 +2633,10 @@ mod tests {
             t_horizon: 1.0,
             k: 1.5,
             a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
         };
 
-        let sim_config = SimConfig {
-            dt: 0.005,
-            num_steps: 200,
-            s_0: 100.0,
-            drift: 0.0,
-            latency_steps: 0,
-        };
+        let sim_config = SimConfig::builder().num_steps(200).build().unwrap();
 
         let sweep_config = SweepConfig {
             gammas: vec![0.01, 0.1],
@@ -180,6 +2645,7 @@ mod tests {
             drifts: vec![0.0],
             sim_config,
             iterations_per_param: 10,
+            ..SweepConfig::default()
         };
 
         let intensity_model = ExponentialIntensity {
@@ -187,8 +2653,2128 @@ mod tests {
             a: base_params.a,
         };
 
-        let results = run_sweep(base_params, &sweep_config, &intensity_model);
+        let results = run_sweep(base_params, &sweep_config, &intensity_model).unwrap();
         assert_eq!(results.len(), 2);
         assert!(results[0].mean_pnl != 0.0);
     }
+
+    #[test]
+    fn test_sweep_extra_axes() {
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let sim_config = SimConfig::builder().num_steps(100).build().unwrap();
+        let sweep_config = SweepConfig {
+            gammas: vec![0.1],
+            sigmas: vec![0.2],
+            ks: vec![1.5],
+            drifts: vec![0.0],
+            as_: vec![70.0, 140.0],
+            t_horizons: vec![0.5, 1.0],
+            latency_steps: vec![0, 2, 5],
+            sim_config,
+            iterations_per_param: 2,
+            ..SweepConfig::default()
+        };
+        assert_eq!(sweep_config.num_combinations(), 12);
+
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let results = run_sweep(base_params, &sweep_config, &intensity).unwrap();
+        assert_eq!(results.len(), 12);
+        for a in [70.0, 140.0] {
+            for t in [0.5, 1.0] {
+                for lat in [0, 2, 5] {
+                    assert!(
+                        results
+                            .iter()
+                            .any(|r| r.a == a && r.t_horizon == t && r.latency_steps == lat)
+                    );
+                }
+            }
+        }
+
+        // Empty axes fall back to the base values.
+        let defaults = SweepConfig {
+            as_: vec![],
+            t_horizons: vec![],
+            latency_steps: vec![],
+            ..sweep_config
+        };
+        assert_eq!(defaults.num_combinations(), 1);
+        let results = run_sweep(base_params, &defaults, &intensity).unwrap();
+        assert_eq!(results[0].a, 140.0);
+        assert_eq!(results[0].t_horizon, 1.0);
+        assert_eq!(results[0].latency_steps, 0);
+    }
+
+    #[test]
+    fn test_median_resists_skew() {
+        assert_eq!(percentile(&[1.0, 2.0, 3.0, 4.0], 0.5), 2.5);
+        assert_eq!(percentile(&[1.0, 2.0, 3.0, 4.0, 5.0], 0.25), 2.0);
+        assert_eq!(percentile(&[7.0], 0.75), 7.0);
+
+        // Mostly small gains with a rare large loss, like a trend leaving inventory stranded.
+        let mut pnls: Vec<f64> = (0..99).map(|i| 1.0 + (i % 10) as f64 * 0.1).collect();
+        pnls.push(-500.0);
+        let mean = pnls.iter().sum::<f64>() / pnls.len() as f64;
+        pnls.sort_by(f64::total_cmp);
+        let median = percentile(&pnls, 0.5);
+        let iqr = percentile(&pnls, 0.75) - percentile(&pnls, 0.25);
+
+        assert!(mean < 0.0);
+        assert!((1.0..2.0).contains(&median));
+        assert!(iqr < 1.0);
+        assert!(iqr < sample_variance(&pnls).sqrt() / 10.0);
+    }
+
+    #[test]
+    fn test_point_accumulator_matches_two_pass() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let sim_config = SimConfig::builder().num_steps(100).build().unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let runs = run_trajectories(&params, &sim_config, &intensity, 300);
+
+        let mut acc = PointAccumulator::default();
+        for res in &runs {
+            acc.push(res, sim_config.dt);
+        }
+        let pnls: Vec<f64> = runs.iter().map(|r| r.final_pnl).collect();
+        let final_qs: Vec<f64> = runs.iter().map(|r| r.final_inventory).collect();
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * (1.0 + b.abs());
+
+        assert_eq!(acc.pnls, pnls);
+        assert!(close(acc.pnl.mean(), pnls.iter().sum::<f64>() / 300.0));
+        assert!(close(acc.pnl.std_dev(), sample_variance(&pnls).sqrt()));
+        assert!(close(acc.pnl.sharpe_ratio(), sharpe_ratio(&pnls)));
+        assert!(close(
+            acc.final_q.mean(),
+            final_qs.iter().sum::<f64>() / 300.0
+        ));
+        assert!(close(
+            acc.final_q.std_dev(),
+            sample_variance(&final_qs).sqrt()
+        ));
+        let fills: usize = runs.iter().map(|r| r.fills.len()).sum();
+        assert_eq!(acc.fills, fills);
+    }
+
+    #[test]
+    fn test_pnl_sem_shrinks_with_iterations() {
+        let sim_config = SimConfig::builder().num_steps(100).build().unwrap();
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let sem_with = |iterations_per_param| {
+            let sweep_config = SweepConfig {
+                gammas: vec![0.1],
+                sigmas: vec![0.2],
+                ks: vec![1.5],
+                drifts: vec![0.0],
+                sim_config: sim_config.clone(),
+                iterations_per_param,
+                ..SweepConfig::default()
+            };
+            let res = run_sweep(base_params, &sweep_config, &intensity)
+                .unwrap()
+                .remove(0);
+            assert!(
+                (res.pnl_sem - res.std_pnl / (iterations_per_param as f64).sqrt()).abs() < 1e-12
+            );
+            res.pnl_sem
+        };
+
+        // Sixteen times the runs should cut the standard error by about four.
+        assert!(sem_with(640) < sem_with(40) / 2.0);
+    }
+
+    #[test]
+    fn test_control_variate_mean_removes_correlated_noise() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let controls: Vec<f64> = (0..2000).map(|_| rng.random_range(0.0..2.0)).collect();
+        let samples: Vec<f64> = controls
+            .iter()
+            .map(|&c| 5.0 + 2.0 * c + rng.random_range(-0.1..0.1))
+            .collect();
+
+        // E[C] = 1, so E[Y] = 7; the adjustment removes the noise the control explains.
+        let cv = control_variate_mean(&samples, &controls, 1.0);
+        let plain_sem = (sample_variance(&samples) / samples.len() as f64).sqrt();
+        assert!((cv.beta - 2.0).abs() < 0.01, "beta {}", cv.beta);
+        assert!((cv.mean - 7.0).abs() < 3.0 * cv.std_error, "{cv:?}");
+        assert!(cv.std_error < plain_sem / 10.0);
+
+        let flat = control_variate_mean(&samples, &vec![1.0; samples.len()], 0.0);
+        assert_eq!(flat.beta, 0.0);
+        assert!((flat.mean - samples.iter().sum::<f64>() / samples.len() as f64).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_control_variate_mean_is_unbiased() {
+        let sim_config = SimConfig::builder()
+            .num_steps(100)
+            .s_0(10.0)
+            .build()
+            .unwrap();
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let expectation = expected_spread_capture(&params, &sim_config, &intensity);
+        let runs = |n: usize| -> Vec<(f64, f64)> {
+            (0..n)
+                .into_par_iter()
+                .map(|_| {
+                    let res = run_trajectory(&params, &sim_config, &intensity);
+                    (res.final_pnl, res.spread_capture_control)
+                })
+                .collect()
+        };
+
+        // Brute-force reference for the true mean PnL, from independent runs.
+        let brute: Vec<f64> = runs(10_000).into_iter().map(|(pnl, _)| pnl).collect();
+        let truth = brute.iter().sum::<f64>() / brute.len() as f64;
+        let truth_sem = (sample_variance(&brute) / brute.len() as f64).sqrt();
+
+        // Averaging many small-batch estimates exposes any bias from fitting `beta` per batch.
+        let estimates: Vec<ControlVariateEstimate> = runs(10_000)
+            .chunks(100)
+            .map(|batch| {
+                let (pnls, controls): (Vec<f64>, Vec<f64>) = batch.iter().copied().unzip();
+                control_variate_mean(&pnls, &controls, expectation)
+            })
+            .collect();
+        let means: Vec<f64> = estimates.iter().map(|e| e.mean).collect();
+        let average = means.iter().sum::<f64>() / means.len() as f64;
+        let average_sem = (sample_variance(&means) / means.len() as f64).sqrt();
+        let tolerance = 4.0 * (average_sem.powi(2) + truth_sem.powi(2)).sqrt();
+        assert!(
+            (average - truth).abs() < tolerance,
+            "control variate mean {average} vs brute force {truth}"
+        );
+
+        // The control soaks up part of the fill noise, so the reported error shrinks.
+        let plain_sem = (sample_variance(&brute) / 100.0).sqrt();
+        let mean_cv_sem = estimates.iter().map(|e| e.std_error).sum::<f64>() / means.len() as f64;
+        assert!(mean_cv_sem < plain_sem);
+    }
+
+    #[test]
+    fn test_tail_risk_statistics() {
+        // PnLs -10..=9: the 5th percentile sits 0.95 of the way from -10 to -9.
+        let pnls: Vec<f64> = (-10..10).map(f64::from).collect();
+        let tail = TailRisk::from_sorted(&pnls, 0.95);
+        assert!((tail.lower - -9.05).abs() < 1e-12);
+        assert!((tail.upper - 8.05).abs() < 1e-12);
+        assert!((tail.var - 9.05).abs() < 1e-12);
+        assert_eq!(tail.cvar, 10.0);
+
+        // A looser level pulls more runs into the shortfall average.
+        let tail = TailRisk::from_sorted(&pnls, 0.8);
+        assert!((tail.lower - -6.2).abs() < 1e-12);
+        assert_eq!(tail.cvar, 8.5);
+
+        let single = TailRisk::from_sorted(&[5.0], 0.95);
+        assert_eq!(
+            (single.lower, single.upper, single.var, single.cvar),
+            (5.0, 5.0, -5.0, -5.0)
+        );
+        let empty = TailRisk::from_sorted(&[], 0.95);
+        assert_eq!((empty.var, empty.cvar), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_bootstrap_ci_matches_normal_theory() {
+        // For n standard normals the mean's 95% interval is about +/- 1.96 / sqrt(n) wide.
+        let mut rng = StdRng::seed_from_u64(17);
+        let samples: Vec<f64> = (0..400)
+            .map(|_| rng.sample::<f64, _>(rand_distr::StandardNormal))
+            .collect();
+        let mean = |xs: &[f64]| xs.iter().sum::<f64>() / xs.len() as f64;
+        let (lo, hi) = bootstrap_ci(&samples, mean, 2000, 1);
+
+        let centre = mean(&samples);
+        let half_width = 1.96 * sample_variance(&samples).sqrt() / 20.0;
+        assert!(lo < centre && centre < hi);
+        assert!(
+            ((hi - lo) / 2.0 - half_width).abs() < 0.15 * half_width,
+            "({lo}, {hi})"
+        );
+        assert!(((lo + hi) / 2.0 - centre).abs() < 0.2 * half_width);
+
+        assert_eq!(bootstrap_ci(&samples, mean, 2000, 1), (lo, hi));
+        assert_eq!(bootstrap_ci(&[3.0; 10], mean, 50, 1), (3.0, 3.0));
+        assert!(bootstrap_ci(&[], mean, 50, 1).0.is_nan());
+    }
+
+    #[test]
+    fn test_sweep_bootstrap_intervals_bracket_estimates() {
+        let sim_config = SimConfig::builder().num_steps(100).build().unwrap();
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let result_with = |bootstrap_resamples| {
+            let sweep_config = SweepConfig {
+                gammas: vec![0.1],
+                sigmas: vec![0.2],
+                ks: vec![1.5],
+                drifts: vec![0.0],
+                sim_config: sim_config.clone(),
+                iterations_per_param: 200,
+                bootstrap_resamples,
+                ..SweepConfig::default()
+            };
+            run_sweep(base_params, &sweep_config, &intensity)
+                .unwrap()
+                .remove(0)
+        };
+
+        let res = result_with(500);
+        assert!(res.mean_pnl_ci_low < res.mean_pnl && res.mean_pnl < res.mean_pnl_ci_high);
+        assert!(res.sharpe_ci_low < res.sharpe_ratio && res.sharpe_ratio < res.sharpe_ci_high);
+        // The interval is roughly +/- 2 standard errors.
+        let width = res.mean_pnl_ci_high - res.mean_pnl_ci_low;
+        assert!((width / (4.0 * res.pnl_sem) - 1.0).abs() < 0.25, "{width}");
+
+        let res = result_with(0);
+        assert!(res.mean_pnl_ci_low.is_nan() && res.sharpe_ci_high.is_nan());
+        assert!(res.pnl_p05 <= res.median_pnl && res.median_pnl <= res.pnl_p95);
+        assert!(res.cvar_95 >= res.var_95);
+    }
+
+    #[test]
+    fn test_sweep_downside_ratios() {
+        let sim_config = SimConfig::builder().num_steps(100).build().unwrap();
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let result_with = |sortino_mar| {
+            let sweep_config = SweepConfig {
+                gammas: vec![0.1],
+                sigmas: vec![0.2],
+                ks: vec![1.5],
+                drifts: vec![0.0],
+                sim_config: sim_config.clone(),
+                iterations_per_param: 200,
+                sortino_mar,
+                ..SweepConfig::default()
+            };
+            run_sweep(base_params, &sweep_config, &intensity)
+                .unwrap()
+                .remove(0)
+        };
+
+        let res = result_with(0.0);
+        assert!(res.max_drawdown > 0.0);
+        assert_eq!(res.calmar_ratio, res.mean_pnl / res.max_drawdown);
+        assert!(res.sortino_ratio.is_finite());
+        assert_eq!(res.sortino_ratio.signum(), res.mean_pnl.signum());
+
+        assert!(res.mean_fills_per_run > 0.0);
+        assert!(res.mean_traded_notional > 90.0 * res.mean_fills_per_run);
+        assert!(res.mean_spread_capture > 0.0);
+
+        // No run loses a million, so nothing falls short of that MAR.
+        assert_eq!(result_with(-1e6).sortino_ratio, f64::INFINITY);
+    }
+
+    #[test]
+    fn test_antithetic_sampling_reduces_sem() {
+        let sim_config = SimConfig::builder()
+            .num_steps(200)
+            .s_0(10.0)
+            .build()
+            .unwrap();
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let sem_with = |antithetic| {
+            let sweep_config = SweepConfig {
+                gammas: vec![0.1],
+                sigmas: vec![0.2],
+                ks: vec![1.5],
+                drifts: vec![0.0],
+                sim_config: sim_config.clone(),
+                iterations_per_param: 1000,
+                antithetic,
+                ..SweepConfig::default()
+            };
+            run_sweep(base_params, &sweep_config, &intensity)
+                .unwrap()
+                .remove(0)
+                .pnl_sem
+        };
+
+        // At s_0 = 10 fills are driven mostly by the arrival draws, so PnL is close to linear in
+        // the price shocks; at s_0 = 100 the inventory term is even in them and pairing does not help.
+        let (plain, mirrored) = (sem_with(false), sem_with(true));
+        assert!(mirrored < plain, "antithetic {mirrored} vs plain {plain}");
+    }
+
+    #[test]
+    fn test_sweep_reports_progress() {
+        let sim_config = SimConfig::builder().num_steps(50).build().unwrap();
+        let sweep_config = SweepConfig {
+            gammas: vec![0.01, 0.1, 1.0],
+            sigmas: vec![0.2],
+            ks: vec![1.5],
+            drifts: vec![0.0, 0.05],
+            sim_config,
+            iterations_per_param: 2,
+            ..SweepConfig::default()
+        };
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+
+        let reports = std::sync::Mutex::new(Vec::new());
+        let results =
+            run_sweep_with_progress(base_params, &sweep_config, &intensity, |done, total| {
+                reports.lock().unwrap().push((done, total));
+            })
+            .unwrap();
+        assert_eq!(results.results().count(), 6);
+
+        let mut reports = reports.into_inner().unwrap();
+        reports.sort();
+        let expected: Vec<(usize, usize)> = (1..=6).map(|done| (done, 6)).collect();
+        assert_eq!(reports, expected);
+    }
+
+    #[test]
+    fn test_streaming_sweep_sends_every_result() {
+        let sim_config = SimConfig::builder().num_steps(50).build().unwrap();
+        let sweep_config = SweepConfig {
+            gammas: vec![0.01, 0.1, 1.0],
+            sigmas: vec![0.2, 0.4],
+            ks: vec![1.5],
+            drifts: vec![0.0],
+            sim_config,
+            iterations_per_param: 2,
+            ..SweepConfig::default()
+        };
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let errors = run_sweep_streaming(base_params, &sweep_config, &intensity, tx).unwrap();
+        assert!(errors.is_empty());
+
+        let mut streamed: Vec<(f64, f64)> = rx.iter().map(|r| (r.gamma, r.sigma)).collect();
+        streamed.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let expected: Vec<(f64, f64)> = run_sweep(base_params, &sweep_config, &intensity)
+            .unwrap()
+            .iter()
+            .map(|r| (r.gamma, r.sigma))
+            .collect();
+        assert_eq!(streamed, expected);
+    }
+
+    #[test]
+    fn test_sweep_until_stops_early() {
+        let sweep_config = SweepConfig {
+            gammas: (1..=40).map(|i| i as f64 * 0.025).collect(),
+            sigmas: vec![0.2],
+            ks: vec![1.5],
+            drifts: vec![0.0],
+            sim_config: SimConfig::builder().num_steps(50).build().unwrap(),
+            iterations_per_param: 5,
+            seed: Some(4),
+            ..SweepConfig::default()
+        };
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        // Two workers have at most two combinations in flight when the first hit lands; run
+        // sequentially, the sweep stops right at it.
+        #[cfg(feature = "parallel")]
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        let until = |min_gamma: f64| {
+            let run = || {
+                run_sweep_until(base_params, &sweep_config, &intensity, |r| {
+                    r.gamma >= min_gamma
+                })
+                .unwrap()
+            };
+            #[cfg(feature = "parallel")]
+            return pool.install(run);
+            #[cfg(not(feature = "parallel"))]
+            run()
+        };
+
+        let stopped = until(0.5);
+        // The disabled bootstrap leaves NaN intervals, so results are compared by key fields.
+        let key = |r: &SweepResult| (r.gamma, r.mean_pnl);
+        let hit = stopped.hit.expect("half the grid satisfies the predicate");
+        assert!(hit.gamma >= 0.5);
+        let completed: Vec<(f64, f64)> = stopped.results.iter().map(key).collect();
+        assert!(completed.contains(&key(&hit)));
+        assert!(completed.len() < 40, "{}", completed.len());
+        let full: Vec<(f64, f64)> = run_sweep(base_params, &sweep_config, &intensity)
+            .unwrap()
+            .iter()
+            .map(key)
+            .collect();
+        assert!(completed.iter().all(|r| full.contains(r)));
+
+        let exhausted = until(1.5);
+        assert!(exhausted.hit.is_none());
+        assert_eq!(exhausted.results.iter().map(key).collect::<Vec<_>>(), full);
+    }
+
+    #[test]
+    fn test_convergence_std_error_shrinks_like_sqrt_n() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let sim_config = SimConfig::builder().num_steps(100).build().unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let path = pnl_convergence(
+            &params,
+            &sim_config,
+            &model,
+            800,
+            &mut StdRng::seed_from_u64(5),
+        );
+
+        assert_eq!(path.len(), 800);
+        assert_eq!(path[0].std_error, 0.0);
+        let pnls: Vec<f64> = {
+            let mut rng = StdRng::seed_from_u64(5);
+            (0..800)
+                .map(|_| run_trajectory_with_rng(&params, &sim_config, &model, &mut rng).final_pnl)
+                .collect()
+        };
+        let last = path[799];
+        assert!((last.mean_pnl - pnls.iter().sum::<f64>() / 800.0).abs() < 1e-9);
+        assert!((last.std_error - (sample_variance(&pnls) / 800.0).sqrt()).abs() < 1e-9);
+
+        // Quadrupling the sample size should roughly halve the standard error.
+        let ratio = path[799].std_error / path[199].std_error;
+        assert!((0.4..0.6).contains(&ratio), "ratio {ratio}");
+    }
+
+    #[test]
+    fn test_sweep_axes_records_coordinates() {
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let sim_config = SimConfig::builder().num_steps(100).build().unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let axes = [
+            SweepAxis::Gamma(vec![0.01, 0.1]),
+            SweepAxis::OrderSize(vec![1, 5, 0]),
+        ];
+
+        let rows = try_run_sweep_axes(base_params, &sim_config, &axes, 4, &intensity);
+        assert_eq!(rows.len(), 6);
+        let (point, row) = &rows[4];
+        assert_eq!(point.get("gamma"), Some(0.1));
+        assert_eq!(point.get("order_size"), Some(5.0));
+        assert_eq!(point.get("sigma"), None);
+        assert_eq!(row.as_ref().unwrap().gamma, 0.1);
+        assert!(matches!(
+            rows[5].1,
+            Err(SweepError {
+                kind: SweepErrorKind::InvalidAxis(_),
+                ..
+            })
+        ));
+
+        let points = run_sweep_axes(base_params, &sim_config, &axes, 4, &intensity);
+        assert_eq!(points.len(), 4);
+    }
+
+    #[test]
+    fn test_drift_axis_reaches_drift_adjusted_maker() {
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let sweep_config = SweepConfig {
+            gammas: vec![0.1],
+            sigmas: vec![0.2],
+            ks: vec![1.5],
+            drifts: vec![0.0, 0.05],
+            sim_config: SimConfig::builder().num_steps(100).build().unwrap(),
+            iterations_per_param: 20,
+            seed: Some(3),
+            ..SweepConfig::default()
+        };
+        let mean_pnls = |quoting_model| -> Vec<f64> {
+            let params = Parameters {
+                quoting_model,
+                ..base_params
+            };
+            run_sweep(params, &sweep_config, &intensity)
+                .unwrap()
+                .iter()
+                .map(|r| r.mean_pnl)
+                .collect()
+        };
+
+        // On the same seeds the two makers only part ways where the swept drift is nonzero.
+        let plain = mean_pnls(QuotingModel::AvellanedaStoikov);
+        let adjusted = mean_pnls(QuotingModel::DriftAdjusted);
+        assert_eq!(adjusted[0], plain[0]);
+        assert_ne!(adjusted[1], plain[1]);
+    }
+
+    #[test]
+    fn test_sweep_rejects_invalid_swept_params() {
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let sweep_config = SweepConfig {
+            gammas: vec![-1.0, 0.1],
+            sigmas: vec![0.2],
+            ks: vec![1.5],
+            drifts: vec![0.0],
+            sim_config: SimConfig::builder().num_steps(50).build().unwrap(),
+            iterations_per_param: 2,
+            ..SweepConfig::default()
+        };
+
+        let rows = try_run_sweep(base_params, &sweep_config, &intensity).rows;
+        assert_eq!(
+            rows[0].as_ref().unwrap_err().kind,
+            SweepErrorKind::InvalidParams(ParamError::NotPositive {
+                name: "gamma",
+                value: -1.0
+            })
+        );
+        assert!(rows[1].is_ok());
+
+        let empty = SweepConfig {
+            gammas: vec![],
+            ..sweep_config
+        };
+        assert_eq!(
+            run_sweep_with_progress(base_params, &empty, &intensity, |_, _| {}).unwrap_err(),
+            ConfigError::EmptyAxis("gammas")
+        );
+        let (tx, _rx) = std::sync::mpsc::channel();
+        assert!(run_sweep_streaming(base_params, &empty, &intensity, tx).is_err());
+        assert!(run_sweep_until(base_params, &empty, &intensity, |_| true).is_err());
+    }
+
+    #[test]
+    fn test_tighter_spread_fills_more() {
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let sim_config = SimConfig::builder().num_steps(200).build().unwrap();
+        let market = ExponentialIntensity { k: 1.5, a: 140.0 };
+
+        // A larger assumed `k` shrinks the quoted spread against the same market.
+        let axes = [SweepAxis::K(vec![0.5, 5.0])];
+        let results = run_sweep_axes(base_params, &sim_config, &axes, 50, &market);
+        let (wide, tight) = (&results[0].1, &results[1].1);
+
+        assert!(tight.mean_fills_per_run > wide.mean_fills_per_run);
+        assert!(tight.mean_realized_half_spread < wide.mean_realized_half_spread);
+
+        // A market that never trades leaves every per-fill average at zero rather than NaN.
+        let dead = ExponentialIntensity { k: 1.5, a: 0.0 };
+        let idle = &run_sweep_axes(base_params, &sim_config, &axes, 5, &dead)[0].1;
+        assert_eq!(idle.mean_fills_per_run, 0.0);
+        assert_eq!(idle.mean_traded_notional, 0.0);
+        assert_eq!(idle.mean_spread_capture, 0.0);
+        assert_eq!(idle.mean_realized_half_spread, 0.0);
+    }
+
+    #[test]
+    fn test_rolling_sharpe() {
+        let path = |wealth: &[f64]| SimResult {
+            trajectory: wealth[..wealth.len() - 1]
+                .iter()
+                .map(|&w| StepRecord {
+                    wealth: w,
+                    ..Default::default()
+                })
+                .collect(),
+            final_pnl: wealth[wealth.len() - 1],
+            ..Default::default()
+        };
+
+        // Steadily rising wealth with small wobbles: increments alternate 1.1 and 0.9.
+        let rising: Vec<f64> = (0..21)
+            .map(|i| i as f64 + if i % 2 == 1 { 0.1 } else { 0.0 })
+            .collect();
+        let sharpe = rolling_sharpe(&path(&rising), 10);
+        assert_eq!(sharpe.len(), 11);
+        assert!(sharpe.iter().all(|&s| s > 5.0), "{sharpe:?}");
+
+        // A flat stretch has no risk-adjusted return at all.
+        let flat = rolling_sharpe(&path(&[3.0; 12]), 5);
+        assert!(flat.iter().all(|&s| s == 0.0));
+
+        assert!(rolling_sharpe(&path(&rising), 0).is_empty());
+        assert!(rolling_sharpe(&path(&rising), 21).is_empty());
+    }
+
+    #[test]
+    fn test_trajectory_profile_masks_stopped_runs() {
+        let row = |time, inventory, wealth| StepRecord {
+            time,
+            inventory,
+            wealth,
+            bid_price: 99.0,
+            ask_price: 101.0,
+            ..Default::default()
+        };
+        let run = |rows: Vec<StepRecord>, stopped_at| SimResult {
+            trajectory: rows,
+            stopped_at,
+            ..Default::default()
+        };
+        let mut halted = row(2.0, 0.0, -5.0);
+        (halted.bid_price, halted.ask_price) = (f64::NAN, f64::NAN);
+        let results = [
+            run(
+                vec![row(0.0, 0.0, 0.0), row(1.0, 2.0, 1.0), row(2.0, 4.0, 3.0)],
+                None,
+            ),
+            run(
+                vec![row(0.0, 0.0, 0.0), row(1.0, -2.0, -5.0), halted],
+                Some(1),
+            ),
+            run(vec![row(0.0, 0.0, 0.0)], None),
+        ];
+
+        let profile = trajectory_profile(&results);
+        assert_eq!(profile.time, [0.0, 1.0, 2.0]);
+        assert_eq!(profile.active_runs, [3, 2, 1]);
+        assert_eq!(profile.inventory_mean, [0.0, 0.0, 4.0]);
+        assert_eq!(profile.inventory_std, [0.0, 8.0f64.sqrt(), 0.0]);
+        assert_eq!(profile.wealth_mean, [0.0, -2.0, 3.0]);
+        // The halted row's NaN quotes never reach the spread.
+        assert_eq!(profile.spread_mean, [2.0, 2.0, 2.0]);
+        assert_eq!(trajectory_profile(&[]), Profile::default());
+    }
+
+    #[test]
+    fn test_sweep_keeps_profiles_on_request() {
+        let sim_config = SimConfig::builder().num_steps(100).build().unwrap();
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let result_with = |keep_profiles| {
+            let sweep_config = SweepConfig {
+                gammas: vec![0.1],
+                sigmas: vec![0.2],
+                ks: vec![1.5],
+                drifts: vec![0.0],
+                sim_config: sim_config.clone(),
+                iterations_per_param: 40,
+                keep_profiles,
+                ..SweepConfig::default()
+            };
+            run_sweep(base_params, &sweep_config, &intensity)
+                .unwrap()
+                .remove(0)
+        };
+
+        assert!(result_with(false).profile.is_none());
+        let res = result_with(true);
+        let profile = res.profile.unwrap();
+        assert_eq!(profile.time.len(), 100);
+        assert!(profile.active_runs.iter().all(|&n| n == 40));
+        // Every run starts flat, and inventory spreads out as fills accumulate.
+        assert_eq!(
+            (profile.inventory_mean[0], profile.inventory_std[0]),
+            (0.0, 0.0)
+        );
+        assert!(profile.inventory_std[99] > 0.0);
+        // The spread narrows toward the horizon as the inventory risk term decays.
+        assert!(profile.spread_mean[99] < profile.spread_mean[0]);
+
+        let direct =
+            trajectory_profile(&run_trajectories(&base_params, &sim_config, &intensity, 40));
+        assert_eq!(direct.active_runs.len(), 100);
+    }
+
+    #[test]
+    fn test_sharpe_annualization() {
+        // 400 steps of 0.01 make runs of 4 time units, 63 of them in a 252-unit year.
+        let sim_config = SimConfig::builder()
+            .num_steps(400)
+            .dt(0.01)
+            .build()
+            .unwrap();
+        assert!((annualization_factor(&sim_config, 252.0) - 63.0).abs() < 1e-9);
+
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let result_with = |annualization_factor| {
+            let sweep_config = SweepConfig {
+                gammas: vec![0.1],
+                sigmas: vec![0.2],
+                ks: vec![1.5],
+                drifts: vec![0.0],
+                sim_config: sim_config.clone(),
+                iterations_per_param: 20,
+                seed: Some(1),
+                annualization_factor,
+                ..SweepConfig::default()
+            };
+            run_sweep(base_params, &sweep_config, &intensity)
+                .unwrap()
+                .remove(0)
+        };
+
+        let derived = result_with(None);
+        let expected = derived.sharpe_ratio * 63f64.sqrt();
+        assert!((derived.sharpe_annualized - expected).abs() < 1e-9);
+        let explicit = result_with(Some(4.0));
+        assert_eq!(explicit.sharpe_ratio, derived.sharpe_ratio);
+        assert!((explicit.sharpe_annualized - 2.0 * explicit.sharpe_ratio).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_sweep_models_share_grid_and_seeds() {
+        let sim_config = SimConfig::builder().num_steps(100).build().unwrap();
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let sweep_config = SweepConfig {
+            gammas: vec![0.1, 1.0],
+            sigmas: vec![0.2],
+            ks: vec![1.5],
+            drifts: vec![0.0, 0.05],
+            sim_config,
+            iterations_per_param: 20,
+            seed: Some(3),
+            ..SweepConfig::default()
+        };
+        let exponential = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let power_law = PowerLawIntensity {
+            a: 140.0,
+            k: 1.5,
+            beta: 2.0,
+        };
+
+        let results = run_sweep_models(
+            base_params,
+            &sweep_config,
+            &[("exponential", &exponential), ("power_law", &power_law)],
+        )
+        .unwrap();
+        assert_eq!(results.len(), 8);
+        let names: Vec<&str> = results.iter().map(|r| r.model_name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["exponential"; 4]
+                .into_iter()
+                .chain(["power_law"; 4])
+                .collect::<Vec<_>>()
+        );
+
+        // The same grid under each model, and each group matches a single-model seeded sweep.
+        let (first, second) = results.split_at(4);
+        for (a, b) in first.iter().zip(second) {
+            assert_eq!((a.gamma, a.drift), (b.gamma, b.drift));
+        }
+        let alone = run_sweep(base_params, &sweep_config, &power_law).unwrap();
+        for (tagged, alone) in second.iter().zip(&alone) {
+            assert!(alone.model_name.is_empty());
+            assert_eq!(tagged.mean_pnl, alone.mean_pnl);
+        }
+        // Only the books differ, and they fill the same quotes at different rates.
+        assert_ne!(second[0].mean_fills_per_run, first[0].mean_fills_per_run);
+    }
+
+    #[test]
+    fn test_baseline_runs_on_the_same_paths() {
+        let sim_config = SimConfig::builder().num_steps(100).build().unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let sweep = |quoting_model, baseline, antithetic| {
+            let base_params = Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, quoting_model).unwrap();
+            let sweep_config = SweepConfig {
+                gammas: vec![0.1],
+                sigmas: vec![0.2],
+                ks: vec![1.5],
+                drifts: vec![0.0],
+                sim_config: sim_config.clone(),
+                iterations_per_param: 50,
+                antithetic,
+                baseline,
+                ..SweepConfig::default()
+            };
+            run_sweep(base_params, &sweep_config, &intensity)
+                .unwrap()
+                .remove(0)
+        };
+
+        let plain = sweep(QuotingModel::AvellanedaStoikov, false, false);
+        assert!(plain.baseline_mean_pnl.is_nan() && plain.pnl_uplift.is_nan());
+
+        // A strategy that is its own baseline replays identical paths, even unseeded.
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let fixed = QuotingModel::FixedSpread {
+            spread: mean_optimal_spread(&params, sim_config.num_steps as f64 * sim_config.dt),
+        };
+        for antithetic in [false, true] {
+            let res = sweep(fixed, true, antithetic);
+            assert_eq!(res.pnl_uplift, 0.0);
+            assert_eq!(res.baseline_std_pnl, res.std_pnl);
+        }
+
+        let res = sweep(QuotingModel::AvellanedaStoikov, true, false);
+        assert!(res.baseline_std_pnl > 0.0);
+        assert_eq!(res.pnl_uplift, res.mean_pnl - res.baseline_mean_pnl);
+    }
+
+    #[test]
+    fn test_seeded_sweep_is_reproducible() {
+        let sim_config = SimConfig::builder().num_steps(100).build().unwrap();
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let sweep = |seed, antithetic| {
+            let sweep_config = SweepConfig {
+                gammas: vec![0.01, 0.1, 1.0],
+                sigmas: vec![0.1, 0.2],
+                ks: vec![1.5],
+                drifts: vec![0.0, 0.05],
+                sim_config: sim_config.clone(),
+                iterations_per_param: 30,
+                antithetic,
+                // Resampled too, and keeps the CIs finite so whole results compare equal; the
+                // baseline likewise.
+                bootstrap_resamples: 50,
+                keep_profiles: true,
+                seed,
+                baseline: true,
+                histogram_bins: 10,
+                ..SweepConfig::default()
+            };
+            run_sweep(base_params, &sweep_config, &intensity).unwrap()
+        };
+
+        for antithetic in [false, true] {
+            let first = sweep(Some(11), antithetic);
+            assert_eq!(first.len(), 12);
+            assert!(first.iter().all(|r| {
+                let hist = r.pnl_histogram.as_ref().unwrap();
+                hist.counts.len() == 10 && hist.total() == 30
+            }));
+            assert_eq!(first, sweep(Some(11), antithetic));
+            assert_ne!(first, sweep(Some(12), antithetic));
+        }
+        assert_ne!(derive_seed(11, 0), derive_seed(11, 1));
+        assert_ne!(derive_seed(11, 0), derive_seed(12, 0));
+    }
+
+    #[test]
+    fn test_chunked_point_matches_across_thread_counts() {
+        let sim_config = SimConfig::builder().num_steps(50).build().unwrap();
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        // One combination with enough draws for several chunks, antithetic or not.
+        let iterations = 2 * DRAWS_PER_CHUNK + 7;
+        let sweep = |antithetic| {
+            let sweep_config = SweepConfig {
+                gammas: vec![0.1],
+                sigmas: vec![0.2],
+                ks: vec![1.5],
+                drifts: vec![0.0],
+                sim_config: sim_config.clone(),
+                iterations_per_param: iterations,
+                antithetic,
+                bootstrap_resamples: 20,
+                keep_profiles: true,
+                seed: Some(5),
+                baseline: true,
+                histogram_bins: 8,
+                ..SweepConfig::default()
+            };
+            run_sweep(base_params, &sweep_config, &intensity)
+                .unwrap()
+                .remove(0)
+        };
+        #[cfg(feature = "parallel")]
+        let on_threads = |threads: usize, antithetic: bool| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| sweep(antithetic))
+        };
+        #[cfg(not(feature = "parallel"))]
+        let on_threads = |_threads: usize, antithetic: bool| sweep(antithetic);
+
+        for antithetic in [false, true] {
+            let single = on_threads(1, antithetic);
+            let runs = if antithetic {
+                iterations + 1
+            } else {
+                iterations
+            };
+            assert_eq!(single.pnl_histogram.as_ref().unwrap().total(), runs);
+            assert_eq!(single, on_threads(4, antithetic));
+        }
+
+        // The merged mean agrees with pushing every run's PnL in order, on the point's own seed.
+        let mut direct = Welford::default();
+        simulate_draws(
+            &mut Workspaces::default(),
+            &base_params,
+            &sim_config,
+            &intensity,
+            false,
+            Some(derive_seed(5, 0)),
+            0..iterations,
+            |res| direct.push(res.final_pnl),
+        );
+        let chunked = on_threads(4, false);
+        assert!((chunked.mean_pnl - direct.mean()).abs() < 1e-9);
+        assert!((chunked.std_pnl - direct.std_dev()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_markouts_negative_before_adverse_moves() {
+        // Each one-unit drop crosses the bid, and the price keeps falling after every purchase.
+        let prices: Vec<f64> = (0..12).map(|i| 100.0 - i as f64).collect();
+        let sim_config = SimConfig::builder()
+            .num_steps(prices.len())
+            .price_process(PriceProcess::Replay {
+                prices: prices.as_slice().into(),
+            })
+            .fill_model(FillModel::CrossDeterministic)
+            .build()
+            .unwrap();
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let res = run_trajectory(
+            &params,
+            &sim_config,
+            &ExponentialIntensity { k: 1.5, a: 140.0 },
+        );
+        assert!(res.fills.iter().all(|fill| fill.side == Side::Bid));
+        assert_eq!(res.ask_fills, 0);
+
+        let marks = markouts(&res, &[1, 5, 10, 20]);
+        assert_eq!(
+            marks.iter().map(|m| m.horizon).collect::<Vec<_>>(),
+            [1, 5, 10, 20]
+        );
+        assert!(marks[0].fills > marks[1].fills && marks[1].fills > 0);
+        // The mid falls one unit per step after every fill.
+        assert_eq!(marks[0].mean, -1.0);
+        assert_eq!(marks[1].mean, -5.0);
+        // Only the first fill, on step 0, is early enough to observe 10 steps of the series.
+        assert_eq!((marks[2].fills, marks[2].mean), (1, -10.0));
+        assert_eq!((marks[3].fills, marks[3].mean), (0, 0.0));
+    }
+
+    #[test]
+    fn test_time_weighted_inventory() {
+        let step = |time: f64, inventory: f64| StepRecord {
+            time,
+            mid_price: 100.0,
+            inventory,
+            cash: 0.0,
+            wealth: 0.0,
+            bid_price: 99.0,
+            ask_price: 101.0,
+            ..Default::default()
+        };
+
+        // Constant dt: identical to the simple step average.
+        let even = [step(0.0, 1.0), step(0.5, -3.0), step(1.0, 2.0)];
+        assert!((time_weighted_abs_inventory(&even, 1.5) - 2.0).abs() < 1e-12);
+
+        // |q| = 4 held for 0.9 of the unit horizon dominates the uneven average.
+        let uneven = [step(0.0, 0.0), step(0.1, 4.0)];
+        assert!((time_weighted_abs_inventory(&uneven, 1.0) - 3.6).abs() < 1e-12);
+
+        let base_params = Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+        let sweep_config = SweepConfig {
+            gammas: vec![0.1],
+            sigmas: vec![0.2],
+            ks: vec![1.5],
+            drifts: vec![0.0],
+            sim_config: SimConfig::builder().num_steps(200).build().unwrap(),
+            iterations_per_param: 10,
+            ..SweepConfig::default()
+        };
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let result = &run_sweep(base_params, &sweep_config, &model).unwrap()[0];
+        assert!(result.mean_abs_inventory > 0.0);
+        assert!((result.time_weighted_abs_inventory - result.mean_abs_inventory).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_inventory_variance_separates_churn_from_steady_positions() {
+        let run = |inventories: &[f64]| SimResult {
+            trajectory: inventories
+                .iter()
+                .enumerate()
+                .map(|(i, &inventory)| StepRecord {
+                    time: i as f64 * 0.5,
+                    inventory,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let aggregate = |runs: &[SimResult]| {
+            let mut acc = PointAccumulator::default();
+            for res in runs {
+                acc.push(res, 0.5);
+            }
+            let n = runs.len() as f64;
+            (acc.abs_q / n, acc.q_variance / n)
+        };
+
+        // Both hold |q| = 2 throughout, one flipping sides every step and one never moving.
+        let oscillating = [run(&[2.0, -2.0, 2.0, -2.0]), run(&[-2.0, 2.0, -2.0, 2.0])];
+        let steady = [run(&[2.0; 4]), run(&[-2.0; 4])];
+        let (osc_abs_q, osc_variance) = aggregate(&oscillating);
+        let (steady_abs_q, steady_variance) = aggregate(&steady);
+
+        assert_eq!(osc_abs_q, steady_abs_q);
+        assert_eq!(steady_variance, 0.0);
+        assert!((osc_variance - 16.0 / 3.0).abs() < 1e-12);
+        assert_eq!(inventory_variance(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_inventory_autocorr_lag1_recovers_ar1_coefficient() {
+        let path = |inventories: &[f64]| -> Vec<StepRecord> {
+            inventories
+                .iter()
+                .map(|&inventory| StepRecord {
+                    inventory,
+                    ..Default::default()
+                })
+                .collect()
+        };
+        // q(t+1) = 0.7 q(t) + e(t) has lag-1 autocorrelation 0.7.
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut q = 0.0;
+        let ar1: Vec<f64> = (0..20_000)
+            .map(|_| {
+                q = 0.7 * q + rng.sample::<f64, _>(rand_distr::StandardNormal);
+                q
+            })
+            .collect();
+        assert!((inventory_autocorr_lag1(&path(&ar1)) - 0.7).abs() < 0.02);
+
+        // Flipping sides every step is perfectly anti-persistent; constant or single-step paths
+        // have no variance and report zero.
+        let flips = path(&[1.0, -1.0, 1.0, -1.0]);
+        assert!((inventory_autocorr_lag1(&flips) + 0.75).abs() < 1e-12);
+        assert_eq!(inventory_autocorr_lag1(&path(&[3.0; 10])), 0.0);
+        assert_eq!(inventory_autocorr_lag1(&path(&[3.0])), 0.0);
+    }
+
+    #[test]
+    fn test_running_penalty_reduces_inventory() {
+        let sim_config = SimConfig::builder().num_steps(200).build().unwrap();
+
+        let sweep_config = SweepConfig {
+            gammas: vec![0.1],
+            sigmas: vec![0.2],
+            ks: vec![1.5],
+            drifts: vec![0.0],
+            sim_config,
+            iterations_per_param: 200,
+            ..SweepConfig::default()
+        };
+
+        let intensity_model = ExponentialIntensity { k: 1.5, a: 140.0 };
+
+        let run_with_phi = |phi: f64| {
+            let base_params = Parameters {
+                gamma: 0.1,
+                sigma: 0.2,
+                t_horizon: 1.0,
+                k: 1.5,
+                a: 140.0,
+                quoting_model: QuotingModel::RunningPenalty { phi },
+            };
+            run_sweep(base_params, &sweep_config, &intensity_model)
+                .unwrap()
+                .remove(0)
+        };
+
+        let loose = run_with_phi(0.001);
+        let tight = run_with_phi(50.0);
+
+        assert!(tight.mean_abs_inventory < loose.mean_abs_inventory);
+        assert!(tight.mean_running_penalty > 0.0);
+        assert!(
+            (tight.mean_objective - (tight.mean_pnl - tight.mean_running_penalty)).abs() < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_inventory_skew_beats_symmetric_quotes() {
+        let sim_config = SimConfig::builder().num_steps(200).build().unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+
+        // Both strategies see the same price path and fill draws on each seed.
+        let terminal_inventory_std = |quoting_model| {
+            let params = Parameters::new(0.5, 0.5, 1.0, 1.5, 140.0, quoting_model).unwrap();
+            let terminal: Vec<f64> = (0..200)
+                .map(|seed| {
+                    let res = run_trajectory_with_rng(
+                        &params,
+                        &sim_config,
+                        &model,
+                        &mut StdRng::seed_from_u64(seed),
+                    );
+                    res.trajectory.last().unwrap().inventory
+                })
+                .collect();
+            sample_variance(&terminal).sqrt()
+        };
+
+        let skewed = terminal_inventory_std(QuotingModel::AvellanedaStoikov);
+        let symmetric = terminal_inventory_std(QuotingModel::SymmetricMid);
+        assert!(
+            skewed < symmetric,
+            "skewed {skewed} vs symmetric {symmetric}"
+        );
+    }
+
+    #[test]
+    fn test_sensitivity_signs() {
+        let sim_config = SimConfig::builder().num_steps(200).build().unwrap();
+        let base_params =
+            Parameters::new(2.0, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let report = |sim_config: &SimConfig| {
+            sensitivity(
+                &base_params,
+                sim_config,
+                &intensity,
+                &SensitivityBumps::default(),
+                300,
+                4,
+            )
+            .unwrap()
+        };
+
+        let base = report(&sim_config);
+        assert_eq!(base.n, 300);
+        assert_eq!(base.gamma.step, 0.1);
+        assert!((base.k.step - 0.075).abs() < 1e-12);
+        assert!(base.gamma.std_error > 0.0);
+        assert_eq!(base, report(&sim_config));
+        // Above gamma ~ 1 the spread keeps tightening with gamma, so mean PnL falls.
+        assert!(base.gamma.estimate < 0.0, "{:?}", base.gamma);
+
+        // A trend fills the quotes on the side it runs toward, leaving the agent short in a
+        // rally and long in a sell-off; the stronger the trend, the more that inventory loses.
+        let trending = |drift| {
+            report(&SimConfig {
+                drift,
+                ..sim_config.clone()
+            })
+            .drift
+        };
+        let up = trending(2.0);
+        let down = trending(-2.0);
+        assert!(up.estimate + 2.0 * up.std_error < 0.0, "{up:?}");
+        assert!(down.estimate - 2.0 * down.std_error > 0.0, "{down:?}");
+    }
+
+    #[test]
+    fn test_compare_parameter_sets_pairs_paths() {
+        let sim_config = SimConfig::builder().num_steps(200).build().unwrap();
+        let params = |gamma| {
+            Parameters::new(gamma, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap()
+        };
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let compare =
+            |a, b| compare_parameter_sets(&params(a), &params(b), &sim_config, &intensity, 200, 8);
+
+        // A strategy against itself: every pair ties exactly.
+        let same = compare(0.1, 0.1);
+        assert_eq!(
+            (same.mean_diff, same.diff_se, same.win_rate),
+            (0.0, 0.0, 0.0)
+        );
+        assert_eq!((same.p_value, same.ci_low, same.ci_high), (1.0, 0.0, 0.0));
+
+        // Nearly identical strategies on shared paths differ by far less than the PnL noise.
+        let close = compare(0.1, 0.101);
+        assert!(close.diff_se < 0.5, "{close:?}");
+        assert!(close.ci_low <= close.mean_diff && close.mean_diff <= close.ci_high);
+
+        // A wide gap in gamma is clearly significant on 200 paths, and the interval agrees.
+        let far = compare(0.01, 1.0);
+        assert!(far.mean_diff > 0.0 && far.ci_low > 0.0, "{far:?}");
+        assert!(far.p_value < 0.01);
+        assert!(far.win_rate > 0.5);
+        assert!((far.mean_diff - (far.mean_pnl_a - far.mean_pnl_b)).abs() < 1e-9);
+        assert_eq!(far, compare(0.01, 1.0));
+
+        // Swapping the legs mirrors the statistics.
+        let swapped = compare(1.0, 0.01);
+        assert_eq!(swapped.t_stat, -far.t_stat);
+        assert!((swapped.p_value - far.p_value).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_pnl_sensitivity_gamma_is_the_sensitivity_gamma_leg() {
+        let sim_config = SimConfig::builder().num_steps(200).build().unwrap();
+        let base_params =
+            Parameters::new(2.0, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let gradient = |h, iterations| {
+            pnl_sensitivity_gamma(&base_params, &sim_config, &intensity, 2.0, h, iterations, 4)
+        };
+
+        // The default gamma bump of 5% is a step of 0.1 here, so the paths and legs coincide.
+        let report = sensitivity(
+            &base_params,
+            &sim_config,
+            &intensity,
+            &SensitivityBumps::default(),
+            300,
+            4,
+        )
+        .unwrap();
+        assert_eq!(gradient(0.1, 300), Ok(report.gamma.estimate));
+        assert!(gradient(0.1, 300).unwrap() < 0.0);
+
+        assert_eq!(
+            gradient(0.0, 300),
+            Err(ConfigError::Params(ParamError::NotPositive {
+                name: "h",
+                value: 0.0
+            }))
+        );
+        assert!(matches!(
+            gradient(2.5, 300),
+            Err(ConfigError::Params(ParamError::NotPositive {
+                name: "gamma",
+                ..
+            }))
+        ));
+        assert_eq!(gradient(0.1, 0), Err(ConfigError::NoIterations));
+    }
+
+    #[test]
+    fn test_sensitivity_rejects_invalid_inputs() {
+        let sim_config = SimConfig::builder().num_steps(20).build().unwrap();
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let run = |params: &Parameters, bumps: SensitivityBumps, n| {
+            sensitivity(params, &sim_config, &intensity, &bumps, n, 4)
+        };
+        let bumps = SensitivityBumps::default();
+
+        assert!(run(&base_params, bumps, 10).is_ok());
+        assert_eq!(run(&base_params, bumps, 0), Err(ConfigError::NoIterations));
+        let whole_gamma = SensitivityBumps {
+            gamma: 1.0,
+            ..bumps
+        };
+        assert_eq!(
+            run(&base_params, whole_gamma, 10).unwrap_err().to_string(),
+            "`bumps.gamma` must be in (0, 1), got 1"
+        );
+        let no_drift = SensitivityBumps {
+            drift: 0.0,
+            ..bumps
+        };
+        assert!(run(&base_params, no_drift, 10).is_err());
+        let flat = Parameters {
+            sigma: 0.0,
+            ..base_params
+        };
+        assert!(matches!(
+            run(&flat, bumps, 10),
+            Err(ConfigError::OutOfRange { name: "sigma", .. })
+        ));
+        let bad_config = SimConfig {
+            dt: 0.0,
+            ..sim_config.clone()
+        };
+        assert!(sensitivity(&base_params, &bad_config, &intensity, &bumps, 10, 4).is_err());
+    }
+
+    #[test]
+    fn test_sweep_with_linear_intensity() {
+        let base_params = || Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+
+        let sweep_config = SweepConfig {
+            gammas: vec![0.1],
+            sigmas: vec![0.2],
+            ks: vec![1.5],
+            drifts: vec![0.0],
+            sim_config: SimConfig::builder().num_steps(200).build().unwrap(),
+            iterations_per_param: 10,
+            ..SweepConfig::default()
+        };
+
+        // Zero intensity everywhere beyond the mid: nothing can ever fill.
+        let dead_book = LinearIntensity { a: 0.0, slope: 1.0 };
+        let results = run_sweep(base_params(), &sweep_config, &dead_book).unwrap();
+        assert_eq!(results[0].mean_pnl, 0.0);
+        assert_eq!(results[0].mean_abs_inventory, 0.0);
+
+        // Closures work as intensity models too.
+        let results = run_sweep(base_params(), &sweep_config, &|d: f64| {
+            100.0 * (-2.0 * d).exp()
+        })
+        .unwrap();
+        assert!(results[0].mean_abs_inventory > 0.0);
+    }
+
+    #[test]
+    fn test_latin_hypercube_covers_every_stratum() {
+        let n = 20;
+        let points = latin_hypercube(n, 4, &mut StdRng::seed_from_u64(3));
+        assert_eq!(points.len(), n);
+
+        for d in 0..4 {
+            let mut strata: Vec<usize> =
+                points.iter().map(|p| (p[d] * n as f64) as usize).collect();
+            strata.sort_unstable();
+            assert_eq!(strata, (0..n).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_random_search_within_ranges() {
+        let base_params = Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+
+        let ranges = SearchRanges {
+            gamma: (0.01, 1.0),
+            sigma: (0.1, 0.3),
+            k: (1.0, 2.0),
+            drift: (-0.05, 0.05),
+            log_gamma: false,
+            log_k: false,
+            sampling: SearchSampling::LatinHypercube,
+            seed: None,
+            sim_config: SimConfig::builder().num_steps(100).build().unwrap(),
+            iterations_per_param: 5,
+        };
+
+        let intensity_model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let results = run_random_search(base_params, &ranges, 12, &intensity_model);
+
+        assert_eq!(results.len(), 12);
+        let inside = |x: f64, (lo, hi): (f64, f64)| (lo..=hi).contains(&x);
+        for res in &results {
+            assert!(inside(res.gamma, ranges.gamma));
+            assert!(inside(res.sigma, ranges.sigma));
+            assert!(inside(res.k, ranges.k));
+            assert!(inside(res.drift, ranges.drift));
+        }
+    }
+
+    #[test]
+    fn test_halton_points_are_radical_inverses() {
+        let points = halton_points(4, 2);
+        assert_eq!(
+            points,
+            [
+                [0.5, 1.0 / 3.0],
+                [0.25, 2.0 / 3.0],
+                [0.75, 1.0 / 9.0],
+                [0.125, 4.0 / 9.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_points_sampling_and_log_scale() {
+        let ranges = |sampling, log_gamma, seed| SearchRanges {
+            gamma: (0.001, 10.0),
+            sigma: (0.1, 0.3),
+            k: (1.0, 2.0),
+            drift: (-0.05, 0.05),
+            log_gamma,
+            log_k: false,
+            sampling,
+            seed,
+            sim_config: SimConfig::builder().build().unwrap(),
+            iterations_per_param: 1,
+        };
+        let share_below = |points: &[[f64; 4]], x: f64| {
+            points.iter().filter(|p| p[0] < x).count() as f64 / points.len() as f64
+        };
+
+        // Log-uniform over [1e-3, 10] puts half the mass below the geometric midpoint 0.1; a linear
+        // scale puts only 1% there.
+        for sampling in [
+            SearchSampling::LatinHypercube,
+            SearchSampling::Uniform,
+            SearchSampling::Halton,
+        ] {
+            let log = search_points(&ranges(sampling, true, Some(1)), 400);
+            assert!((share_below(&log, 0.1) - 0.5).abs() < 0.1, "{sampling:?}");
+            assert!(log.iter().all(|p| (0.001..=10.0).contains(&p[0])));
+            let linear = search_points(&ranges(sampling, false, Some(1)), 400);
+            assert!(share_below(&linear, 0.1) < 0.05, "{sampling:?}");
+        }
+
+        // A seed fixes the points; Halton needs none.
+        let seeded = ranges(SearchSampling::Uniform, false, Some(9));
+        assert_eq!(search_points(&seeded, 20), search_points(&seeded, 20));
+        let halton = ranges(SearchSampling::Halton, false, None);
+        assert_eq!(search_points(&halton, 20), search_points(&halton, 20));
+    }
+
+    #[test]
+    fn test_asymmetric_flow_biases_inventory() {
+        let base_params = || Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+
+        let sweep_config = SweepConfig {
+            gammas: vec![0.1],
+            sigmas: vec![0.2],
+            ks: vec![1.5],
+            drifts: vec![0.0],
+            sim_config: SimConfig::builder().num_steps(200).build().unwrap(),
+            iterations_per_param: 200,
+            ..SweepConfig::default()
+        };
+
+        // Sellers arrive four times as often as buyers, so the maker ends up long.
+        let sell_pressure = MarketIntensity::new(
+            ExponentialIntensity { k: 1.5, a: 200.0 },
+            ExponentialIntensity { k: 1.5, a: 50.0 },
+        );
+        let skewed = run_sweep(base_params(), &sweep_config, &sell_pressure).unwrap();
+        assert!(skewed[0].terminal_inventory_mean > 1.0);
+
+        let buy_pressure = MarketIntensity::new(
+            ExponentialIntensity { k: 1.5, a: 50.0 },
+            ExponentialIntensity { k: 1.5, a: 200.0 },
+        );
+        let skewed = run_sweep(base_params(), &sweep_config, &buy_pressure).unwrap();
+        assert!(skewed[0].terminal_inventory_mean < -1.0);
+
+        // A bid that fills on every step leaves one unit more than the last step started with.
+        let one_step = SweepConfig {
+            sim_config: SimConfig::builder().num_steps(1).build().unwrap(),
+            iterations_per_param: 3,
+            ..sweep_config
+        };
+        let bid_only = MarketIntensity::new(|_: f64| 200.0, |_: f64| 0.0);
+        let filled = run_sweep(base_params(), &one_step, &bid_only).unwrap();
+        assert_eq!(filled[0].terminal_inventory_mean, 1.0);
+    }
+
+    #[test]
+    fn test_top_n_ordering() {
+        let base_params = Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+
+        let sweep_config = SweepConfig {
+            gammas: vec![0.01, 0.1, 1.0, 5.0],
+            sigmas: vec![0.2],
+            ks: vec![1.5],
+            drifts: vec![0.0],
+            sim_config: SimConfig::builder().num_steps(100).build().unwrap(),
+            iterations_per_param: 20,
+            ..SweepConfig::default()
+        };
+
+        let intensity_model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let results = run_sweep(base_params, &sweep_config, &intensity_model).unwrap();
+
+        let best = top_n(&results, 3, SweepMetric::SharpeRatio, true);
+        assert_eq!(best.len(), 3);
+        assert!(
+            best.windows(2)
+                .all(|w| w[0].sharpe_ratio >= w[1].sharpe_ratio)
+        );
+        let max_sharpe = results
+            .iter()
+            .map(|r| r.sharpe_ratio)
+            .fold(f64::MIN, f64::max);
+        assert_eq!(best[0].sharpe_ratio, max_sharpe);
+
+        let calmest = top_n(&results, 10, SweepMetric::StdPnl, false);
+        assert_eq!(calmest.len(), results.len());
+        assert!(calmest.windows(2).all(|w| w[0].std_pnl <= w[1].std_pnl));
+    }
+
+    #[test]
+    fn test_top_n_pushes_nan_last() {
+        let with_pnl = |mean_pnl| SweepResult {
+            mean_pnl,
+            ..Default::default()
+        };
+        let results = vec![
+            with_pnl(f64::NAN),
+            with_pnl(1.0),
+            with_pnl(3.0),
+            with_pnl(2.0),
+        ];
+
+        let best = top_n(&results, 4, SweepMetric::MeanPnl, true);
+        assert_eq!(best[0].mean_pnl, 3.0);
+        assert!(best[3].mean_pnl.is_nan());
+
+        let worst = top_n(&results, 4, SweepMetric::MeanPnl, false);
+        assert_eq!(worst[0].mean_pnl, 1.0);
+        assert!(worst[3].mean_pnl.is_nan());
+    }
+
+    #[test]
+    fn test_inventory_penalty_changes_top_ranked() {
+        let result = |gamma, mean_pnl, terminal_inventory_std| SweepResult {
+            gamma,
+            mean_pnl,
+            terminal_inventory_std,
+            ..Default::default()
+        };
+        // The low-gamma maker earns more but ends the horizon holding far more inventory.
+        let results = vec![result(0.01, 10.0, 3.0), result(1.0, 8.0, 0.5)];
+
+        let by_pnl = top_n(&results, 1, SweepMetric::MeanPnl, true);
+        assert_eq!(by_pnl[0].gamma, 0.01);
+        let unpenalized = SweepMetric::InventoryPenalized {
+            inventory_penalty: 0.0,
+        };
+        assert_eq!(top_n(&results, 1, unpenalized, true), by_pnl);
+
+        let penalized = SweepMetric::InventoryPenalized {
+            inventory_penalty: 1.0,
+        };
+        assert_eq!(inventory_penalized_score(&results[0], 1.0), 1.0);
+        assert_eq!(inventory_penalized_score(&results[1], 1.0), 7.75);
+        assert_eq!(objective(&results[1], 1.0), 7.75);
+        assert_eq!(top_n(&results, 1, penalized, true)[0].gamma, 1.0);
+
+        // The optimiser scores points by the same utility.
+        let for_optimizer = crate::optimize::Objective::InventoryPenalized {
+            inventory_penalty: 1.0,
+        };
+        for result in &results {
+            assert_eq!(for_optimizer.value(result), penalized.value(result));
+        }
+    }
+
+    #[test]
+    fn test_pareto_frontier_excludes_dominated() {
+        let point = |mean_pnl, std_pnl| SweepResult {
+            mean_pnl,
+            std_pnl,
+            ..Default::default()
+        };
+        let results = vec![
+            point(10.0, 5.0), // frontier
+            point(8.0, 3.0),  // frontier
+            point(12.0, 9.0), // frontier
+            point(9.0, 6.0),  // dominated by (10, 5)
+            point(10.0, 7.0), // ties on mean, loses on std
+            point(8.0, 3.0),  // duplicate of a frontier point
+            point(7.0, 3.0),  // ties on std, loses on mean
+        ];
+
+        let mut frontier = pareto_frontier(&results, SweepMetric::StdPnl, SweepMetric::MeanPnl);
+        let coords = |frontier: &[&SweepResult]| -> Vec<(f64, f64)> {
+            frontier.iter().map(|r| (r.mean_pnl, r.std_pnl)).collect()
+        };
+        assert_eq!(
+            coords(&frontier),
+            vec![(10.0, 5.0), (8.0, 3.0), (12.0, 9.0), (8.0, 3.0)]
+        );
+
+        sort_frontier_by_risk(&mut frontier, SweepMetric::StdPnl);
+        assert_eq!(
+            coords(&frontier),
+            vec![(8.0, 3.0), (8.0, 3.0), (10.0, 5.0), (12.0, 9.0)]
+        );
+    }
+
+    #[test]
+    fn test_pareto_frontier_by_cvar_skips_nan() {
+        let point = |mean_pnl, cvar_95| SweepResult {
+            mean_pnl,
+            cvar_95,
+            ..Default::default()
+        };
+        let results = vec![
+            point(5.0, 2.0),
+            point(f64::NAN, 0.5), // unrankable, though it would win on risk
+            point(9.0, 4.0),
+            point(6.0, f64::NAN), // unrankable
+            point(4.0, 3.0),      // dominated by (5, 2)
+            point(9.0, 6.0),      // ties on mean, loses on CVaR
+            point(-1.0, 1.0),     // least risk
+            point(f64::INFINITY, 7.0),
+        ];
+
+        let frontier = pareto_frontier(&results, SweepMetric::Cvar95, SweepMetric::MeanPnl);
+        let coords: Vec<(f64, f64)> = frontier.iter().map(|r| (r.mean_pnl, r.cvar_95)).collect();
+        assert_eq!(
+            coords,
+            vec![(5.0, 2.0), (9.0, 4.0), (-1.0, 1.0), (f64::INFINITY, 7.0)]
+        );
+        assert_eq!(
+            unranked_count(&results, SweepMetric::Cvar95, SweepMetric::MeanPnl),
+            2
+        );
+        assert!(pareto_frontier(&[], SweepMetric::Cvar95, SweepMetric::MeanPnl).is_empty());
+    }
+
+    /// Exponential intensity that panics when a quote sits exactly `trap_delta` from the mid.
+    struct TrapIntensity {
+        inner: ExponentialIntensity,
+        trap_delta: f64,
+    }
+
+    impl IntensityModel for TrapIntensity {
+        fn calculate_intensity(&self, delta: f64) -> f64 {
+            if (delta - self.trap_delta).abs() < 1e-9 {
+                panic!("trap sprung at delta {delta}");
+            }
+            self.inner.calculate_intensity(delta)
+        }
+    }
+
+    #[test]
+    fn test_sweep_isolates_failing_combination() {
+        // Zero volatility and drift keep the mid fixed, so the first quote of every trajectory sits
+        // exactly half a spread away and the spread depends only on gamma.
+        let base_params = || Parameters {
+            gamma: 0.1,
+            sigma: 0.0,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+        let trap_params = Parameters {
+            gamma: 1.0,
+            ..base_params()
+        };
+
+        let sweep_config = SweepConfig {
+            gammas: vec![0.1, 0.5, 1.0, 2.0],
+            sigmas: vec![0.0],
+            ks: vec![1.5],
+            drifts: vec![0.0],
+            sim_config: SimConfig::builder().num_steps(50).build().unwrap(),
+            iterations_per_param: 5,
+            ..SweepConfig::default()
+        };
+
+        let intensity_model = TrapIntensity {
+            inner: ExponentialIntensity { k: 1.5, a: 140.0 },
+            trap_delta: optimal_spread(&trap_params, 0.0) / 2.0,
+        };
+
+        let outcome = try_run_sweep(base_params(), &sweep_config, &intensity_model);
+        assert_eq!(outcome.rows.len(), 4);
+
+        let gammas: Vec<f64> = outcome.results().map(|r| r.gamma).collect();
+        assert_eq!(gammas, vec![0.1, 0.5, 2.0]);
+
+        let errors: Vec<&SweepError> = outcome.errors().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].point.get("gamma"), Some(1.0));
+        assert_eq!(
+            errors[0].to_string().split(" failed").next(),
+            Some(
+                "combination gamma=1, sigma=0, k=1.5, a=140, t_horizon=1, drift=0, latency_steps=0"
+            )
+        );
+        assert!(matches!(&errors[0].kind, SweepErrorKind::Panicked(msg) if msg.contains("trap")));
+
+        assert_eq!(
+            run_sweep(base_params(), &sweep_config, &intensity_model)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_sweep_rejects_degenerate_configs() {
+        let base_params = || Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+        let mut sweep_config = SweepConfig {
+            gammas: vec![0.1],
+            sigmas: vec![0.2],
+            ks: vec![1.5],
+            drifts: vec![0.0],
+            // Invalid on purpose, so it bypasses the builder's validation.
+            sim_config: SimConfig {
+                num_steps: 0,
+                ..SimConfig::default()
+            },
+            iterations_per_param: 1,
+            ..SweepConfig::default()
+        };
+        let intensity_model = ExponentialIntensity { k: 1.5, a: 140.0 };
+
+        let outcome = try_run_sweep(base_params(), &sweep_config, &intensity_model);
+        assert_eq!(
+            outcome.errors().next().unwrap().kind,
+            SweepErrorKind::EmptyTrajectory
+        );
+
+        sweep_config.sim_config.num_steps = 10;
+        sweep_config.iterations_per_param = 0;
+        let outcome = try_run_sweep(base_params(), &sweep_config, &intensity_model);
+        assert_eq!(
+            outcome.errors().next().unwrap().kind,
+            SweepErrorKind::NoIterations
+        );
+
+        // A single iteration has no dispersion estimate but must not produce NaNs.
+        sweep_config.iterations_per_param = 1;
+        let results = run_sweep(base_params(), &sweep_config, &intensity_model).unwrap();
+        assert_eq!(results[0].std_pnl, 0.0);
+        assert_eq!(results[0].terminal_inventory_std, 0.0);
+    }
+
+    #[test]
+    fn test_run_sweep_validates_up_front() {
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let valid = SweepConfig {
+            gammas: vec![0.1],
+            sigmas: vec![0.2],
+            ks: vec![1.5],
+            drifts: vec![0.0],
+            sim_config: SimConfig::builder().num_steps(20).build().unwrap(),
+            iterations_per_param: 2,
+            seed: Some(1),
+            ..SweepConfig::default()
+        };
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let rejects = |base_params: Parameters, sweep_config: &SweepConfig| {
+            run_sweep(base_params, sweep_config, &intensity).unwrap_err()
+        };
+        assert_eq!(run_sweep(base_params, &valid, &intensity).unwrap().len(), 1);
+
+        let err = rejects(
+            Parameters {
+                gamma: -1.0,
+                ..base_params
+            },
+            &valid,
+        );
+        assert_eq!(
+            err,
+            ConfigError::Params(ParamError::NotPositive {
+                name: "gamma",
+                value: -1.0
+            })
+        );
+        assert!(std::error::Error::source(&err).is_some());
+
+        let mut config = valid.clone();
+        config.sim_config.dt = 0.0;
+        assert_eq!(
+            rejects(base_params, &config),
+            ConfigError::Sim(SimError::NonPositiveDt(0.0))
+        );
+        config.sim_config.dt = valid.sim_config.dt;
+        config.sim_config.num_steps = 0;
+        assert_eq!(
+            rejects(base_params, &config),
+            ConfigError::Sim(SimError::NoSteps)
+        );
+        config.sim_config.num_steps = 20;
+        config.sim_config.s_0 = 0.0;
+        assert_eq!(
+            rejects(base_params, &config),
+            ConfigError::Sim(SimError::NonPositiveS0(0.0))
+        );
+
+        // A step of a whole time unit makes even the opening quotes fill more than surely.
+        let mut config = valid.clone();
+        config.sim_config.dt = 1.0;
+        assert!(matches!(
+            rejects(base_params, &config),
+            ConfigError::Sim(SimError::FillProbabilityAboveOne { probability }) if probability > 1.0
+        ));
+        config.sim_config.fill_model = FillModel::CrossDeterministic;
+        assert!(run_sweep(base_params, &config, &intensity).is_ok());
+
+        for axis in ["gammas", "sigmas", "ks", "drifts"] {
+            let mut config = valid.clone();
+            match axis {
+                "gammas" => config.gammas.clear(),
+                "sigmas" => config.sigmas.clear(),
+                "ks" => config.ks.clear(),
+                _ => config.drifts.clear(),
+            }
+            assert_eq!(rejects(base_params, &config), ConfigError::EmptyAxis(axis));
+        }
+
+        let config = SweepConfig {
+            iterations_per_param: 0,
+            ..valid.clone()
+        };
+        let err = rejects(base_params, &config);
+        assert_eq!(err, ConfigError::NoIterations);
+        assert_eq!(err.to_string(), "`iterations_per_param` must be > 0");
+
+        let config = SweepConfig {
+            tail_confidence: 1.5,
+            ..valid.clone()
+        };
+        let err = rejects(base_params, &config);
+        assert_eq!(
+            err.to_string(),
+            "`tail_confidence` must be in [0.5, 1), got 1.5"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_sweep_spec_from_example_toml() {
+        let example = include_str!("../examples/sweep.toml");
+        let spec = SweepSpec::from_toml(example).unwrap();
+        assert_eq!(spec.sweep.gammas, vec![0.01, 0.1, 1.0]);
+        assert_eq!(spec.sweep.seed, Some(42));
+        assert_eq!(spec.sweep.sim_config.num_steps, 600);
+        assert_eq!(
+            spec.intensity,
+            IntensitySpec::Exponential { a: 140.0, k: 1.5 }
+        );
+
+        // A misspelt key is named in the error rather than ignored.
+        let typo = example.replace("iterations_per_param", "iterations_per_point");
+        let err = SweepSpec::from_toml(&typo).unwrap_err().to_string();
+        assert!(err.contains("iterations_per_point"), "{err}");
+
+        let err = SweepSpec::from_toml(&example.replace("gamma = 0.1", "gamma = -0.1"));
+        assert_eq!(
+            err,
+            Err(SpecError::Params(ParamError::NotPositive {
+                name: "gamma",
+                value: -0.1
+            }))
+        );
+        let err = SweepSpec::from_toml(&example.replace("dt = 0.005", "dt = 0.0"));
+        assert_eq!(err, Err(SpecError::Sim(SimError::NonPositiveDt(0.0))));
+        // The intensity's `k` comes last in the file, after the base parameters' one.
+        let (head, tail) = example.rsplit_once("k = 1.5").unwrap();
+        let err = SweepSpec::from_toml(&format!("{head}k = -1.5{tail}"));
+        assert_eq!(
+            err,
+            Err(SpecError::Intensity(ParamError::NotPositive {
+                name: "k",
+                value: -1.5
+            }))
+        );
+        let err =
+            SweepSpec::from_toml(&example.replace("gammas = [0.01, 0.1, 1.0]", "gammas = []"));
+        assert_eq!(err, Err(SpecError::Sweep(ConfigError::EmptyAxis("gammas"))));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_sweep_spec_from_json() {
+        let json = r#"{
+            "base_params": {
+                "gamma": 0.1, "sigma": 0.2, "t_horizon": 1.0, "k": 1.5, "a": 140.0,
+                "quoting_model": {"type": "avellaneda_stoikov"}
+            },
+            "sweep": {
+                "gammas": [0.05, 0.1],
+                "sigmas": [0.2],
+                "ks": [1.5],
+                "drifts": [0.0],
+                "sim_config": {
+                    "dt": 0.005, "num_steps": 100, "s_0": 100.0, "drift": 0.0,
+                    "latency_steps": 0, "staleness_threshold": 0.01,
+                    "hawkes": {"alpha": 1.0, "beta": 50.0, "excitation": "both_sides"}
+                },
+                "iterations_per_param": 5
+            },
+            "intensity": {"type": "exponential", "a": 140, "k": 1.5}
+        }"#;
+        let spec: SweepSpec = serde_json::from_str(json).unwrap();
+        assert_eq!(spec.sweep.gammas, vec![0.05, 0.1]);
+        assert_eq!(
+            spec.intensity,
+            IntensitySpec::Exponential { a: 140.0, k: 1.5 }
+        );
+        assert_eq!(spec.run().unwrap().len(), 2);
+
+        let round_trip = serde_json::to_string(&spec).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SweepSpec>(&round_trip).unwrap(),
+            spec
+        );
+
+        let ranges = SearchRanges {
+            gamma: (0.01, 1.0),
+            sigma: (0.1, 0.4),
+            k: (1.0, 2.0),
+            drift: (-0.1, 0.1),
+            log_gamma: true,
+            log_k: false,
+            sampling: SearchSampling::Halton,
+            seed: Some(7),
+            sim_config: spec.sweep.sim_config.clone(),
+            iterations_per_param: 5,
+        };
+        let json = serde_json::to_string(&ranges).unwrap();
+        assert_eq!(serde_json::from_str::<SearchRanges>(&json).unwrap(), ranges);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_checkpoint_resumes_interrupted_sweep() {
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let sweep_config = |gammas: Vec<f64>| SweepConfig {
+            gammas,
+            sigmas: vec![0.1, 0.3],
+            ks: vec![1.5],
+            drifts: vec![0.0],
+            sim_config: SimConfig::builder().num_steps(50).build().unwrap(),
+            iterations_per_param: 20,
+            ..SweepConfig::default()
+        };
+        let path = std::env::temp_dir().join(format!("as_checkpoint_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let records = |path: &std::path::Path| {
+            let contents = std::fs::read(path).unwrap();
+            read_checkpoint(&contents).1
+        };
+        // The bootstrap intervals are NaN, so compare the identifying and simulated fields.
+        let key = |results: &[SweepResult]| -> Vec<(f64, f64, f64, f64)> {
+            results
+                .iter()
+                .map(|r| (r.gamma, r.sigma, r.mean_pnl, r.max_drawdown))
+                .collect()
+        };
+
+        // The "interrupted" run finished the gamma = 0.1 half of the grid, then died mid-write.
+        let first =
+            run_sweep_with_checkpoint(base_params, &sweep_config(vec![0.1]), &intensity, &path)
+                .unwrap()
+                .into_results();
+        assert_eq!(first.len(), 2);
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        std::io::Write::write_all(&mut file, b"4096 {\"gamma\": 0.5, \"sig").unwrap();
+        drop(file);
+
+        let full = sweep_config(vec![0.1, 0.5]);
+        let resumed = run_sweep_with_checkpoint(base_params, &full, &intensity, &path)
+            .unwrap()
+            .into_results();
+        assert_eq!(resumed.len(), 4);
+        // Recorded cells come back as they were stored rather than re-simulated, in grid order.
+        assert_eq!(key(&resumed[..2]), key(&first));
+        assert_eq!((resumed[2].gamma, resumed[3].sigma), (0.5, 0.3));
+
+        // The torn record was dropped, and only the two missing cells were appended.
+        let stored = records(&path);
+        assert_eq!(stored.len(), 4);
+        assert_eq!(key(&stored[2..]), key(&resumed[2..]));
+
+        // A finished sweep computes nothing more.
+        let again = run_sweep_with_checkpoint(base_params, &full, &intensity, &path)
+            .unwrap()
+            .into_results();
+        assert_eq!(key(&again), key(&resumed));
+        assert_eq!(records(&path).len(), 4);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_checkpoint_resume_matches_uninterrupted_sweep() {
+        let base_params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let sweep_config = SweepConfig {
+            gammas: vec![0.1, 0.5],
+            sigmas: vec![0.1, 0.3],
+            ks: vec![1.5],
+            drifts: vec![0.0, 0.05],
+            sim_config: SimConfig::builder().num_steps(50).build().unwrap(),
+            iterations_per_param: 20,
+            bootstrap_resamples: 50,
+            seed: Some(11),
+            ..SweepConfig::default()
+        };
+        let path = std::env::temp_dir().join(format!("as_resume_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let uninterrupted = run_sweep(base_params, &sweep_config, &intensity).unwrap();
+
+        // Keep the header and three of the eight cells, as if the run had been killed there.
+        run_sweep_with_checkpoint(base_params, &sweep_config, &intensity, &path).unwrap();
+        let contents = std::fs::read(&path).unwrap();
+        let mut kept = 0;
+        for _ in 0..4 {
+            kept += next_record(&contents[kept..]).unwrap().1;
+        }
+        std::fs::write(&path, &contents[..kept]).unwrap();
+
+        let resumed = run_sweep_with_checkpoint(base_params, &sweep_config, &intensity, &path)
+            .unwrap()
+            .into_results();
+        // Compared as JSON, which writes the NaN statistics as null.
+        assert_eq!(
+            serde_json::to_string(&resumed).unwrap(),
+            serde_json::to_string(&uninterrupted).unwrap()
+        );
+        assert_eq!(read_checkpoint(&std::fs::read(&path).unwrap()).1.len(), 8);
+
+        // Other settings would make the recorded cells incomparable with new ones.
+        let changed = SweepConfig {
+            iterations_per_param: 40,
+            ..sweep_config
+        };
+        let err = run_sweep_with_checkpoint(base_params, &changed, &intensity, &path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -1,72 +1,590 @@
-use avellaneda_stoikov_rs::analysis::{SweepConfig, run_sweep};
-use avellaneda_stoikov_rs::model::{ExponentialIntensity, Parameters};
+use avellaneda_stoikov_rs::analysis::{
+    DEFAULT_PERIODS_PER_YEAR, DEFAULT_TAIL_CONFIDENCE, SearchRanges, SearchSampling, SweepConfig,
+    SweepMetric, SweepOutcome, SweepResult, SweepSpec, pareto_frontier, run_random_search,
+    run_sweep_with_progress, sort_frontier_by_risk, unranked_count,
+};
+use avellaneda_stoikov_rs::export::{FormatOptions, write_sweep_csv, write_sweep_table};
+use avellaneda_stoikov_rs::model::{IntensityModel, IntensitySpec, Parameters, QuotingModel};
 use avellaneda_stoikov_rs::sim::SimConfig;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum};
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
+/// Monte Carlo parameter sweep of the Avellaneda-Stoikov market maker.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Risk aversion values to sweep (comma-separated)
+    #[arg(long, value_delimiter = ',', default_values_t = [0.01, 0.05, 0.1, 0.5, 1.0, 5.0])]
+    gammas: Vec<f64>,
+    /// Volatility values to sweep (comma-separated)
+    #[arg(long, value_delimiter = ',', default_values_t = [0.1, 0.2, 0.4])]
+    sigmas: Vec<f64>,
+    /// Order book liquidity values to sweep (comma-separated)
+    #[arg(long, value_delimiter = ',', default_values_t = [1.5])]
+    ks: Vec<f64>,
+    /// Price drift values to sweep (comma-separated)
+    #[arg(long, value_delimiter = ',', allow_hyphen_values = true, default_values_t = [0.0, 0.05, -0.05])]
+    drifts: Vec<f64>,
+    /// Base arrival rates to sweep (comma-separated)
+    #[arg(long = "as", value_delimiter = ',', default_values_t = [140.0])]
+    as_: Vec<f64>,
+    /// Strategy horizons to sweep (comma-separated)
+    #[arg(long, value_delimiter = ',', default_values_t = [1.0])]
+    t_horizons: Vec<f64>,
+    /// Quote latencies in steps to sweep (comma-separated)
+    #[arg(long, value_delimiter = ',', default_values_t = [0])]
+    latency_steps: Vec<usize>,
+    /// Monte Carlo trajectories per parameter combination
+    #[arg(long, default_value_t = 1000)]
+    iterations: usize,
+    /// Sample trajectories as antithetic pairs with mirrored price shocks
+    #[arg(long)]
+    antithetic: bool,
+    /// Also run each grid cell as a symmetric fixed-spread baseline on the same paths, reporting
+    /// its PnL and the strategy's uplift over it in the saved results
+    #[arg(long)]
+    baseline: bool,
+    /// Bootstrap resamples for the mean PnL and Sharpe confidence intervals (0 disables)
+    #[arg(long, default_value_t = 1000)]
+    bootstrap_resamples: usize,
+    /// Confidence level of the VaR and CVaR columns
+    #[arg(long, default_value_t = DEFAULT_TAIL_CONFIDENCE)]
+    tail_confidence: f64,
+    /// Minimum acceptable PnL per run for the Sortino ratio
+    #[arg(long, default_value_t = 0.0, allow_hyphen_values = true)]
+    sortino_mar: f64,
+    /// Model time units per year for the annualised Sharpe ratio (252 reads a unit as a trading
+    /// day)
+    #[arg(long, default_value_t = DEFAULT_PERIODS_PER_YEAR)]
+    periods_per_year: f64,
+    /// Simulation steps per trajectory
+    #[arg(long, default_value_t = 600)]
+    num_steps: usize,
+    /// Time step size
+    #[arg(long, default_value_t = 0.005)]
+    dt: f64,
+    /// Initial mid price
+    #[arg(long = "s0", default_value_t = 100.0)]
+    s0: f64,
+    /// Instead of the grid, evaluate N points sampled between the min and max of each of
+    /// `--gammas`, `--sigmas`, `--ks`, and `--drifts`
+    #[arg(long, value_name = "N")]
+    random_search: Option<usize>,
+    /// Point placement for `--random-search`
+    #[arg(long, value_enum, default_value_t = Sampling::Lhs)]
+    sampling: Sampling,
+    /// Sample gamma and k log-uniformly in `--random-search`
+    #[arg(long)]
+    log_scale: bool,
+    /// Seed for the `--random-search` point placement, or for the Monte Carlo runs of the grid
+    #[arg(long)]
+    seed: Option<u64>,
+    /// TOML sweep config with base parameters, grid, and intensity model, see
+    /// `examples/sweep.toml` (requires the `serde` feature). Flags given alongside override it
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Size of the worker thread pool (defaults to one per core)
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Also write the full results as CSV
+    #[arg(long)]
+    csv: Option<PathBuf>,
+    /// Also write the full results as JSON (requires the `serde` feature)
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// Draw gamma-by-sigma heatmaps of mean PnL and the Sharpe ratio into this directory as PNG,
+    /// one pair per intensity model (requires the `plot` feature)
+    #[arg(long)]
+    plot_dir: Option<PathBuf>,
+    /// Record grid results in this file as they finish and skip those already in it, so an
+    /// interrupted sweep can be resumed (requires the `serde` feature)
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+    /// Shape of the market's fill intensity, with the base `a = 140` and `k = 1.5`. Several
+    /// comma-separated models run the same sweep against each, on the same seeds
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [Intensity::Exponential])]
+    intensity: Vec<Intensity>,
+    /// Tail exponent of `--intensity power-law`
+    #[arg(long, default_value_t = 1.5)]
+    beta: f64,
+    /// Risk measure of the efficient-frontier table printed after the results, against mean PnL
+    #[arg(long, value_enum, default_value_t = FrontierRisk::Std)]
+    frontier_risk: FrontierRisk,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Intensity {
+    Exponential,
+    UnclampedExponential,
+    PowerLaw,
+}
+
+impl Intensity {
+    /// The command-line spelling, which also tags each model's results.
+    fn name(self) -> &'static str {
+        match self {
+            Intensity::Exponential => "exponential",
+            Intensity::UnclampedExponential => "unclamped-exponential",
+            Intensity::PowerLaw => "power-law",
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FrontierRisk {
+    Std,
+    Var,
+    Cvar,
+    Drawdown,
+}
+
+impl FrontierRisk {
+    fn label(self) -> &'static str {
+        match self {
+            FrontierRisk::Std => "PnL std",
+            FrontierRisk::Var => "VaR",
+            FrontierRisk::Cvar => "CVaR",
+            FrontierRisk::Drawdown => "max drawdown",
+        }
+    }
+}
+
+impl From<FrontierRisk> for SweepMetric {
+    fn from(risk: FrontierRisk) -> Self {
+        match risk {
+            FrontierRisk::Std => SweepMetric::StdPnl,
+            FrontierRisk::Var => SweepMetric::Var95,
+            FrontierRisk::Cvar => SweepMetric::Cvar95,
+            FrontierRisk::Drawdown => SweepMetric::MaxDrawdown,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Sampling {
+    Lhs,
+    Uniform,
+    Halton,
+}
+
+impl From<Sampling> for SearchSampling {
+    fn from(sampling: Sampling) -> Self {
+        match sampling {
+            Sampling::Lhs => SearchSampling::LatinHypercube,
+            Sampling::Uniform => SearchSampling::Uniform,
+            Sampling::Halton => SearchSampling::Halton,
+        }
+    }
+}
+
+/// `(min, max)` of a list of sweep values.
+fn bounds(values: &[f64]) -> (f64, f64) {
+    values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(v), hi.max(v))
+        })
+}
+
+#[cfg(feature = "serde")]
+fn write_json(results: &[SweepResult], path: &Path) {
+    use avellaneda_stoikov_rs::export::write_sweep_json;
+    use std::fs::File;
+    use std::io::BufWriter;
+
+    let file = File::create(path)
+        .unwrap_or_else(|err| panic!("failed to create {}: {err}", path.display()));
+    write_sweep_json(results, BufWriter::new(file), &FormatOptions::default())
+        .expect("failed to write JSON results");
+    println!("Wrote {} results to {}", results.len(), path.display());
+}
+
+#[cfg(not(feature = "serde"))]
+fn write_json(_results: &[SweepResult], _path: &Path) {
+    unreachable!("--output is rejected at startup without the serde feature");
+}
+
+#[cfg(feature = "serde")]
+fn run_checkpointed(
+    base_params: Parameters,
+    sweep_config: &SweepConfig,
+    intensity_model: &dyn IntensityModel,
+    path: &Path,
+) -> SweepOutcome {
+    use avellaneda_stoikov_rs::analysis::run_sweep_with_checkpoint;
+
+    run_sweep_with_checkpoint(base_params, sweep_config, intensity_model, path)
+        .unwrap_or_else(|err| panic!("checkpoint {} failed: {err}", path.display()))
+}
+
+#[cfg(not(feature = "serde"))]
+fn run_checkpointed(
+    _base_params: Parameters,
+    _sweep_config: &SweepConfig,
+    _intensity_model: &dyn IntensityModel,
+    _path: &Path,
+) -> SweepOutcome {
+    unreachable!("--checkpoint is rejected at startup without the serde feature");
+}
+
+/// Replaces the settings of a config-file sweep with those given on the command line.
+fn apply_overrides(sweep: &mut SweepConfig, args: &Args, given: &dyn Fn(&str) -> bool) {
+    if given("gammas") {
+        sweep.gammas = args.gammas.clone();
+    }
+    if given("sigmas") {
+        sweep.sigmas = args.sigmas.clone();
+    }
+    if given("ks") {
+        sweep.ks = args.ks.clone();
+    }
+    if given("drifts") {
+        sweep.drifts = args.drifts.clone();
+    }
+    if given("as_") {
+        sweep.as_ = args.as_.clone();
+    }
+    if given("t_horizons") {
+        sweep.t_horizons = args.t_horizons.clone();
+    }
+    if given("latency_steps") {
+        sweep.latency_steps = args.latency_steps.clone();
+    }
+    if given("iterations") {
+        sweep.iterations_per_param = args.iterations;
+    }
+    // Switches can only be turned on from the command line.
+    sweep.antithetic |= args.antithetic;
+    sweep.baseline |= args.baseline;
+    if given("bootstrap_resamples") {
+        sweep.bootstrap_resamples = args.bootstrap_resamples;
+    }
+    if given("tail_confidence") {
+        sweep.tail_confidence = args.tail_confidence;
+    }
+    if given("sortino_mar") {
+        sweep.sortino_mar = args.sortino_mar;
+    }
+    if given("periods_per_year") {
+        sweep.periods_per_year = args.periods_per_year;
+    }
+    if given("seed") {
+        sweep.seed = args.seed;
+    }
+    let sim_config = &mut sweep.sim_config;
+    if given("num_steps") {
+        sim_config.num_steps = args.num_steps;
+    }
+    if given("dt") {
+        sim_config.dt = args.dt;
+    }
+    if given("s0") {
+        sim_config.s_0 = args.s0;
+    }
+    if let Err(e) = sim_config.validate() {
+        eprintln!("invalid simulation settings: {e}");
+        std::process::exit(2);
+    }
+}
+
+#[cfg(feature = "serde")]
+fn load_spec(path: &Path) -> SweepSpec {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("failed to read {}: {err}", path.display());
+        std::process::exit(2);
+    });
+    SweepSpec::from_toml(&text).unwrap_or_else(|err| {
+        eprintln!("{}: {err}", path.display());
+        std::process::exit(2);
+    })
+}
+
+#[cfg(not(feature = "serde"))]
+fn load_spec(_path: &Path) -> SweepSpec {
+    unreachable!("--config is rejected at startup without the serde feature");
+}
+
+#[cfg(feature = "plot")]
+fn write_plots(results: &[SweepResult], dir: &Path) {
+    use avellaneda_stoikov_rs::plot::{PlotAxis, plot_sweep_heatmap};
+
+    if let Err(err) = std::fs::create_dir_all(dir) {
+        eprintln!("failed to create {}: {err}", dir.display());
+        std::process::exit(1);
+    }
+    let mut model_names: Vec<&str> = Vec::new();
+    for res in results {
+        if !model_names.contains(&res.model_name.as_str()) {
+            model_names.push(&res.model_name);
+        }
+    }
+    for name in model_names {
+        let model_results: Vec<SweepResult> = results
+            .iter()
+            .filter(|r| r.model_name == name)
+            .cloned()
+            .collect();
+        for metric in [SweepMetric::MeanPnl, SweepMetric::SharpeRatio] {
+            let file = if name.is_empty() {
+                format!("{}.png", metric.name())
+            } else {
+                format!("{name}_{}.png", metric.name())
+            };
+            let path = dir.join(file);
+            let plotted = plot_sweep_heatmap(
+                &model_results,
+                PlotAxis::Gamma,
+                PlotAxis::Sigma,
+                metric,
+                &path,
+            );
+            if let Err(err) = plotted {
+                eprintln!("failed to plot {}: {err}", path.display());
+                std::process::exit(1);
+            }
+            println!("Wrote {}", path.display());
+        }
+    }
+}
+
+#[cfg(not(feature = "plot"))]
+fn write_plots(_results: &[SweepResult], _dir: &Path) {
+    unreachable!("--plot-dir is rejected at startup without the plot feature");
+}
+
+/// Reports each failed combination on stderr and keeps the successful results.
+fn report_failures(outcome: SweepOutcome) -> Vec<SweepResult> {
+    for err in outcome.errors() {
+        eprintln!("warning: {err}");
+    }
+    outcome.into_results()
+}
+
+fn write_csv(results: &[SweepResult], path: &Path) {
+    let written = File::create(path)
+        .and_then(|file| write_sweep_csv(results, BufWriter::new(file), &FormatOptions::default()));
+    if let Err(err) = written {
+        eprintln!("failed to write {}: {err}", path.display());
+        std::process::exit(1);
+    }
+    println!("Wrote {} results to {}", results.len(), path.display());
+}
+
 fn main() {
-    let base_params = Parameters {
-        gamma: 0.1,
-        sigma: 0.2,
-        t_horizon: 1.0,
-        k: 1.5,
-        a: 140.0,
-    };
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    // Flags typed on the command line override the config file; defaults do not.
+    let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
 
-    let sim_config = SimConfig {
-        dt: 0.005,
-        num_steps: 600,
-        s_0: 100.0,
-        drift: 0.0, // Base drift
-        latency_steps: 0,
+    if args.output.is_some() && cfg!(not(feature = "serde")) {
+        eprintln!("--output requires building with `--features serde`");
+        std::process::exit(2);
+    }
+    if args.checkpoint.is_some() && cfg!(not(feature = "serde")) {
+        eprintln!("--checkpoint requires building with `--features serde`");
+        std::process::exit(2);
+    }
+    if args.config.is_some() && cfg!(not(feature = "serde")) {
+        eprintln!("--config requires building with `--features serde`");
+        std::process::exit(2);
+    }
+    if args.plot_dir.is_some() && cfg!(not(feature = "plot")) {
+        eprintln!("--plot-dir requires building with `--features plot`");
+        std::process::exit(2);
+    }
+    if args.plot_dir.is_some() && args.random_search.is_some() {
+        eprintln!("--plot-dir draws the grid and cannot be combined with --random-search");
+        std::process::exit(2);
+    }
+    if args.checkpoint.is_some() && args.intensity.len() > 1 {
+        eprintln!("--checkpoint supports a single --intensity");
+        std::process::exit(2);
+    }
+    if let Some(threads) = args.threads {
+        if threads == 0 {
+            eprintln!("--threads must be at least 1");
+            std::process::exit(2);
+        }
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .expect("the global thread pool is configured once, before any parallel work");
+    }
+
+    let (base_params, mut sweep_config, file_intensity) = match &args.config {
+        Some(path) => {
+            let spec = load_spec(path);
+            (spec.base_params, spec.sweep, Some(spec.intensity))
+        }
+        None => {
+            let base_params =
+                Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov)
+                    .expect("base parameters are valid");
+            let sweep_config = SweepConfig {
+                gammas: args.gammas.clone(),
+                sigmas: args.sigmas.clone(),
+                ks: args.ks.clone(),
+                drifts: args.drifts.clone(),
+                as_: args.as_.clone(),
+                t_horizons: args.t_horizons.clone(),
+                latency_steps: args.latency_steps.clone(),
+                sim_config: SimConfig::builder()
+                    .dt(args.dt)
+                    .num_steps(args.num_steps)
+                    .s_0(args.s0)
+                    .build()
+                    .unwrap_or_else(|e| {
+                        eprintln!("invalid simulation settings: {e}");
+                        std::process::exit(2);
+                    }),
+                iterations_per_param: args.iterations,
+                antithetic: args.antithetic,
+                bootstrap_resamples: args.bootstrap_resamples,
+                tail_confidence: args.tail_confidence,
+                sortino_mar: args.sortino_mar,
+                seed: args.seed,
+                periods_per_year: args.periods_per_year,
+                baseline: args.baseline,
+                ..SweepConfig::default()
+            };
+            (base_params, sweep_config, None)
+        }
     };
+    if args.config.is_some() {
+        apply_overrides(&mut sweep_config, &args, &given);
+    }
 
-    // Define the sweep configuration
-    let sweep_config = SweepConfig {
-        // Vary Risk Aversion
-        gammas: vec![0.01, 0.05, 0.1, 0.5, 1.0, 5.0],
-        // Vary Volatility
-        sigmas: vec![0.1, 0.2, 0.4],
-        // Constant K
-        ks: vec![1.5],
-        // Vary Drift
-        drifts: vec![0.0, 0.05, -0.05],
-        sim_config,
-        iterations_per_param: 1000,
+    // The config file's model, unless `--intensity` picks the built-in shapes instead.
+    let (a, k) = (base_params.a, base_params.k);
+    let spec = |intensity| match intensity {
+        Intensity::Exponential => IntensitySpec::Exponential { a, k },
+        Intensity::UnclampedExponential => IntensitySpec::UnclampedExponential { a, k },
+        Intensity::PowerLaw => IntensitySpec::PowerLaw {
+            a,
+            k,
+            beta: args.beta,
+        },
     };
+    let models: Vec<(&str, IntensitySpec)> = match file_intensity {
+        Some(intensity) if !given("intensity") => vec![("", intensity)],
+        _ => args
+            .intensity
+            .iter()
+            .map(|&intensity| (intensity.name(), spec(intensity)))
+            .collect(),
+    };
+    let named = models.len() > 1;
 
-    println!(
-        "Starting parameter sweep with {} iterations per config...",
-        sweep_config.iterations_per_param
-    );
-    let start_time = Instant::now();
+    // Check every model and the sweep as a whole before any of it runs.
+    for (_, intensity_spec) in &models {
+        if let Err(err) = intensity_spec.validate() {
+            eprintln!("invalid intensity model: {err}");
+            std::process::exit(2);
+        }
+        if let Err(err) = sweep_config.validate(&base_params, intensity_spec) {
+            eprintln!("invalid sweep: {err}");
+            std::process::exit(2);
+        }
+    }
 
-    let intensity_model = ExponentialIntensity {
-        k: base_params.k,
-        a: base_params.a,
-    };
+    let start_time = Instant::now();
+    let mut results = Vec::new();
+    for (name, intensity_spec) in models {
+        let intensity_model = intensity_spec
+            .into_model()
+            .expect("intensity specs are validated before the sweep");
+        let model_start = Instant::now();
+        if named {
+            println!("Model: {name}");
+        }
+        let mut model_results = if let Some(n_samples) = args.random_search {
+            let ranges = SearchRanges {
+                gamma: bounds(&sweep_config.gammas),
+                sigma: bounds(&sweep_config.sigmas),
+                k: bounds(&sweep_config.ks),
+                drift: bounds(&sweep_config.drifts),
+                log_gamma: args.log_scale,
+                log_k: args.log_scale,
+                sampling: args.sampling.into(),
+                seed: sweep_config.seed,
+                sim_config: sweep_config.sim_config.clone(),
+                iterations_per_param: sweep_config.iterations_per_param,
+            };
+            println!(
+                "Starting {:?} random search over {n_samples} points with {} iterations each...",
+                args.sampling, ranges.iterations_per_param
+            );
+            run_random_search(base_params, &ranges, n_samples, &*intensity_model)
+        } else {
+            println!(
+                "Starting parameter sweep over {} combinations with {} iterations each...",
+                sweep_config.num_combinations(),
+                sweep_config.iterations_per_param
+            );
 
-    let results = run_sweep(base_params, &sweep_config, &intensity_model);
+            report_failures(if let Some(path) = &args.checkpoint {
+                run_checkpointed(base_params, &sweep_config, &*intensity_model, path)
+            } else {
+                run_sweep_with_progress(
+                    base_params,
+                    &sweep_config,
+                    &*intensity_model,
+                    |done, total| {
+                        let elapsed = model_start.elapsed().as_secs_f64();
+                        let eta = elapsed / done as f64 * (total - done) as f64;
+                        eprint!("\r{done}/{total} done, ETA {eta:.1}s   ");
+                        if done == total {
+                            eprintln!();
+                        }
+                    },
+                )
+                .expect("the sweep is validated before it runs")
+            })
+        };
+        if named {
+            for res in &mut model_results {
+                res.model_name = name.to_string();
+            }
+        }
+        results.append(&mut model_results);
+    }
 
     let duration = start_time.elapsed();
     println!("Sweep completed in {:.2}s", duration.as_secs_f64());
+    if results.is_empty() {
+        eprintln!("no parameter combination produced a result");
+        std::process::exit(1);
+    }
+    write_sweep_table(&results, io::stdout().lock(), &FormatOptions::table())
+        .expect("failed to write results table");
+
+    let risk = SweepMetric::from(args.frontier_risk);
+    let skipped = unranked_count(&results, risk, SweepMetric::MeanPnl);
+    if skipped > 0 {
+        eprintln!("warning: {skipped} results with a NaN metric are left off the frontier");
+    }
+    let mut frontier = pareto_frontier(&results, risk, SweepMetric::MeanPnl);
+    sort_frontier_by_risk(&mut frontier, risk);
+    let frontier: Vec<SweepResult> = frontier.into_iter().cloned().collect();
     println!(
-        "{:<8} {:<8} {:<6} {:<8} | {:<12} {:<12} {:<10} | {:<10} {:<10}",
-        "Gamma", "Sigma", "K", "Drift", "Mean PnL", "Std PnL", "Sharpe", "Mean |Q|", "Final Q"
+        "\nEfficient frontier, mean PnL against {} ({} of {} results):",
+        args.frontier_risk.label(),
+        frontier.len(),
+        results.len()
     );
-    println!("{}", "-".repeat(100));
+    write_sweep_table(&frontier, io::stdout().lock(), &FormatOptions::table())
+        .expect("failed to write frontier table");
 
-    for res in results {
-        println!(
-            "{:<8.2} {:<8.2} {:<6.2} {:<8.2} | {:<12.4} {:<12.4} {:<10.4} | {:<10.2} {:<10.2}",
-            res.gamma,
-            res.sigma,
-            res.k,
-            res.drift,
-            res.mean_pnl,
-            res.std_pnl,
-            res.sharpe_ratio,
-            res.mean_abs_inventory,
-            res.terminal_inventory_mean
-        );
+    if let Some(path) = args.csv {
+        write_csv(&results, &path);
+    }
+    if let Some(path) = args.output {
+        write_json(&results, &path);
+    }
+    if let Some(dir) = args.plot_dir {
+        write_plots(&results, &dir);
     }
 }
@@ -0,0 +1,128 @@
+use avellaneda_stoikov_rs::export::write_trajectory_csv;
+use avellaneda_stoikov_rs::model::{ExponentialIntensity, Parameters, QuotingModel};
+use avellaneda_stoikov_rs::sim::{SimConfig, SimResult, run_trajectory_with_rng};
+use avellaneda_stoikov_rs::stats::max_drawdown;
+use clap::Parser;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+/// Simulates one Avellaneda-Stoikov trajectory and writes every step as CSV.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Risk aversion
+    #[arg(long, default_value_t = 0.1)]
+    gamma: f64,
+    /// Volatility
+    #[arg(long, default_value_t = 0.2)]
+    sigma: f64,
+    /// Order book liquidity, for both the strategy and the market's exponential intensity
+    #[arg(long, default_value_t = 1.5)]
+    k: f64,
+    /// Base arrival rate, for both the strategy and the market
+    #[arg(long, default_value_t = 140.0)]
+    a: f64,
+    /// Strategy horizon
+    #[arg(long, default_value_t = 1.0)]
+    t_horizon: f64,
+    /// Price drift
+    #[arg(long, default_value_t = 0.0, allow_hyphen_values = true)]
+    drift: f64,
+    /// Quote latency in steps
+    #[arg(long, default_value_t = 0)]
+    latency_steps: usize,
+    /// Simulation steps
+    #[arg(long, default_value_t = 600)]
+    num_steps: usize,
+    /// Time step size
+    #[arg(long, default_value_t = 0.005)]
+    dt: f64,
+    /// Initial mid price
+    #[arg(long = "s0", default_value_t = 100.0)]
+    s0: f64,
+    /// Seed for the run; the same seed and settings always give the same CSV
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Write the CSV here instead of to stdout
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+    /// Also print final PnL, max drawdown, and fill counts (to stderr when the CSV goes to stdout)
+    #[arg(long)]
+    summary: bool,
+}
+
+fn write_summary<W: Write>(res: &SimResult, mut w: W) -> io::Result<()> {
+    let wealth: Vec<f64> = res
+        .trajectory
+        .iter()
+        .map(|s| s.wealth)
+        .chain([res.final_pnl])
+        .collect();
+    writeln!(w, "Final PnL:    {:.4}", res.final_pnl)?;
+    writeln!(w, "Max drawdown: {:.4}", max_drawdown(&wealth))?;
+    writeln!(
+        w,
+        "Fills:        {} bid, {} ask",
+        res.bid_fills, res.ask_fills
+    )
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let params = Parameters::new(
+        args.gamma,
+        args.sigma,
+        args.t_horizon,
+        args.k,
+        args.a,
+        QuotingModel::AvellanedaStoikov,
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("invalid parameters: {e}");
+        std::process::exit(2);
+    });
+    let sim_config = SimConfig::builder()
+        .dt(args.dt)
+        .num_steps(args.num_steps)
+        .s_0(args.s0)
+        .drift(args.drift)
+        .latency_steps(args.latency_steps)
+        .build()
+        .unwrap_or_else(|e| {
+            eprintln!("invalid simulation settings: {e}");
+            std::process::exit(2);
+        });
+    let intensity = ExponentialIntensity {
+        k: args.k,
+        a: args.a,
+    };
+
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_rng(&mut rand::rng()),
+    };
+    let res = run_trajectory_with_rng(&params, &sim_config, &intensity, &mut rng);
+
+    let written = match &args.output {
+        Some(path) => File::create(path)
+            .and_then(|file| write_trajectory_csv(&res, BufWriter::new(file)))
+            .map_err(|err| format!("failed to write {}: {err}", path.display())),
+        None => write_trajectory_csv(&res, io::stdout().lock())
+            .map_err(|err| format!("failed to write CSV: {err}")),
+    };
+    if let Err(message) = written {
+        eprintln!("{message}");
+        std::process::exit(1);
+    }
+
+    if args.summary {
+        let printed = match args.output {
+            Some(_) => write_summary(&res, io::stdout().lock()),
+            None => write_summary(&res, io::stderr().lock()),
+        };
+        printed.expect("failed to write summary");
+    }
+}
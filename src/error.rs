@@ -0,0 +1,66 @@
+//! The crate-wide error for inputs rejected before any simulation runs.
+
+use crate::model::ParamError;
+use crate::sim::SimError;
+use std::fmt;
+
+/// Why a simulation or sweep input was rejected up front, wrapping the error of the stage that
+/// found the problem.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// The base strategy parameters are outside the model's domain.
+    Params(ParamError),
+    /// The simulation settings are invalid, see `SimConfig::validate`.
+    Sim(SimError),
+    /// The named sweep axis (`gammas`, `sigmas`, `ks`, or `drifts`) has no values, so the grid
+    /// would be empty.
+    EmptyAxis(&'static str),
+    /// `iterations_per_param` is zero, so there is nothing to average.
+    NoIterations,
+    /// The named setting lies outside the range it is `expected` in, e.g. `in [0.5, 1)`.
+    OutOfRange {
+        name: &'static str,
+        value: f64,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Params(err) => write!(f, "invalid parameters: {err}"),
+            ConfigError::Sim(err) => write!(f, "invalid simulation settings: {err}"),
+            ConfigError::EmptyAxis(axis) => write!(f, "sweep axis `{axis}` is empty"),
+            ConfigError::NoIterations => write!(f, "`iterations_per_param` must be > 0"),
+            ConfigError::OutOfRange {
+                name,
+                value,
+                expected,
+            } => write!(f, "`{name}` must be {expected}, got {value}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Params(err) => Some(err),
+            ConfigError::Sim(err) => Some(err),
+            ConfigError::EmptyAxis(_)
+            | ConfigError::NoIterations
+            | ConfigError::OutOfRange { .. } => None,
+        }
+    }
+}
+
+impl From<ParamError> for ConfigError {
+    fn from(err: ParamError) -> Self {
+        ConfigError::Params(err)
+    }
+}
+
+impl From<SimError> for ConfigError {
+    fn from(err: SimError) -> Self {
+        ConfigError::Sim(err)
+    }
+}
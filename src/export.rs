@@ -0,0 +1,807 @@
+#[cfg(feature = "parquet")]
+use crate::analysis::ParamPoint;
+use crate::analysis::SweepResult;
+#[cfg(feature = "parquet")]
+use crate::sim::StepRecord;
+use crate::sim::{Side, SimResult};
+use std::io::{self, Write};
+
+/// Kind of quantity a column holds, used to pick its precision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumberClass {
+    Param,    // Swept model inputs (gamma, sigma, k, drift)
+    Price,    // Price levels and price distances
+    Pnl,      // PnL levels, dispersion, and penalties
+    Ratio,    // Dimensionless ratios and fractions
+    Quantity, // Inventory statistics
+}
+
+/// Number formatting shared by all exporters.
+///
+/// Output is produced with Rust's `format!`, which never consults the process locale: the
+/// decimal separator is always `.` and there are no thousands separators. With `scientific` off,
+/// values are written in plain fixed-point notation, so a value formatted with `d` decimals parses
+/// back to within `0.5 * 10^-d` of the original; that rounding is the only precision lost.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FormatOptions {
+    pub param_decimals: usize,
+    pub price_decimals: usize,
+    pub pnl_decimals: usize,
+    pub ratio_decimals: usize,
+    pub quantity_decimals: usize,
+    pub scientific: bool,
+}
+
+impl Default for FormatOptions {
+    /// Six decimals for swept parameters, enough to tell apart any grid or random-search point
+    /// in practice, and four for the outcome statistics.
+    fn default() -> Self {
+        Self {
+            param_decimals: 6,
+            price_decimals: 4,
+            pnl_decimals: 4,
+            ratio_decimals: 4,
+            quantity_decimals: 2,
+            scientific: false,
+        }
+    }
+}
+
+impl FormatOptions {
+    /// The precision of the original `run_analysis` table, whose parameter columns are eight
+    /// characters wide.
+    pub fn table() -> Self {
+        Self {
+            param_decimals: 2,
+            ..Self::default()
+        }
+    }
+
+    /// Fewer decimals for large exports where file size matters more than the last digits.
+    pub fn compact() -> Self {
+        Self {
+            param_decimals: 4,
+            price_decimals: 2,
+            pnl_decimals: 2,
+            ratio_decimals: 3,
+            quantity_decimals: 1,
+            scientific: false,
+        }
+    }
+
+    pub fn decimals(&self, class: NumberClass) -> usize {
+        match class {
+            NumberClass::Param => self.param_decimals,
+            NumberClass::Price => self.price_decimals,
+            NumberClass::Pnl => self.pnl_decimals,
+            NumberClass::Ratio => self.ratio_decimals,
+            NumberClass::Quantity => self.quantity_decimals,
+        }
+    }
+
+    pub fn format(&self, value: f64, class: NumberClass) -> String {
+        let decimals = self.decimals(class);
+        if self.scientific {
+            format!("{value:.decimals$e}")
+        } else {
+            format!("{value:.decimals$}")
+        }
+    }
+}
+
+type Column = (&'static str, NumberClass, fn(&SweepResult) -> f64);
+
+/// Every exported `SweepResult` field, in output order.
+const SWEEP_COLUMNS: &[Column] = &[
+    ("gamma", NumberClass::Param, |r| r.gamma),
+    ("sigma", NumberClass::Param, |r| r.sigma),
+    ("k", NumberClass::Param, |r| r.k),
+    ("drift", NumberClass::Param, |r| r.drift),
+    ("a", NumberClass::Param, |r| r.a),
+    ("t_horizon", NumberClass::Param, |r| r.t_horizon),
+    ("latency_steps", NumberClass::Param, |r| {
+        r.latency_steps as f64
+    }),
+    ("mean_pnl", NumberClass::Pnl, |r| r.mean_pnl),
+    ("std_pnl", NumberClass::Pnl, |r| r.std_pnl),
+    ("pnl_sem", NumberClass::Pnl, |r| r.pnl_sem),
+    ("cv_mean_pnl", NumberClass::Pnl, |r| r.cv_mean_pnl),
+    ("cv_pnl_sem", NumberClass::Pnl, |r| r.cv_pnl_sem),
+    ("median_pnl", NumberClass::Pnl, |r| r.median_pnl),
+    ("iqr_pnl", NumberClass::Pnl, |r| r.iqr_pnl),
+    ("mean_pnl_ci_low", NumberClass::Pnl, |r| r.mean_pnl_ci_low),
+    ("mean_pnl_ci_high", NumberClass::Pnl, |r| r.mean_pnl_ci_high),
+    ("sharpe_ratio", NumberClass::Ratio, |r| r.sharpe_ratio),
+    ("sharpe_annualized", NumberClass::Ratio, |r| {
+        r.sharpe_annualized
+    }),
+    ("sharpe_ci_low", NumberClass::Ratio, |r| r.sharpe_ci_low),
+    ("sharpe_ci_high", NumberClass::Ratio, |r| r.sharpe_ci_high),
+    ("pnl_p05", NumberClass::Pnl, |r| r.pnl_p05),
+    ("pnl_p95", NumberClass::Pnl, |r| r.pnl_p95),
+    ("var_95", NumberClass::Pnl, |r| r.var_95),
+    ("cvar_95", NumberClass::Pnl, |r| r.cvar_95),
+    ("sortino_ratio", NumberClass::Ratio, |r| r.sortino_ratio),
+    ("calmar_ratio", NumberClass::Ratio, |r| r.calmar_ratio),
+    ("max_drawdown", NumberClass::Pnl, |r| r.max_drawdown),
+    ("mean_abs_inventory", NumberClass::Quantity, |r| {
+        r.mean_abs_inventory
+    }),
+    ("time_weighted_abs_inventory", NumberClass::Quantity, |r| {
+        r.time_weighted_abs_inventory
+    }),
+    ("mean_inventory_variance", NumberClass::Quantity, |r| {
+        r.mean_inventory_variance
+    }),
+    ("mean_inventory_autocorr_lag1", NumberClass::Ratio, |r| {
+        r.mean_inventory_autocorr_lag1
+    }),
+    ("max_inventory", NumberClass::Quantity, |r| r.max_inventory),
+    ("terminal_inventory_mean", NumberClass::Quantity, |r| {
+        r.terminal_inventory_mean
+    }),
+    ("terminal_inventory_std", NumberClass::Quantity, |r| {
+        r.terminal_inventory_std
+    }),
+    ("mean_running_penalty", NumberClass::Pnl, |r| {
+        r.mean_running_penalty
+    }),
+    ("mean_objective", NumberClass::Pnl, |r| r.mean_objective),
+    ("mean_carry_paid", NumberClass::Pnl, |r| r.mean_carry_paid),
+    ("quote_staleness_mean", NumberClass::Price, |r| {
+        r.quote_staleness_mean
+    }),
+    ("quote_staleness_exceed_frac", NumberClass::Ratio, |r| {
+        r.quote_staleness_exceed_frac
+    }),
+    ("stressed_fraction_mean", NumberClass::Ratio, |r| {
+        r.stressed_fraction_mean
+    }),
+    ("mean_calm_pnl", NumberClass::Pnl, |r| r.mean_calm_pnl),
+    ("mean_stressed_pnl", NumberClass::Pnl, |r| {
+        r.mean_stressed_pnl
+    }),
+    ("mean_spread_pnl", NumberClass::Pnl, |r| r.mean_spread_pnl),
+    ("mean_inventory_pnl", NumberClass::Pnl, |r| {
+        r.mean_inventory_pnl
+    }),
+    ("mean_liquidity", NumberClass::Ratio, |r| r.mean_liquidity),
+    ("pnl_liquidity_corr", NumberClass::Ratio, |r| {
+        r.pnl_liquidity_corr
+    }),
+    ("stopped_frac", NumberClass::Ratio, |r| r.stopped_frac),
+    ("mean_fills_per_run", NumberClass::Quantity, |r| {
+        r.mean_fills_per_run
+    }),
+    ("mean_realized_half_spread", NumberClass::Price, |r| {
+        r.mean_realized_half_spread
+    }),
+    ("mean_traded_notional", NumberClass::Pnl, |r| {
+        r.mean_traded_notional
+    }),
+    ("mean_spread_capture", NumberClass::Price, |r| {
+        r.mean_spread_capture
+    }),
+    ("baseline_mean_pnl", NumberClass::Pnl, |r| {
+        r.baseline_mean_pnl
+    }),
+    ("baseline_std_pnl", NumberClass::Pnl, |r| r.baseline_std_pnl),
+    ("pnl_uplift", NumberClass::Pnl, |r| r.pnl_uplift),
+];
+
+/// Whether any result carries a `model_name`, in which case the exports lead with a `model_name`
+/// column.
+fn has_model_names(results: &[SweepResult]) -> bool {
+    results.iter().any(|r| !r.model_name.is_empty())
+}
+
+/// Writes one CSV row per result, with a header row naming every `SweepResult` field.
+///
+/// Results of `run_sweep_models` get a leading `model_name` column, quoted.
+pub fn write_sweep_csv<W: Write>(
+    results: &[SweepResult],
+    mut w: W,
+    options: &FormatOptions,
+) -> io::Result<()> {
+    let named = has_model_names(results);
+    let mut header: Vec<&str> = SWEEP_COLUMNS.iter().map(|(name, _, _)| *name).collect();
+    if named {
+        header.insert(0, "model_name");
+    }
+    writeln!(w, "{}", header.join(","))?;
+
+    for res in results {
+        let mut row: Vec<String> = SWEEP_COLUMNS
+            .iter()
+            .map(|(_, class, get)| options.format(get(res), *class))
+            .collect();
+        if named {
+            row.insert(0, format!("\"{}\"", res.model_name.replace('"', "\"\"")));
+        }
+        writeln!(w, "{}", row.join(","))?;
+    }
+
+    Ok(())
+}
+
+/// Writes the fixed-width summary table printed by `run_analysis`.
+///
+/// Results of `run_sweep_models` are listed under a `Model:` heading each time the model changes.
+pub fn write_sweep_table<W: Write>(
+    results: &[SweepResult],
+    mut w: W,
+    options: &FormatOptions,
+) -> io::Result<()> {
+    use NumberClass::*;
+
+    writeln!(
+        w,
+        "{:<8} {:<8} {:<6} {:<8} {:<8} {:<6} {:<4} | {:<12} {:<12} {:<10} {:<12} | {:<10} {:<10} | {:<10}",
+        "Gamma",
+        "Sigma",
+        "K",
+        "Drift",
+        "A",
+        "T",
+        "Lat",
+        "Mean PnL",
+        "Std PnL",
+        "Sharpe",
+        "CVaR",
+        "Mean |Q|",
+        "Final Q",
+        "Fills/Run"
+    )?;
+    writeln!(w, "{}", "-".repeat(148))?;
+
+    let mut model = "";
+    for res in results {
+        if res.model_name != model {
+            model = &res.model_name;
+            writeln!(w, "Model: {model}")?;
+        }
+        writeln!(
+            w,
+            "{:<8} {:<8} {:<6} {:<8} {:<8} {:<6} {:<4} | {:<12} {:<12} {:<10} {:<12} | {:<10} {:<10} | {:<10}",
+            options.format(res.gamma, Param),
+            options.format(res.sigma, Param),
+            options.format(res.k, Param),
+            options.format(res.drift, Param),
+            options.format(res.a, Param),
+            options.format(res.t_horizon, Param),
+            res.latency_steps,
+            options.format(res.mean_pnl, Pnl),
+            options.format(res.std_pnl, Pnl),
+            options.format(res.sharpe_ratio, Ratio),
+            options.format(res.cvar_95, Pnl),
+            options.format(res.mean_abs_inventory, Quantity),
+            options.format(res.terminal_inventory_mean, Quantity),
+            options.format(res.mean_fills_per_run, Quantity)
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Writes one CSV row per step of a single trajectory: `time`, `mid`, `bid`, `ask`,
+/// `reservation_price`, `inventory`, `cash`, `wealth`, and the sizes `bid_fill` and `ask_fill`
+/// traded at the end of the step (zero without a fill).
+///
+/// Values are written at full precision rather than through `FormatOptions`, so the file
+/// reproduces the run exactly and a seeded run always gives the same bytes. Quotes are `NaN`
+/// once the kill-switch has halted quoting.
+pub fn write_trajectory_csv<W: Write>(result: &SimResult, mut w: W) -> io::Result<()> {
+    writeln!(
+        w,
+        "time,mid,bid,ask,reservation_price,inventory,cash,wealth,bid_fill,ask_fill"
+    )?;
+
+    let mut fills = result.fills.iter().peekable();
+    for (step, record) in result.trajectory.iter().enumerate() {
+        let (mut bid_fill, mut ask_fill) = (0.0, 0.0);
+        while let Some(fill) = fills.next_if(|fill| fill.step == step) {
+            match fill.side {
+                Side::Bid => bid_fill += fill.size,
+                Side::Ask => ask_fill += fill.size,
+            }
+        }
+        writeln!(
+            w,
+            "{},{},{},{},{},{},{},{},{},{}",
+            record.time,
+            record.mid_price,
+            record.bid_price,
+            record.ask_price,
+            record.reservation_price,
+            record.inventory,
+            record.cash,
+            record.wealth,
+            bid_fill,
+            ask_fill
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Writes `results` as a JSON array with one object per result, keyed by field name.
+///
+/// Every exported column is rounded to the precision `options` gives its class, as in
+/// `write_sweep_csv`; `scientific` has no effect, since JSON numbers carry no notation. Keys come
+/// out in alphabetical order. JSON has no representation for NaN or infinity; serde_json writes
+/// such values as `null`. Serialize the results directly for an exact copy.
+#[cfg(feature = "serde")]
+pub fn write_sweep_json<W: Write>(
+    results: &[SweepResult],
+    w: W,
+    options: &FormatOptions,
+) -> serde_json::Result<()> {
+    let rounded = results
+        .iter()
+        .map(|res| {
+            let mut value = serde_json::to_value(res)?;
+            if let Some(fields) = value.as_object_mut() {
+                for (name, class, get) in SWEEP_COLUMNS {
+                    // Integer columns are exact already, and non-finite values are `null`.
+                    let Some(field) = fields.get_mut(*name).filter(|v| v.is_f64()) else {
+                        continue;
+                    };
+                    let rounded = format!("{:.*}", options.decimals(*class), get(res));
+                    if let Ok(number) = rounded.parse::<serde_json::Number>() {
+                        *field = serde_json::Value::Number(number);
+                    }
+                }
+            }
+            Ok(value)
+        })
+        .collect::<serde_json::Result<Vec<_>>>()?;
+    serde_json::to_writer(w, &rounded)
+}
+
+/// Writes `results` to a Parquet file at `path`, one row per result.
+///
+/// See `write_sweep_parquet_to` for the schema.
+#[cfg(feature = "parquet")]
+pub fn write_sweep_parquet<P: AsRef<std::path::Path>>(
+    results: &[SweepResult],
+    path: P,
+) -> parquet::errors::Result<()> {
+    write_sweep_parquet_to(results, std::fs::File::create(path)?)
+}
+
+/// Writes `results` as Parquet to `w` in a single record batch, one row per result.
+///
+/// The columns are those of `write_sweep_csv`, in the same order: a non-null `Utf8`
+/// `model_name` when any result has one, `latency_steps` as a non-null `UInt64`, and every other
+/// field a non-null `Float64`, with NaN and infinity written as such rather than as nulls.
+/// `SweepResult::profile` is not exported.
+#[cfg(feature = "parquet")]
+pub fn write_sweep_parquet_to<W: Write + Send>(
+    results: &[SweepResult],
+    w: W,
+) -> parquet::errors::Result<()> {
+    use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, UInt64Array};
+    use arrow_schema::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+    use std::sync::Arc;
+
+    let (mut fields, mut columns): (Vec<Field>, Vec<ArrayRef>) = SWEEP_COLUMNS
+        .iter()
+        .map(|&(name, _, get)| {
+            if name == "latency_steps" {
+                let values = results.iter().map(|r| r.latency_steps as u64);
+                (
+                    Field::new(name, DataType::UInt64, false),
+                    Arc::new(UInt64Array::from_iter_values(values)) as ArrayRef,
+                )
+            } else {
+                (
+                    Field::new(name, DataType::Float64, false),
+                    Arc::new(Float64Array::from_iter_values(results.iter().map(get))) as ArrayRef,
+                )
+            }
+        })
+        .unzip();
+    if has_model_names(results) {
+        let names = results.iter().map(|r| Some(r.model_name.as_str()));
+        fields.insert(0, Field::new("model_name", DataType::Utf8, false));
+        columns.insert(0, Arc::new(StringArray::from_iter(names)));
+    }
+    let schema = Arc::new(Schema::new(fields));
+
+    let mut writer = ArrowWriter::try_new(w, schema.clone(), None)?;
+    writer.write(&RecordBatch::try_new(schema, columns)?)?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Writes every trajectory in `results` to a Parquet file at `path`, one row per step.
+///
+/// See `write_trajectories_parquet_to` for the schema.
+#[cfg(feature = "parquet")]
+pub fn write_trajectories_parquet<P: AsRef<std::path::Path>>(
+    results: &[(ParamPoint, SimResult)],
+    path: P,
+) -> parquet::errors::Result<()> {
+    write_trajectories_parquet_to(results, std::fs::File::create(path)?)
+}
+
+/// Writes every trajectory in `results` as Parquet to `w`, one record batch per trajectory.
+///
+/// The schema, in column order:
+///
+/// - one non-null `Float64` column per `ParamPoint` coordinate, named after its axis; every point
+///   must have the same axes in the same order as the first,
+/// - `trajectory_id: UInt64`, the index of the trajectory in `results`,
+/// - `step: UInt64`, the step index within the trajectory,
+/// - `time`, `mid`, `bid`, `ask`, `inventory`, `cash`, `wealth: Float64`; `bid`/`ask` are NaN
+///   once the kill-switch has halted quoting.
+#[cfg(feature = "parquet")]
+pub fn write_trajectories_parquet_to<W: Write + Send>(
+    results: &[(ParamPoint, SimResult)],
+    w: W,
+) -> parquet::errors::Result<()> {
+    use arrow_array::{ArrayRef, Float64Array, RecordBatch, UInt64Array};
+    use arrow_schema::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+    use parquet::errors::ParquetError;
+    use std::sync::Arc;
+
+    let axes: Vec<&'static str> = results
+        .first()
+        .map(|(point, _)| point.coords.iter().map(|&(name, _)| name).collect())
+        .unwrap_or_default();
+
+    let mut fields: Vec<Field> = axes
+        .iter()
+        .map(|&name| Field::new(name, DataType::Float64, false))
+        .collect();
+    fields.extend([
+        Field::new("trajectory_id", DataType::UInt64, false),
+        Field::new("step", DataType::UInt64, false),
+        Field::new("time", DataType::Float64, false),
+        Field::new("mid", DataType::Float64, false),
+        Field::new("bid", DataType::Float64, false),
+        Field::new("ask", DataType::Float64, false),
+        Field::new("inventory", DataType::Float64, false),
+        Field::new("cash", DataType::Float64, false),
+        Field::new("wealth", DataType::Float64, false),
+    ]);
+    let schema = Arc::new(Schema::new(fields));
+
+    let mut writer = ArrowWriter::try_new(w, schema.clone(), None)?;
+    for (id, (point, res)) in results.iter().enumerate() {
+        if !point
+            .coords
+            .iter()
+            .map(|&(name, _)| name)
+            .eq(axes.iter().copied())
+        {
+            return Err(ParquetError::General(format!(
+                "trajectory {id} has different sweep axes from trajectory 0"
+            )));
+        }
+
+        let n = res.trajectory.len();
+        let steps = &res.trajectory;
+        let floats = |get: fn(&StepRecord) -> f64| -> ArrayRef {
+            Arc::new(Float64Array::from_iter_values(steps.iter().map(get)))
+        };
+
+        let mut columns: Vec<ArrayRef> = point
+            .coords
+            .iter()
+            .map(|&(_, value)| Arc::new(Float64Array::from(vec![value; n])) as ArrayRef)
+            .collect();
+        columns.extend([
+            Arc::new(UInt64Array::from(vec![id as u64; n])) as ArrayRef,
+            Arc::new(UInt64Array::from_iter_values(0..n as u64)),
+            floats(|s| s.time),
+            floats(|s| s.mid_price),
+            floats(|s| s.bid_price),
+            floats(|s| s.ask_price),
+            floats(|s| s.inventory),
+            floats(|s| s.cash),
+            floats(|s| s.wealth),
+        ]);
+
+        writer.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
+    }
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_result(mean_pnl: f64, sharpe_ratio: f64) -> SweepResult {
+        SweepResult {
+            gamma: 0.1,
+            sigma: 0.2,
+            k: 1.5,
+            drift: 0.0,
+            mean_pnl,
+            std_pnl: 12.345678,
+            sharpe_ratio,
+            mean_abs_inventory: 3.21,
+            max_inventory: 9.0,
+            terminal_inventory_mean: -0.4,
+            terminal_inventory_std: 2.5,
+            mean_objective: mean_pnl,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_large_values_avoid_scientific_notation() {
+        let options = FormatOptions::default();
+        assert_eq!(
+            options.format(1234567.891, NumberClass::Pnl),
+            "1234567.8910"
+        );
+        assert_eq!(options.format(1e-7, NumberClass::Pnl), "0.0000");
+        assert_eq!(
+            FormatOptions::compact().format(1234567.891, NumberClass::Pnl),
+            "1234567.89"
+        );
+    }
+
+    #[test]
+    fn test_ratios_keep_four_decimals_by_default() {
+        let options = FormatOptions::default();
+        assert!(options.ratio_decimals >= 4);
+        assert_eq!(options.format(0.123456, NumberClass::Ratio), "0.1235");
+    }
+
+    #[test]
+    fn test_csv_round_trips_within_precision() {
+        let results = vec![
+            sample_result(1234567.891, 0.987654321),
+            sample_result(-42.123456, -1.5),
+        ];
+
+        for options in [FormatOptions::default(), FormatOptions::compact()] {
+            let mut buf = Vec::new();
+            write_sweep_csv(&results, &mut buf, &options).unwrap();
+            let text = String::from_utf8(buf).unwrap();
+
+            let mut lines = text.lines();
+            let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+            assert_eq!(header.len(), SWEEP_COLUMNS.len());
+
+            for (res, line) in results.iter().zip(lines) {
+                for (field, (_, class, get)) in line.split(',').zip(SWEEP_COLUMNS) {
+                    let parsed: f64 = field.parse().unwrap();
+                    let tolerance = 0.5 * 10f64.powi(-(options.decimals(*class) as i32)) + 1e-12;
+                    assert!((parsed - get(res)).abs() <= tolerance);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_model_names_lead_csv_and_group_table() {
+        let named = |model_name: &str, mean_pnl| SweepResult {
+            model_name: model_name.to_string(),
+            ..sample_result(mean_pnl, 0.5)
+        };
+        let results = vec![
+            named("exponential", 1.0),
+            named("exponential", 2.0),
+            named("power_law", 3.0),
+        ];
+
+        let mut buf = Vec::new();
+        write_sweep_csv(&results, &mut buf, &FormatOptions::default()).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("model_name,gamma,"));
+        assert!(lines[3].starts_with("\"power_law\",0.100000,"));
+        assert_eq!(lines[1].split(',').count(), SWEEP_COLUMNS.len() + 1);
+
+        let mut buf = Vec::new();
+        write_sweep_table(&results, &mut buf, &FormatOptions::default()).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        let headings: Vec<&str> = text.lines().filter(|l| l.starts_with("Model:")).collect();
+        assert_eq!(headings, ["Model: exponential", "Model: power_law"]);
+
+        // Unnamed results keep the plain layout.
+        let mut buf = Vec::new();
+        write_sweep_table(
+            &[sample_result(1.0, 0.5)],
+            &mut buf,
+            &FormatOptions::default(),
+        )
+        .unwrap();
+        assert!(!String::from_utf8(buf).unwrap().contains("Model:"));
+    }
+
+    #[test]
+    fn test_trajectory_csv_is_reproducible() {
+        use crate::model::{ExponentialIntensity, Parameters, QuotingModel};
+        use crate::sim::{SimConfig, run_trajectory_with_rng};
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let config = SimConfig::builder().num_steps(50).build().unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let csv = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let res = run_trajectory_with_rng(&params, &config, &intensity, &mut rng);
+            let mut buf = Vec::new();
+            write_trajectory_csv(&res, &mut buf).unwrap();
+            (res, String::from_utf8(buf).unwrap())
+        };
+
+        let (res, text) = csv(4);
+        assert_eq!(text, csv(4).1);
+        assert_ne!(text, csv(5).1);
+
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 51);
+        assert_eq!(lines[0].split(',').nth(4), Some("reservation_price"));
+        // Every fill lands on its step's row, and zero latency quotes centre on the reservation
+        // price.
+        let mut traded = 0.0;
+        for (line, record) in lines[1..].iter().zip(&res.trajectory) {
+            let fields: Vec<f64> = line.split(',').map(|f| f.parse().unwrap()).collect();
+            assert_eq!(fields[1], record.mid_price);
+            assert!(((fields[2] + fields[3]) / 2.0 - fields[4]).abs() < 1e-9);
+            traded += fields[8] + fields[9];
+        }
+        assert!(traded > 0.0);
+        assert_eq!(traded, res.fills.iter().map(|f| f.size).sum::<f64>());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_round_trip() {
+        let results = vec![
+            sample_result(1234567.891, 0.987654321),
+            sample_result(-42.123456, -1.5),
+        ];
+
+        for options in [FormatOptions::default(), FormatOptions::compact()] {
+            let mut buf = Vec::new();
+            write_sweep_json(&results, &mut buf, &options).unwrap();
+            let parsed: Vec<SweepResult> = serde_json::from_slice(&buf).unwrap();
+            assert_eq!(parsed.len(), results.len());
+            for (back, res) in parsed.iter().zip(&results) {
+                assert_eq!(back.latency_steps, res.latency_steps);
+                for (name, class, get) in SWEEP_COLUMNS {
+                    let tolerance = 0.5 * 10f64.powi(-(options.decimals(*class) as i32)) + 1e-9;
+                    assert!((get(back) - get(res)).abs() <= tolerance, "{name} drifted");
+                }
+            }
+            if options == FormatOptions::compact() {
+                assert!(
+                    String::from_utf8(buf)
+                        .unwrap()
+                        .contains("\"mean_pnl\":1234567.89,")
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_parquet_round_trip() {
+        use arrow_array::cast::AsArray;
+        use arrow_array::types::{Float64Type, UInt64Type};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let run = |n: usize, offset: f64| SimResult {
+            trajectory: (0..n)
+                .map(|i| StepRecord {
+                    time: i as f64 * 0.01,
+                    mid_price: offset + i as f64,
+                    inventory: i as f64 - 1.0,
+                    bid_price: f64::NAN,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let point = |gamma: f64| ParamPoint {
+            coords: vec![("gamma", gamma), ("latency_steps", 2.0)],
+        };
+        let results = vec![(point(0.1), run(3, 100.0)), (point(0.5), run(2, 50.0))];
+
+        let path = std::env::temp_dir().join(format!("as_export_{}.parquet", std::process::id()));
+        write_trajectories_parquet(&results, &path).unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<_> = reader.map(Result::unwrap).collect();
+        std::fs::remove_file(&path).unwrap();
+
+        let schema = batches[0].schema();
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "gamma",
+                "latency_steps",
+                "trajectory_id",
+                "step",
+                "time",
+                "mid",
+                "bid",
+                "ask",
+                "inventory",
+                "cash",
+                "wealth"
+            ]
+        );
+
+        let floats = |name: &str| -> Vec<f64> {
+            batches
+                .iter()
+                .flat_map(|b| b[name].as_primitive::<Float64Type>().values().to_vec())
+                .collect()
+        };
+        let ids: Vec<u64> = batches
+            .iter()
+            .flat_map(|b| {
+                b["trajectory_id"]
+                    .as_primitive::<UInt64Type>()
+                    .values()
+                    .to_vec()
+            })
+            .collect();
+
+        assert_eq!(floats("gamma"), [0.1, 0.1, 0.1, 0.5, 0.5]);
+        assert_eq!(ids, [0, 0, 0, 1, 1]);
+        assert_eq!(floats("mid"), [100.0, 101.0, 102.0, 50.0, 51.0]);
+        assert_eq!(floats("inventory"), [-1.0, 0.0, 1.0, -1.0, 0.0]);
+        assert!(floats("bid").iter().all(|b| b.is_nan()));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_sweep_parquet_round_trip() {
+        use arrow_array::cast::AsArray;
+        use arrow_array::types::{Float64Type, UInt64Type};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let mut results = vec![
+            sample_result(1234567.891, 0.987654321),
+            sample_result(-42.123456, -1.5),
+            sample_result(7.0, f64::INFINITY),
+        ];
+        results[1].latency_steps = 3;
+        results[2].sharpe_ci_low = f64::NAN;
+
+        let path = std::env::temp_dir().join(format!("as_sweep_{}.parquet", std::process::id()));
+        write_sweep_parquet(&results, &path).unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<_> = reader.map(Result::unwrap).collect();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+        let batch = &batches[0];
+        assert_eq!(batch.num_columns(), SWEEP_COLUMNS.len());
+        let floats = |name: &str| batch[name].as_primitive::<Float64Type>().values().to_vec();
+
+        assert_eq!(floats("mean_pnl"), [1234567.891, -42.123456, 7.0]);
+        assert_eq!(floats("sharpe_ratio")[..2], [0.987654321, -1.5]);
+        assert_eq!(floats("sharpe_ratio")[2], f64::INFINITY);
+        assert!(floats("sharpe_ci_low")[2].is_nan());
+        assert_eq!(
+            batch["latency_steps"]
+                .as_primitive::<UInt64Type>()
+                .values()
+                .to_vec(),
+            [0, 3, 0]
+        );
+    }
+}
@@ -1,3 +1,12 @@
 pub mod analysis;
+pub mod error;
+pub mod export;
 pub mod model;
+pub mod optimize;
+pub mod pair;
+mod par;
+#[cfg(feature = "plot")]
+pub mod plot;
 pub mod sim;
+pub mod sizes;
+pub mod stats;
@@ -1,16 +1,164 @@
 use std::f64;
+use std::fmt;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
 pub struct Parameters {
-    pub gamma: f64,     // Risk aversion
-    pub sigma: f64,     // Volatility
-    pub t_horizon: f64, // T (end time)
-    pub k: f64,         // Liquidity parameter (used for strategy calc)
-    pub a: f64,         // Base arrival rate (used for strategy calc)
+    pub gamma: f64,                  // Risk aversion
+    pub sigma: f64,                  // Volatility
+    pub t_horizon: f64,              // T (end time)
+    pub k: f64,                      // Liquidity parameter (used for strategy calc)
+    pub a: f64,                      // Base arrival rate (used for strategy calc)
+    pub quoting_model: QuotingModel, // Inventory skew formulation
+}
+
+impl Parameters {
+    /// Builds validated parameters; see `validate` for the accepted domain.
+    pub fn new(
+        gamma: f64,
+        sigma: f64,
+        t_horizon: f64,
+        k: f64,
+        a: f64,
+        quoting_model: QuotingModel,
+    ) -> Result<Self, ParamError> {
+        let params = Self {
+            gamma,
+            sigma,
+            t_horizon,
+            k,
+            a,
+            quoting_model,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Checks `gamma, k, a, t_horizon > 0` and `sigma, phi >= 0`.
+    ///
+    /// `optimal_spread` divides by `gamma` and takes `ln(1 + gamma / k)`, so values outside this
+    /// domain would otherwise surface as NaN quotes rather than an error.
+    pub fn validate(&self) -> Result<(), ParamError> {
+        ensure_positive("gamma", self.gamma)?;
+        ensure_non_negative("sigma", self.sigma)?;
+        ensure_positive("k", self.k)?;
+        ensure_positive("a", self.a)?;
+        ensure_positive("t_horizon", self.t_horizon)?;
+        match self.quoting_model {
+            QuotingModel::RunningPenalty { phi } => ensure_non_negative("phi", phi)?,
+            QuotingModel::FixedSpread { spread } => ensure_positive("spread", spread)?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Selects how the agent penalises inventory when skewing its reservation price.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
+pub enum QuotingModel {
+    /// Terminal-utility formulation of Avellaneda-Stoikov: skew `gamma * sigma^2 * (T - t)`.
+    #[default]
+    AvellanedaStoikov,
+    /// Guéant-style running penalty of `phi * q^2` per unit time, giving a horizon-independent skew.
+    RunningPenalty { phi: f64 },
+    /// Infinite-horizon (stationary) quotes: the asymptotic Guéant-Lehalle-Fernandez-Tapia skew and
+    /// spread, which do not depend on `t`.
+    Stationary,
+    /// Naive symmetric maker: quotes are centred on the mid with no inventory skew, using the
+    /// Avellaneda-Stoikov spread for their width. Useful as an A/B baseline.
+    SymmetricMid,
+    /// Quotes centred on the mid with a constant total width `spread`, whatever the inventory or
+    /// time. See `mean_optimal_spread` for a width comparable to an Avellaneda-Stoikov agent's
+    /// over a run.
+    FixedSpread { spread: f64 },
+    /// Avellaneda-Stoikov quotes around a reservation price raised by the expected move
+    /// `drift * s * (T - t)` over the rest of the horizon, so the maker leans into a known trend.
+    /// `drift` is the simulation's own relative drift per unit time, `SimConfig::drift`, so a
+    /// sweep over drifts moves the maker's view with the market.
+    DriftAdjusted,
+}
+
+impl QuotingModel {
+    /// Inventory penalty charged per unit time per unit of `q^2`.
+    pub fn running_penalty_rate(&self) -> f64 {
+        match *self {
+            QuotingModel::AvellanedaStoikov
+            | QuotingModel::Stationary
+            | QuotingModel::SymmetricMid
+            | QuotingModel::FixedSpread { .. }
+            | QuotingModel::DriftAdjusted => 0.0,
+            QuotingModel::RunningPenalty { phi } => phi,
+        }
+    }
 }
 
 /// Defines how the market intensity (arrival rate of fill) depends on the distance from mid-price.
 pub trait IntensityModel: Send + Sync {
     fn calculate_intensity(&self, delta: f64) -> f64;
+
+    /// Arrival rate of sell orders hitting our bid `delta` below the mid.
+    fn bid_intensity(&self, delta: f64) -> f64 {
+        self.calculate_intensity(delta)
+    }
+
+    /// Arrival rate of buy orders lifting our ask `delta` above the mid.
+    fn ask_intensity(&self, delta: f64) -> f64 {
+        self.calculate_intensity(delta)
+    }
+}
+
+/// Any `Fn(delta) -> intensity` closure is an intensity model, for prototyping new shapes.
+impl<F> IntensityModel for F
+where
+    F: Fn(f64) -> f64 + Send + Sync,
+{
+    fn calculate_intensity(&self, delta: f64) -> f64 {
+        self(delta)
+    }
+}
+
+/// Separate intensity curves for the two sides of the book, for asymmetric order flow.
+pub struct MarketIntensity {
+    pub bid: Box<dyn IntensityModel>,
+    pub ask: Box<dyn IntensityModel>,
+}
+
+impl MarketIntensity {
+    pub fn new(bid: impl IntensityModel + 'static, ask: impl IntensityModel + 'static) -> Self {
+        Self {
+            bid: Box::new(bid),
+            ask: Box::new(ask),
+        }
+    }
+
+    /// Uses the same curve on both sides, equivalent to passing `model` directly.
+    pub fn symmetric<M: IntensityModel + Clone + 'static>(model: M) -> Self {
+        Self::new(model.clone(), model)
+    }
+}
+
+impl IntensityModel for MarketIntensity {
+    /// Side-agnostic callers see the average of the two curves.
+    fn calculate_intensity(&self, delta: f64) -> f64 {
+        0.5 * (self.bid.bid_intensity(delta) + self.ask.ask_intensity(delta))
+    }
+
+    fn bid_intensity(&self, delta: f64) -> f64 {
+        self.bid.bid_intensity(delta)
+    }
+
+    fn ask_intensity(&self, delta: f64) -> f64 {
+        self.ask.ask_intensity(delta)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -25,6 +173,203 @@ impl IntensityModel for ExponentialIntensity {
     }
 }
 
+/// Exponential fill model `A * exp(-k * delta)` that keeps growing for quotes through the mid.
+///
+/// `ExponentialIntensity` treats any negative `delta` as zero, so a quote more aggressive than the
+/// mid fills no faster than one at it. Here a negative `delta` raises the rate above `a`; once
+/// `intensity * dt` reaches one the simulator fills on every step.
+#[derive(Clone, Copy, Debug)]
+pub struct UnclampedExponentialIntensity {
+    pub k: f64,
+    pub a: f64,
+}
+
+impl UnclampedExponentialIntensity {
+    pub fn new(a: f64, k: f64) -> Result<Self, ParamError> {
+        ensure_positive("a", a)?;
+        ensure_positive("k", k)?;
+        Ok(Self { k, a })
+    }
+}
+
+impl IntensityModel for UnclampedExponentialIntensity {
+    fn calculate_intensity(&self, delta: f64) -> f64 {
+        self.a * (-self.k * delta).exp()
+    }
+}
+
+/// Minimum number of labelled samples accepted by `ExponentialIntensity::fit`.
+pub const MIN_FIT_SAMPLES: usize = 20;
+
+impl ExponentialIntensity {
+    pub fn new(a: f64, k: f64) -> Result<Self, ParamError> {
+        ensure_positive("a", a)?;
+        ensure_positive("k", k)?;
+        Ok(Self { k, a })
+    }
+
+    /// Maximum-likelihood calibration of `a` and `k` from `(delta, filled within dt)` samples.
+    ///
+    /// Uses the simulator's fill rule `P(fill | delta) = a * exp(-k * delta) * dt`, which matches
+    /// the Poisson probability `1 - exp(-lambda * dt)` when `lambda * dt` is small. For each
+    /// candidate `k` the likelihood is maximised over `a` by safeguarded Newton on its score; the
+    /// resulting profile likelihood is searched on a log-spaced grid of `k` and refined by
+    /// golden-section search around the best grid point.
+    pub fn fit(samples: &[(f64, bool)], dt: f64) -> Result<Self, FitError> {
+        if dt.is_nan() || dt <= 0.0 {
+            return Err(FitError::InvalidDt(dt));
+        }
+        if samples.len() < MIN_FIT_SAMPLES {
+            return Err(FitError::TooFewSamples {
+                got: samples.len(),
+                min: MIN_FIT_SAMPLES,
+            });
+        }
+
+        let fills: Vec<f64> = samples
+            .iter()
+            .filter(|s| s.1)
+            .map(|s| s.0.max(0.0))
+            .collect();
+        let misses: Vec<f64> = samples
+            .iter()
+            .filter(|s| !s.1)
+            .map(|s| s.0.max(0.0))
+            .collect();
+        if fills.is_empty() {
+            return Err(FitError::NoFills);
+        }
+        if misses.is_empty() {
+            return Err(FitError::AllFilled);
+        }
+
+        let (lo, hi) = samples.iter().fold((f64::MAX, f64::MIN), |(lo, hi), s| {
+            (lo.min(s.0.max(0.0)), hi.max(s.0.max(0.0)))
+        });
+        if hi - lo < 1e-12 {
+            return Err(FitError::NoDeltaSpread);
+        }
+
+        // Profile out `a` for a given `k`, returning (log-likelihood, a).
+        let profile = |k: f64| {
+            // P(fill) = a * c with c = dt * exp(-k * delta)
+            let fill_c: Vec<f64> = fills.iter().map(|&d| dt * (-k * d).exp()).collect();
+            let miss_c: Vec<f64> = misses.iter().map(|&d| dt * (-k * d).exp()).collect();
+            let n_fills = fill_c.len() as f64;
+
+            // The score F/a - sum c/(1 - a c) over misses is strictly decreasing on (0, a_max),
+            // where a_max keeps every miss probability below one. Solve it by Newton's method from
+            // the small-probability estimate F / sum(c), bisecting whenever a step leaves the bracket.
+            let a_max = 1.0 / miss_c.iter().fold(0.0, |m: f64, &c| m.max(c));
+            let (mut a_lo, mut a_hi) = (0.0, a_max);
+            let total_c = fill_c.iter().sum::<f64>() + miss_c.iter().sum::<f64>();
+            let mut a = (n_fills / total_c).min(0.5 * a_max);
+            for _ in 0..100 {
+                let (mut score, mut slope) = (n_fills / a, -n_fills / (a * a));
+                for &c in &miss_c {
+                    let ratio = c / (1.0 - a * c);
+                    score -= ratio;
+                    slope -= ratio * ratio;
+                }
+                if score > 0.0 {
+                    a_lo = a;
+                } else {
+                    a_hi = a;
+                }
+                let newton = a - score / slope;
+                let next = if newton > a_lo && newton < a_hi {
+                    newton
+                } else {
+                    0.5 * (a_lo + a_hi)
+                };
+                let converged = (next - a).abs() <= 1e-12 * a;
+                a = next;
+                if converged {
+                    break;
+                }
+            }
+
+            let log_lik = fill_c.iter().map(|&c| (a * c).min(1.0).ln()).sum::<f64>()
+                + miss_c.iter().map(|&c| (1.0 - a * c).ln()).sum::<f64>();
+            (log_lik, a)
+        };
+
+        // Coarse log-spaced grid over k, then golden-section refinement around the best node.
+        let grid: Vec<f64> = (0..=40)
+            .map(|i| 10f64.powf(-3.0 + 5.0 * i as f64 / 40.0))
+            .collect();
+        let grid_lik: Vec<f64> = grid.iter().map(|&k| profile(k).0).collect();
+        let best = (0..grid.len())
+            .max_by(|&i, &j| grid_lik[i].total_cmp(&grid_lik[j]))
+            .unwrap_or(0);
+        let (mut k_lo, mut k_hi) = (grid[best.saturating_sub(1)], grid[(best + 1).min(40)]);
+
+        let inv_phi = (5f64.sqrt() - 1.0) / 2.0;
+        let mut k1 = k_hi - inv_phi * (k_hi - k_lo);
+        let mut k2 = k_lo + inv_phi * (k_hi - k_lo);
+        let (mut f1, mut f2) = (profile(k1).0, profile(k2).0);
+        for _ in 0..40 {
+            if f1 < f2 {
+                k_lo = k1;
+                (k1, f1) = (k2, f2);
+                k2 = k_lo + inv_phi * (k_hi - k_lo);
+                f2 = profile(k2).0;
+            } else {
+                k_hi = k2;
+                (k2, f2) = (k1, f1);
+                k1 = k_hi - inv_phi * (k_hi - k_lo);
+                f1 = profile(k1).0;
+            }
+        }
+
+        let k = 0.5 * (k_lo + k_hi);
+        let (log_lik, a) = profile(k);
+        if !log_lik.is_finite() {
+            return Err(FitError::NoConvergence);
+        }
+        Ok(ExponentialIntensity { k, a })
+    }
+}
+
+/// Error returned when an intensity model cannot be calibrated from data.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FitError {
+    InvalidDt(f64),
+    TooFewSamples {
+        got: usize,
+        min: usize,
+    },
+    /// Every sample filled, so the likelihood grows without bound in `a`.
+    AllFilled,
+    /// No sample filled, so the likelihood is maximised at `a = 0`.
+    NoFills,
+    /// All deltas are equal, so `k` is not identifiable.
+    NoDeltaSpread,
+    NoConvergence,
+}
+
+impl fmt::Display for FitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FitError::InvalidDt(dt) => write!(f, "dt must be > 0, got {dt}"),
+            FitError::TooFewSamples { got, min } => {
+                write!(f, "need at least {min} samples to fit, got {got}")
+            }
+            FitError::AllFilled => write!(f, "every sample filled; the arrival rate is unbounded"),
+            FitError::NoFills => write!(f, "no sample filled; the arrival rate is zero"),
+            FitError::NoDeltaSpread => {
+                write!(
+                    f,
+                    "all samples share one delta; the decay rate k is not identifiable"
+                )
+            }
+            FitError::NoConvergence => write!(f, "likelihood maximisation did not converge"),
+        }
+    }
+}
+
+impl std::error::Error for FitError {}
+
 #[derive(Clone, Copy, Debug)]
 pub struct PowerLawIntensity {
     pub a: f64,
@@ -32,6 +377,15 @@ pub struct PowerLawIntensity {
     pub beta: f64,
 }
 
+impl PowerLawIntensity {
+    pub fn new(a: f64, k: f64, beta: f64) -> Result<Self, ParamError> {
+        ensure_positive("a", a)?;
+        ensure_positive("k", k)?;
+        ensure_positive("beta", beta)?;
+        Ok(Self { a, k, beta })
+    }
+}
+
 impl IntensityModel for PowerLawIntensity {
     fn calculate_intensity(&self, delta: f64) -> f64 {
         // A / (1 + k * delta) ^ beta
@@ -39,17 +393,806 @@ impl IntensityModel for PowerLawIntensity {
     }
 }
 
-pub fn reservation_price(params: &Parameters, s: f64, q: i32, t: f64) -> f64 {
-    s - q as f64 * params.gamma * params.sigma * params.sigma * (params.t_horizon - t)
+/// Piecewise-linear fill model: intensity falls linearly from `a` and hits zero at `a / slope`.
+#[derive(Clone, Copy, Debug)]
+pub struct LinearIntensity {
+    pub a: f64,
+    pub slope: f64,
+}
+
+impl LinearIntensity {
+    pub fn new(a: f64, slope: f64) -> Result<Self, ParamError> {
+        ensure_positive("a", a)?;
+        ensure_positive("slope", slope)?;
+        Ok(Self { a, slope })
+    }
+}
+
+impl IntensityModel for LinearIntensity {
+    fn calculate_intensity(&self, delta: f64) -> f64 {
+        // max(0, A - slope * delta)
+        (self.a - self.slope * delta.max(0.0)).max(0.0)
+    }
+}
+
+/// Logistic fill model `A / (1 + exp(k * (delta - delta0)))`.
+///
+/// Saturates at `a` for quotes well inside `delta0` instead of growing without bound like the
+/// exponential model, and decays smoothly beyond it.
+#[derive(Clone, Copy, Debug)]
+pub struct LogisticIntensity {
+    pub a: f64,
+    pub k: f64,
+    pub delta0: f64,
+}
+
+impl LogisticIntensity {
+    pub fn new(a: f64, k: f64, delta0: f64) -> Result<Self, ParamError> {
+        ensure_positive("a", a)?;
+        ensure_positive("k", k)?;
+        Ok(Self { a, k, delta0 })
+    }
+}
+
+impl IntensityModel for LogisticIntensity {
+    fn calculate_intensity(&self, delta: f64) -> f64 {
+        self.a / (1.0 + (self.k * (delta - self.delta0)).exp())
+    }
+}
+
+/// A serialisable description of one of the built-in intensity models, for configs.
+///
+/// Tagged by `type`, e.g. `{"type": "exponential", "a": 140, "k": 1.5}`. `Asymmetric` nests one
+/// spec per side like `MarketIntensity`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)
+)]
+pub enum IntensitySpec {
+    Exponential {
+        a: f64,
+        k: f64,
+    },
+    UnclampedExponential {
+        a: f64,
+        k: f64,
+    },
+    PowerLaw {
+        a: f64,
+        k: f64,
+        beta: f64,
+    },
+    Linear {
+        a: f64,
+        slope: f64,
+    },
+    Logistic {
+        a: f64,
+        k: f64,
+        delta0: f64,
+    },
+    Asymmetric {
+        bid: Box<IntensitySpec>,
+        ask: Box<IntensitySpec>,
+    },
+}
+
+/// The name the run-time model selection was first asked for under. `IntensitySpec::Linear`
+/// calls the decay rate `slope` (as `LinearIntensity` does) where that request called it `k`.
+pub type IntensityKind = IntensitySpec;
+
+impl IntensitySpec {
+    /// Builds the concrete model the spec describes, for callers that pick the model at run time.
+    ///
+    /// Each variant goes through its model's constructor, so a spec that `validate` rejects is an
+    /// error here too. The spec is itself an `IntensityModel`, matching on its variant at every
+    /// call; the boxed model dispatches once through the vtable instead.
+    pub fn into_model(self) -> Result<Box<dyn IntensityModel>, ParamError> {
+        Ok(match self {
+            IntensitySpec::Exponential { a, k } => Box::new(ExponentialIntensity::new(a, k)?),
+            IntensitySpec::UnclampedExponential { a, k } => {
+                Box::new(UnclampedExponentialIntensity::new(a, k)?)
+            }
+            IntensitySpec::PowerLaw { a, k, beta } => Box::new(PowerLawIntensity::new(a, k, beta)?),
+            IntensitySpec::Linear { a, slope } => Box::new(LinearIntensity::new(a, slope)?),
+            IntensitySpec::Logistic { a, k, delta0 } => {
+                Box::new(LogisticIntensity::new(a, k, delta0)?)
+            }
+            IntensitySpec::Asymmetric { bid, ask } => Box::new(MarketIntensity {
+                bid: bid.into_model()?,
+                ask: ask.into_model()?,
+            }),
+        })
+    }
+
+    /// Checks the spec's parameters as the model constructors do: `a` and every decay rate (`k`,
+    /// `slope`, `beta`) must be positive, on both sides of an `Asymmetric` spec.
+    pub fn validate(&self) -> Result<(), ParamError> {
+        match *self {
+            IntensitySpec::Exponential { a, k } => ExponentialIntensity::new(a, k).map(drop),
+            IntensitySpec::UnclampedExponential { a, k } => {
+                UnclampedExponentialIntensity::new(a, k).map(drop)
+            }
+            IntensitySpec::PowerLaw { a, k, beta } => PowerLawIntensity::new(a, k, beta).map(drop),
+            IntensitySpec::Linear { a, slope } => LinearIntensity::new(a, slope).map(drop),
+            IntensitySpec::Logistic { a, k, delta0 } => {
+                LogisticIntensity::new(a, k, delta0).map(drop)
+            }
+            IntensitySpec::Asymmetric { ref bid, ref ask } => {
+                bid.validate()?;
+                ask.validate()
+            }
+        }
+    }
+}
+
+impl IntensityModel for IntensitySpec {
+    fn calculate_intensity(&self, delta: f64) -> f64 {
+        match *self {
+            IntensitySpec::Exponential { a, k } => {
+                ExponentialIntensity { k, a }.calculate_intensity(delta)
+            }
+            IntensitySpec::UnclampedExponential { a, k } => {
+                UnclampedExponentialIntensity { k, a }.calculate_intensity(delta)
+            }
+            IntensitySpec::PowerLaw { a, k, beta } => {
+                PowerLawIntensity { a, k, beta }.calculate_intensity(delta)
+            }
+            IntensitySpec::Linear { a, slope } => {
+                LinearIntensity { a, slope }.calculate_intensity(delta)
+            }
+            IntensitySpec::Logistic { a, k, delta0 } => {
+                LogisticIntensity { a, k, delta0 }.calculate_intensity(delta)
+            }
+            IntensitySpec::Asymmetric { ref bid, ref ask } => {
+                0.5 * (bid.bid_intensity(delta) + ask.ask_intensity(delta))
+            }
+        }
+    }
+
+    fn bid_intensity(&self, delta: f64) -> f64 {
+        match self {
+            IntensitySpec::Asymmetric { bid, .. } => bid.bid_intensity(delta),
+            _ => self.calculate_intensity(delta),
+        }
+    }
+
+    fn ask_intensity(&self, delta: f64) -> f64 {
+        match self {
+            IntensitySpec::Asymmetric { ask, .. } => ask.ask_intensity(delta),
+            _ => self.calculate_intensity(delta),
+        }
+    }
+}
+
+/// Error returned when a model parameter is outside its valid domain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParamError {
+    /// The named parameter must be strictly positive (NaN is rejected too).
+    NotPositive { name: &'static str, value: f64 },
+    /// The named parameter must be zero or positive (NaN is rejected too).
+    Negative { name: &'static str, value: f64 },
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamError::NotPositive { name, value } => {
+                write!(f, "parameter `{name}` must be > 0, got {value}")
+            }
+            ParamError::Negative { name, value } => {
+                write!(f, "parameter `{name}` must be >= 0, got {value}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParamError {}
+
+fn ensure_positive(name: &'static str, value: f64) -> Result<(), ParamError> {
+    if value > 0.0 {
+        Ok(())
+    } else {
+        Err(ParamError::NotPositive { name, value })
+    }
+}
+
+fn ensure_non_negative(name: &'static str, value: f64) -> Result<(), ParamError> {
+    if value >= 0.0 {
+        Ok(())
+    } else {
+        Err(ParamError::Negative { name, value })
+    }
+}
+
+/// Time left to the horizon, `T - t`, held at zero once `t` passes `T`: a run longer than the
+/// horizon keeps quoting as at `T` rather than flipping the sign of the skew and shrinking the
+/// spread without bound.
+pub fn remaining_time(params: &Parameters, t: f64) -> f64 {
+    (params.t_horizon - t).max(0.0)
+}
+
+/// Reservation price shift per unit of inventory at time `t`.
+///
+/// Under `RunningPenalty` the `gamma * sigma^2 * (T - t)` coefficient is replaced by the
+/// closed-form asymptotic approximation of Guéant (2017) for a risk-neutral agent paying
+/// `phi * q^2` per unit time: `sqrt(e * phi / (k * a))`. This is the long-horizon limit of the
+/// Guéant-Lehalle-Fernandez-Tapia skew with `gamma * sigma^2 / 2 = phi` and `gamma -> 0`, where
+/// `(1 + gamma / k)^(1 + k / gamma) -> e`. It ignores the discreteness of `q` and the end-of-horizon
+/// boundary layer, so it is accurate only away from `T` and for moderate inventories.
+///
+/// Under `Stationary` it is the full asymptotic skew, see `stationary_skew`, and under
+/// `SymmetricMid` and `FixedSpread` it is zero.
+pub fn inventory_skew(params: &Parameters, t: f64) -> f64 {
+    match params.quoting_model {
+        QuotingModel::AvellanedaStoikov | QuotingModel::DriftAdjusted => {
+            params.gamma * params.sigma * params.sigma * remaining_time(params, t)
+        }
+        QuotingModel::RunningPenalty { phi } => {
+            (f64::consts::E * phi / (params.k * params.a)).sqrt()
+        }
+        QuotingModel::Stationary => stationary_skew(params),
+        QuotingModel::SymmetricMid | QuotingModel::FixedSpread { .. } => 0.0,
+    }
+}
+
+/// Long-horizon limit of the Guéant-Lehalle-Fernandez-Tapia inventory skew:
+/// `sqrt(sigma^2 * gamma / (2 * k * a) * (1 + gamma / k)^(1 + k / gamma))`.
+pub fn stationary_skew(params: &Parameters) -> f64 {
+    let (gamma, k) = (params.gamma, params.k);
+    let growth = (1.0 + gamma / k).powf(1.0 + k / gamma);
+    (params.sigma * params.sigma * gamma / (2.0 * k * params.a) * growth).sqrt()
+}
+
+/// Expected mid move `drift * s * (T - t)` added to the reservation price under `DriftAdjusted`,
+/// zero under every other quoting model.
+///
+/// `drift` is relative, per unit time, as in `SimConfig::drift`, whose price process grows the mid
+/// by `drift * s * dt` a step; scaling by the mid `s` turns it into price units.
+pub fn drift_adjustment(params: &Parameters, drift: f64, s: f64, t: f64) -> f64 {
+    match params.quoting_model {
+        QuotingModel::DriftAdjusted => drift * s * remaining_time(params, t),
+        _ => 0.0,
+    }
+}
+
+/// Reservation price at mid `s` with inventory `q`. `drift` is the relative trend the maker
+/// expects, read only under `DriftAdjusted` (see `drift_adjustment`).
+pub fn reservation_price(params: &Parameters, drift: f64, s: f64, q: f64, t: f64) -> f64 {
+    s + drift_adjustment(params, drift, s, t) - q * inventory_skew(params, t)
+}
+
+/// `reservation_price` shifted by `signal_weight * signal`. A bullish (positive) signal lifts both
+/// quotes, so the bid fills more often and inventory builds ahead of the expected rise.
+pub fn reservation_price_with_signal(
+    params: &Parameters,
+    drift: f64,
+    s: f64,
+    q: f64,
+    t: f64,
+    signal: f64,
+    signal_weight: f64,
+) -> f64 {
+    reservation_price(params, drift, s, q, t) + signal_weight * signal
 }
 
 pub fn optimal_spread(parameters: &Parameters, t: f64) -> f64 {
     let sigma_sq = parameters.sigma * parameters.sigma;
-    parameters.gamma * sigma_sq * (parameters.t_horizon - t)
-        + (2.0 / parameters.gamma) * (1.0 + (parameters.gamma / parameters.k)).ln()
+    let liquidity_term = (2.0 / parameters.gamma) * (1.0 + (parameters.gamma / parameters.k)).ln();
+    match parameters.quoting_model {
+        // The asymptotic quotes sit `skew / 2` further out on each side, in place of the
+        // `(T - t)` risk term.
+        QuotingModel::Stationary => liquidity_term + stationary_skew(parameters),
+        QuotingModel::FixedSpread { spread } => spread,
+        _ => parameters.gamma * sigma_sq * remaining_time(parameters, t) + liquidity_term,
+    }
+}
+
+/// `spread` if it is positive and finite, otherwise zero. Valid parameters always give a positive
+/// spread, but ones outside the domain (`gamma` or `k` at or below zero) can give a negative, NaN,
+/// or infinite one, which would cross the book or put a quote at infinity.
+pub fn quotable_spread(spread: f64) -> f64 {
+    if spread > 0.0 && spread.is_finite() {
+        spread
+    } else {
+        0.0
+    }
 }
 
+/// `optimal_spread` averaged over `[0, duration]`, the span a simulation quotes over
+/// (`num_steps * dt`); the spread at `t = 0` when `duration` is not positive.
+///
+/// The spread is linear in `t` up to `T` under every quoting model, so over `[0, min(duration, T)]`
+/// its mean is that of its values at the two ends. A run longer than the horizon quotes the `T`
+/// spread for the rest of it (see `remaining_time`), which is weighted in for the time past `T`.
+pub fn mean_optimal_spread(parameters: &Parameters, duration: f64) -> f64 {
+    let start = optimal_spread(parameters, 0.0);
+    if duration.is_nan() || duration <= 0.0 {
+        return start;
+    }
+    let end = duration.min(parameters.t_horizon);
+    let ramp = (start + optimal_spread(parameters, end)) / 2.0 * end;
+    let tail = optimal_spread(parameters, parameters.t_horizon) * (duration - end);
+    (ramp + tail) / duration
+}
+
+/// `(ask, bid)` either side of `r_price`. The spread must not be negative, see `quotable_spread`.
 pub fn quotes(r_price: f64, spread: f64) -> (f64, f64) {
     let spread_half = spread / 2.0;
-    (r_price + spread_half, r_price - spread_half)
+    let (ask, bid) = (r_price + spread_half, r_price - spread_half);
+    debug_assert!(
+        ask >= bid || r_price.is_nan() || spread.is_nan(),
+        "quotes cross: bid {bid} above ask {ask}"
+    );
+    (ask, bid)
+}
+
+/// The strategy's quotes at one `(s, q, t)`, see `decide_quotes`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuoteDecision {
+    pub reservation_price: f64,
+    pub spread: f64, // `optimal_spread`, which is not positive only for out-of-domain parameters
+    pub bid: f64,
+    pub ask: f64,
+}
+
+/// What the strategy quotes with inventory `q` at mid `s` and time `t`, without running a
+/// trajectory: the reservation price, the optimal spread, and the bid and ask around it. `drift`
+/// is the `SimConfig::drift` the simulator passes, which only `DriftAdjusted` reads.
+///
+/// These are the quotes the simulator computes at the start of each step, before the
+/// `SimConfig` half-spread bounds, a quote signal, or latency apply. A spread that is not
+/// positive and finite puts both quotes on the reservation price, as the simulator does, so the
+/// book never crosses.
+pub fn decide_quotes(params: &Parameters, drift: f64, s: f64, q: f64, t: f64) -> QuoteDecision {
+    let reservation_price = reservation_price(params, drift, s, q, t);
+    let spread = optimal_spread(params, t);
+    let (ask, bid) = quotes(reservation_price, quotable_spread(spread));
+    QuoteDecision {
+        reservation_price,
+        spread,
+        bid,
+        ask,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_decide_quotes_pins_known_inputs() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+
+        // Two units long halfway to T: skew 0.1 * 0.04 * 0.5 per unit, spread
+        // 0.002 + 20 ln(1 + 0.1 / 1.5).
+        let long = decide_quotes(&params, 0.0, 100.0, 2.0, 0.5);
+        assert!((long.reservation_price - 99.996).abs() < 1e-12);
+        assert!((long.spread - 1.292_770_422_751_423).abs() < 1e-12);
+        assert!((long.bid - 99.349_614_788_624_28).abs() < 1e-12);
+        assert!((long.ask - 100.642_385_211_375_71).abs() < 1e-12);
+
+        // Flat inventory quotes symmetrically around the mid; short inventory leans up.
+        let flat = decide_quotes(&params, 0.0, 100.0, 0.0, 0.5);
+        assert_eq!(flat.reservation_price, 100.0);
+        assert!((flat.ask - 100.0 - (100.0 - flat.bid)).abs() < 1e-12);
+        let short = decide_quotes(&params, 0.0, 100.0, -2.0, 0.5);
+        assert!((short.reservation_price - 100.004).abs() < 1e-12);
+        assert_eq!(short.spread, long.spread);
+
+        // Far past the horizon the remaining time stays at zero, so the quotes are those at T.
+        let late = decide_quotes(&params, 0.0, 100.0, 1.0, 1000.0);
+        assert_eq!(
+            late,
+            decide_quotes(&params, 0.0, 100.0, 1.0, params.t_horizon)
+        );
+        assert!(late.spread > 0.0 && late.ask > late.bid);
+    }
+
+    #[test]
+    fn test_decide_quotes_survives_out_of_domain_params() {
+        let valid =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let collapses = |params: Parameters| {
+            let decision = decide_quotes(&params, 0.0, 100.0, 1.0, 0.0);
+            assert!(!(decision.spread > 0.0 && decision.spread.is_finite()));
+            assert_eq!(
+                (decision.bid, decision.ask),
+                (decision.reservation_price, decision.reservation_price),
+                "{params:?}"
+            );
+        };
+
+        // A negative gamma turns the risk term negative: -1 * 4 * 1 + 2 ln 3 < 0.
+        collapses(Parameters {
+            gamma: -1.0,
+            sigma: 2.0,
+            ..valid
+        });
+        // gamma / k below -1 takes the log of a negative number.
+        collapses(Parameters { k: -0.05, ..valid });
+        // k = 0 makes the liquidity term infinite.
+        collapses(Parameters { k: 0.0, ..valid });
+        assert_eq!(quotable_spread(f64::NAN), 0.0);
+        assert_eq!(quotable_spread(f64::INFINITY), 0.0);
+        assert_eq!(quotable_spread(-0.5), 0.0);
+        assert_eq!(quotable_spread(0.5), 0.5);
+    }
+
+    #[test]
+    fn test_linear_intensity_boundaries() {
+        let model = LinearIntensity {
+            a: 100.0,
+            slope: 50.0,
+        };
+
+        // Quotes at or through the mid saturate at `a`.
+        assert_eq!(model.calculate_intensity(0.0), 100.0);
+        assert_eq!(model.calculate_intensity(-1.0), 100.0);
+
+        assert_eq!(model.calculate_intensity(1.0), 50.0);
+
+        // Exactly zero from `a / slope` onwards, never negative.
+        assert_eq!(model.calculate_intensity(2.0), 0.0);
+        assert_eq!(model.calculate_intensity(10.0), 0.0);
+    }
+
+    #[test]
+    fn test_unclamped_exponential_rewards_aggressive_quotes() {
+        let model = UnclampedExponentialIntensity { k: 1.5, a: 140.0 };
+        assert!(model.calculate_intensity(-0.1) > model.calculate_intensity(0.1));
+        assert_eq!(model.calculate_intensity(0.0), 140.0);
+        assert!((model.calculate_intensity(-0.1) - 140.0 * 0.15f64.exp()).abs() < 1e-9);
+
+        // The clamped model agrees on the passive side only.
+        let clamped = ExponentialIntensity { k: 1.5, a: 140.0 };
+        assert_eq!(
+            model.calculate_intensity(0.4),
+            clamped.calculate_intensity(0.4)
+        );
+        assert_eq!(clamped.calculate_intensity(-0.1), 140.0);
+    }
+
+    #[test]
+    fn test_spec_into_model_matches_variant() {
+        let at_mid = |spec: IntensitySpec| spec.into_model().unwrap().calculate_intensity(0.0);
+        assert_eq!(
+            at_mid(IntensitySpec::Exponential { a: 140.0, k: 1.5 }),
+            140.0
+        );
+        assert_eq!(
+            at_mid(IntensitySpec::UnclampedExponential { a: 140.0, k: 1.5 }),
+            140.0
+        );
+        let power_law = IntensitySpec::PowerLaw {
+            a: 100.0,
+            k: 2.0,
+            beta: 1.5,
+        };
+        assert_eq!(at_mid(power_law.clone()), 100.0);
+        assert_eq!(
+            at_mid(IntensityKind::Linear {
+                a: 80.0,
+                slope: 50.0
+            }),
+            80.0
+        );
+        let logistic = IntensitySpec::Logistic {
+            a: 100.0,
+            k: 2.0,
+            delta0: 0.5,
+        };
+        assert!((at_mid(logistic) - 100.0 / (1.0 + (-1.0f64).exp())).abs() < 1e-12);
+
+        // Each side of an asymmetric spec keeps its own curve.
+        let model = IntensitySpec::Asymmetric {
+            bid: Box::new(IntensitySpec::Exponential { a: 140.0, k: 1.5 }),
+            ask: Box::new(power_law),
+        }
+        .into_model()
+        .unwrap();
+        assert_eq!(model.bid_intensity(0.0), 140.0);
+        assert_eq!(model.ask_intensity(0.0), 100.0);
+        assert_eq!(model.calculate_intensity(0.0), 120.0);
+    }
+
+    #[test]
+    fn test_spec_rejects_invalid_parameters() {
+        let logistic = IntensitySpec::Logistic {
+            a: 100.0,
+            k: -1.5,
+            delta0: 0.5,
+        };
+        let expected = ParamError::NotPositive {
+            name: "k",
+            value: -1.5,
+        };
+        assert_eq!(logistic.validate(), Err(expected));
+        assert_eq!(logistic.clone().into_model().err(), Some(expected));
+
+        let linear = IntensitySpec::Linear {
+            a: 80.0,
+            slope: -50.0,
+        };
+        assert!(linear.validate().is_err());
+        // A bad side fails the whole asymmetric spec.
+        let asymmetric = IntensitySpec::Asymmetric {
+            bid: Box::new(IntensitySpec::Exponential { a: 140.0, k: 1.5 }),
+            ask: Box::new(linear),
+        };
+        assert!(asymmetric.validate().is_err());
+        assert!(asymmetric.into_model().is_err());
+        assert!(
+            IntensitySpec::PowerLaw {
+                a: 100.0,
+                k: 2.0,
+                beta: 0.0
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_logistic_intensity_values() {
+        let model = LogisticIntensity::new(100.0, 2.0, 0.5).unwrap();
+
+        let at_mid = 100.0 / (1.0 + (-1.0f64).exp());
+        assert!((model.calculate_intensity(0.0) - at_mid).abs() < 1e-12);
+        assert!((model.calculate_intensity(0.5) - 50.0).abs() < 1e-12);
+        assert!(model.calculate_intensity(50.0) < 1e-12);
+
+        // Saturates at `a` for very aggressive quotes.
+        assert!((model.calculate_intensity(-50.0) - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_logistic_intensity_validation() {
+        assert_eq!(
+            LogisticIntensity::new(100.0, 0.0, 0.5).unwrap_err(),
+            ParamError::NotPositive {
+                name: "k",
+                value: 0.0
+            }
+        );
+        assert!(LogisticIntensity::new(-1.0, 2.0, 0.5).is_err());
+        assert!(LogisticIntensity::new(f64::NAN, 2.0, 0.5).is_err());
+    }
+
+    #[test]
+    fn test_market_intensity_sides() {
+        let market = MarketIntensity::new(
+            ExponentialIntensity { k: 1.5, a: 200.0 },
+            ExponentialIntensity { k: 1.5, a: 50.0 },
+        );
+        assert_eq!(market.bid_intensity(0.0), 200.0);
+        assert_eq!(market.ask_intensity(0.0), 50.0);
+
+        let symmetric = MarketIntensity::symmetric(ExponentialIntensity { k: 1.5, a: 140.0 });
+        assert_eq!(symmetric.bid_intensity(0.3), symmetric.ask_intensity(0.3));
+        assert_eq!(
+            symmetric.calculate_intensity(0.3),
+            symmetric.bid_intensity(0.3)
+        );
+    }
+
+    #[test]
+    fn test_exponential_fit_recovers_parameters() {
+        let truth = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let dt = 0.005;
+        let mut rng = StdRng::seed_from_u64(21);
+
+        // Same Bernoulli fill rule as `run_trajectory`: fill iff U < lambda(delta) * dt.
+        let samples: Vec<(f64, bool)> = (0..200_000)
+            .map(|_| {
+                let delta = rng.random::<f64>() * 2.0;
+                let filled = rng.random::<f64>() < truth.calculate_intensity(delta) * dt;
+                (delta, filled)
+            })
+            .collect();
+
+        let fitted = ExponentialIntensity::fit(&samples, dt).unwrap();
+        assert!(
+            (fitted.a - truth.a).abs() / truth.a < 0.05,
+            "a = {}",
+            fitted.a
+        );
+        assert!(
+            (fitted.k - truth.k).abs() / truth.k < 0.05,
+            "k = {}",
+            fitted.k
+        );
+    }
+
+    #[test]
+    fn test_exponential_fit_degenerate_inputs() {
+        let spread = |filled: bool| -> Vec<(f64, bool)> {
+            (0..50).map(|i| (i as f64 * 0.02, filled)).collect()
+        };
+
+        assert_eq!(
+            ExponentialIntensity::fit(&spread(true), 0.005).unwrap_err(),
+            FitError::AllFilled
+        );
+        assert_eq!(
+            ExponentialIntensity::fit(&spread(false), 0.005).unwrap_err(),
+            FitError::NoFills
+        );
+        assert_eq!(
+            ExponentialIntensity::fit(&spread(true)[..5], 0.005).unwrap_err(),
+            FitError::TooFewSamples {
+                got: 5,
+                min: MIN_FIT_SAMPLES
+            }
+        );
+
+        let flat: Vec<(f64, bool)> = (0..50).map(|i| (0.5, i % 2 == 0)).collect();
+        assert_eq!(
+            ExponentialIntensity::fit(&flat, 0.005).unwrap_err(),
+            FitError::NoDeltaSpread
+        );
+    }
+
+    #[test]
+    fn test_stationary_quotes_ignore_time() {
+        let params = Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::Stationary,
+        };
+        let mid = params.t_horizon / 2.0;
+
+        assert_eq!(optimal_spread(&params, 0.0), optimal_spread(&params, mid));
+        assert_eq!(
+            reservation_price(&params, 0.0, 100.0, 3.0, 0.0),
+            reservation_price(&params, 0.0, 100.0, 3.0, mid)
+        );
+        assert!(reservation_price(&params, 0.0, 100.0, 3.0, mid) < 100.0);
+        assert!(optimal_spread(&params, params.t_horizon) > 0.0);
+    }
+
+    #[test]
+    fn test_drift_adjustment_shifts_reservation_price() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let adjusted = Parameters {
+            quoting_model: QuotingModel::DriftAdjusted,
+            ..params
+        };
+
+        assert_eq!(drift_adjustment(&params, 0.02, 100.0, 0.25), 0.0);
+        // A 2% relative drift at a mid of 100 is 2 price units per unit time, over 0.75 left.
+        let shift = reservation_price(&adjusted, 0.02, 100.0, 3.0, 0.25)
+            - reservation_price(&params, 0.02, 100.0, 3.0, 0.25);
+        assert!((shift - 1.5).abs() < 1e-12, "{shift}");
+        // The move is proportional to the mid, like the price process's drift.
+        assert!((drift_adjustment(&adjusted, 0.02, 50.0, 0.25) - 0.75).abs() < 1e-12);
+        // The expected move runs out at the horizon; the spread is unchanged throughout.
+        assert_eq!(drift_adjustment(&adjusted, 0.02, 100.0, 1.0), 0.0);
+        assert_eq!(
+            optimal_spread(&adjusted, 0.25),
+            optimal_spread(&params, 0.25)
+        );
+    }
+
+    #[test]
+    fn test_fixed_spread_quotes_ignore_inventory_and_time() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        // The Avellaneda-Stoikov spread falls linearly to the liquidity term at T.
+        let liquidity = 20.0 * (1.0 + 0.1 / 1.5f64).ln();
+        let expected = 0.1 * 0.04 / 2.0 + liquidity;
+        assert!((mean_optimal_spread(&params, 1.0) - expected).abs() < 1e-12);
+        // Half the horizon only sees the wider early spreads; past `T` the spread stays at its
+        // `T` value.
+        let half = 0.1 * 0.04 * 0.75 + liquidity;
+        assert!((mean_optimal_spread(&params, 0.5) - half).abs() < 1e-12);
+        let double = 0.1 * 0.04 / 4.0 + liquidity;
+        assert!((mean_optimal_spread(&params, 2.0) - double).abs() < 1e-12);
+
+        let fixed = Parameters {
+            quoting_model: QuotingModel::FixedSpread { spread: 0.5 },
+            ..params
+        };
+        assert_eq!(reservation_price(&fixed, 0.0, 100.0, 3.0, 0.2), 100.0);
+        assert_eq!(optimal_spread(&fixed, 0.0), 0.5);
+        assert_eq!(optimal_spread(&fixed, 1.0), 0.5);
+        assert_eq!(mean_optimal_spread(&fixed, 3.0), 0.5);
+
+        let invalid = Parameters {
+            quoting_model: QuotingModel::FixedSpread { spread: 0.0 },
+            ..params
+        };
+        assert_eq!(
+            invalid.validate(),
+            Err(ParamError::NotPositive {
+                name: "spread",
+                value: 0.0
+            })
+        );
+    }
+
+    #[test]
+    fn test_parameters_new_validates_domain() {
+        let new = |gamma, sigma, k| {
+            Parameters::new(gamma, sigma, 1.0, k, 140.0, QuotingModel::AvellanedaStoikov)
+        };
+
+        assert!(new(0.1, 0.0, 1.5).is_ok());
+        assert_eq!(
+            new(0.0, 0.2, 1.5),
+            Err(ParamError::NotPositive {
+                name: "gamma",
+                value: 0.0
+            })
+        );
+        assert!(matches!(
+            new(0.1, -0.2, 1.5),
+            Err(ParamError::Negative { name: "sigma", .. })
+        ));
+        assert!(matches!(
+            new(0.1, f64::NAN, 1.5),
+            Err(ParamError::Negative { name: "sigma", .. })
+        ));
+        assert!(matches!(
+            new(0.1, 0.2, -1.0),
+            Err(ParamError::NotPositive { name: "k", .. })
+        ));
+
+        let penalty = QuotingModel::RunningPenalty { phi: -1.0 };
+        let err = Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, penalty).unwrap_err();
+        assert_eq!(err.to_string(), "parameter `phi` must be >= 0, got -1");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip_params_and_intensity() {
+        let params = Parameters::new(
+            0.1,
+            0.2,
+            1.0,
+            1.5,
+            140.0,
+            QuotingModel::RunningPenalty { phi: 0.01 },
+        )
+        .unwrap();
+        let json = serde_json::to_string(&params).unwrap();
+        assert_eq!(serde_json::from_str::<Parameters>(&json).unwrap(), params);
+
+        let spec: IntensitySpec =
+            serde_json::from_str(r#"{"type": "exponential", "a": 140, "k": 1.5}"#).unwrap();
+        assert_eq!(spec, IntensitySpec::Exponential { a: 140.0, k: 1.5 });
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        assert_eq!(
+            spec.calculate_intensity(0.3),
+            model.calculate_intensity(0.3)
+        );
+
+        let unclamped: IntensitySpec =
+            serde_json::from_str(r#"{"type": "unclamped_exponential", "a": 140, "k": 1.5}"#)
+                .unwrap();
+        assert!(unclamped.calculate_intensity(-0.1) > spec.calculate_intensity(-0.1));
+
+        let asymmetric = IntensitySpec::Asymmetric {
+            bid: Box::new(spec),
+            ask: Box::new(IntensitySpec::PowerLaw {
+                a: 100.0,
+                k: 2.0,
+                beta: 1.5,
+            }),
+        };
+        let json = serde_json::to_string(&asymmetric).unwrap();
+        assert_eq!(
+            serde_json::from_str::<IntensitySpec>(&json).unwrap(),
+            asymmetric
+        );
+    }
 }
@@ -0,0 +1,542 @@
+//! Iterative grid refinement over the strategy's `gamma` and `k`.
+//!
+//! `optimize` evaluates a coarse log-spaced grid over the bounds, then repeatedly re-centres a
+//! narrower grid on the best point found so far. Each point is scored by the sweep's own
+//! Monte Carlo aggregation (`SweepResult`) under a caller-chosen `Objective`. The market is
+//! always the given intensity model; only the strategy's assumptions move.
+
+use crate::analysis::{
+    SweepError, SweepResult, derive_seed, evaluate_seeded_point, inventory_penalized_score,
+};
+use crate::model::{IntensityModel, Parameters};
+use crate::par::*;
+use crate::sim::SimConfig;
+use crate::stats::Welford;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+/// What `optimize` maximises, computed from a point's `SweepResult`.
+#[derive(Clone)]
+pub enum Objective {
+    MeanPnl,
+    Sharpe,
+    /// `mean_pnl - lambda * cvar_95`, trading mean PnL against expected shortfall.
+    CvarAdjusted {
+        lambda: f64,
+    },
+    /// `analysis::inventory_penalized_score`, the utility `top_n` ranks by under
+    /// `SweepMetric::InventoryPenalized`.
+    InventoryPenalized {
+        inventory_penalty: f64,
+    },
+    /// Any score of the aggregated statistics; higher is better.
+    Custom(Arc<dyn Fn(&SweepResult) -> f64 + Send + Sync>),
+}
+
+impl Objective {
+    pub fn custom<F: Fn(&SweepResult) -> f64 + Send + Sync + 'static>(score: F) -> Self {
+        Objective::Custom(Arc::new(score))
+    }
+
+    pub fn value(&self, result: &SweepResult) -> f64 {
+        match self {
+            Objective::MeanPnl => result.mean_pnl,
+            Objective::Sharpe => result.sharpe_ratio,
+            Objective::CvarAdjusted { lambda } => result.mean_pnl - lambda * result.cvar_95,
+            Objective::InventoryPenalized { inventory_penalty } => {
+                inventory_penalized_score(result, *inventory_penalty)
+            }
+            Objective::Custom(score) => score(result),
+        }
+    }
+}
+
+impl fmt::Debug for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Objective::MeanPnl => write!(f, "MeanPnl"),
+            Objective::Sharpe => write!(f, "Sharpe"),
+            Objective::CvarAdjusted { lambda } => f
+                .debug_struct("CvarAdjusted")
+                .field("lambda", lambda)
+                .finish(),
+            Objective::InventoryPenalized { inventory_penalty } => f
+                .debug_struct("InventoryPenalized")
+                .field("inventory_penalty", inventory_penalty)
+                .finish(),
+            Objective::Custom(_) => f.debug_tuple("Custom").finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Inclusive `(low, high)` search ranges; both must be positive, since the grid is log-spaced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptBounds {
+    pub gamma: (f64, f64),
+    pub k: (f64, f64),
+}
+
+/// How much work `optimize` does, and how it shares randomness between candidates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptBudget {
+    /// Grid points along each axis per round, so each round has up to `points_per_axis^2`
+    /// candidates. At least two.
+    pub points_per_axis: usize,
+    /// Rounds after the initial grid over the full bounds.
+    pub refinements: usize,
+    /// Factor in `(0, 1)` by which the grid's log-span shrinks each round.
+    pub shrink: f64,
+    /// Monte Carlo runs per candidate.
+    pub iterations: usize,
+    /// Independent re-evaluations of the final point behind `OptResult::objective_se`. At least
+    /// two.
+    pub replications: usize,
+    /// Run every candidate on the same seeds, so they see identical price paths and fill draws and
+    /// their differences are not swamped by sampling noise. Without it each candidate gets its own
+    /// seeds.
+    pub common_random_numbers: bool,
+}
+
+impl Default for OptBudget {
+    fn default() -> Self {
+        OptBudget {
+            points_per_axis: 5,
+            refinements: 3,
+            shrink: 0.5,
+            iterations: 200,
+            replications: 5,
+            common_random_numbers: true,
+        }
+    }
+}
+
+/// One candidate `optimize` evaluated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptEvaluation {
+    pub round: usize, // Zero for the initial grid
+    pub gamma: f64,
+    pub k: f64,
+    pub objective: Option<f64>, // None when the evaluation failed
+    pub result: Result<SweepResult, SweepError>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptResult {
+    /// `base_params` with the best `gamma` and `k` found.
+    pub best: Parameters,
+    /// The search evaluation that selected `best`.
+    pub best_result: SweepResult,
+    /// Mean objective over `OptBudget::replications` fresh evaluations of `best`. Unlike the
+    /// search score of `best_result`, it is not biased upward by having been picked as a maximum.
+    pub objective: f64,
+    pub objective_se: f64, // Standard error of `objective` across the replications
+    /// Every candidate in evaluation order: round by round, `gamma` varying slowest.
+    pub history: Vec<OptEvaluation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptError {
+    /// The named range must satisfy `0 < low <= high` with both ends finite.
+    InvalidBounds {
+        name: &'static str,
+        low: f64,
+        high: f64,
+    },
+    /// A budget setting is out of range; the message states the requirement.
+    InvalidBudget(&'static str),
+    /// Every candidate failed; the first failure is kept.
+    NoFeasiblePoint(SweepError),
+}
+
+impl fmt::Display for OptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptError::InvalidBounds { name, low, high } => write!(
+                f,
+                "bounds of `{name}` must satisfy 0 < low <= high, got ({low}, {high})"
+            ),
+            OptError::InvalidBudget(requirement) => write!(f, "invalid budget: {requirement}"),
+            OptError::NoFeasiblePoint(err) => write!(f, "no candidate could be evaluated: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for OptError {}
+
+/// Searches `bounds` for the `gamma` and `k` that maximise `objective`, starting from
+/// `base_params` for everything else.
+///
+/// Round zero evaluates a `points_per_axis` grid spaced evenly in log space over the full bounds.
+/// Each later round shrinks the log-span of the grid by `budget.shrink` and centres it on the best
+/// point so far, shifted back inside the bounds where needed; points already evaluated are not run
+/// again. Candidates within a round run in parallel.
+///
+/// All seeds derive from `seed`, so a given call is reproducible regardless of thread scheduling.
+/// A candidate whose objective is NaN never becomes the best.
+pub fn optimize<M: IntensityModel + ?Sized>(
+    base_params: Parameters,
+    bounds: &OptBounds,
+    objective: &Objective,
+    sim_config: &SimConfig,
+    budget: &OptBudget,
+    seed: u64,
+    intensity_model: &M,
+) -> Result<OptResult, OptError> {
+    let log_bounds = [log_range("gamma", bounds.gamma)?, log_range("k", bounds.k)?];
+    if budget.points_per_axis < 2 {
+        return Err(OptError::InvalidBudget(
+            "`points_per_axis` must be at least 2",
+        ));
+    }
+    if !(budget.shrink > 0.0 && budget.shrink < 1.0) {
+        return Err(OptError::InvalidBudget("`shrink` must lie in (0, 1)"));
+    }
+    if budget.iterations == 0 {
+        return Err(OptError::InvalidBudget("`iterations` must be > 0"));
+    }
+    if budget.replications < 2 {
+        return Err(OptError::InvalidBudget("`replications` must be at least 2"));
+    }
+
+    let candidate = |gamma, k| Parameters {
+        gamma,
+        k,
+        ..base_params
+    };
+    let search_seed = |index: usize| {
+        // Stream 0 is shared under common random numbers; otherwise each candidate takes its own.
+        let stream = if budget.common_random_numbers {
+            0
+        } else {
+            index as u64 + 1
+        };
+        derive_seed(seed, stream)
+    };
+
+    let mut history: Vec<OptEvaluation> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut best: Option<usize> = None;
+    let mut windows = log_bounds;
+
+    for round in 0..=budget.refinements {
+        let points: Vec<(f64, f64)> = log_grid(windows[0], budget.points_per_axis)
+            .flat_map(|g| log_grid(windows[1], budget.points_per_axis).map(move |k| (g, k)))
+            .filter(|&(g, k)| seen.insert((g.to_bits(), k.to_bits())))
+            .collect();
+        let first = history.len();
+
+        let evaluations: Vec<OptEvaluation> = points
+            .par_iter()
+            .enumerate()
+            .map(|(i, &(gamma, k))| {
+                let result = evaluate_seeded_point(
+                    &candidate(gamma, k),
+                    sim_config,
+                    budget.iterations,
+                    search_seed(first + i),
+                    intensity_model,
+                );
+                OptEvaluation {
+                    round,
+                    gamma,
+                    k,
+                    objective: result.as_ref().ok().map(|res| objective.value(res)),
+                    result,
+                }
+            })
+            .collect();
+        history.extend(evaluations);
+
+        for (i, eval) in history.iter().enumerate().skip(first) {
+            let score = eval.objective.unwrap_or(f64::NAN);
+            let incumbent = best.and_then(|b| history[b].objective);
+            if !score.is_nan() && incumbent.is_none_or(|b| score > b) {
+                best = Some(i);
+            }
+        }
+
+        let Some(b) = best else { continue };
+        let centre = [history[b].gamma.ln(), history[b].k.ln()];
+        for ((window, range), centre) in windows.iter_mut().zip(log_bounds).zip(centre) {
+            let half = (window.1 - window.0) / 2.0 * budget.shrink;
+            *window = recentre(centre, half, range);
+        }
+    }
+
+    let Some(best) = best else {
+        let first_error = history
+            .into_iter()
+            .find_map(|eval| eval.result.err())
+            .expect("a search with no scored candidate has a failed one");
+        return Err(OptError::NoFeasiblePoint(first_error));
+    };
+    let best_eval = &history[best];
+    let best_params = candidate(best_eval.gamma, best_eval.k);
+    let best_result = best_eval
+        .result
+        .clone()
+        .expect("the best candidate was evaluated");
+
+    // Replications use streams of their own, disjoint from every search seed.
+    let replication_seed = derive_seed(seed, u64::MAX);
+    let scores: Vec<f64> = (0..budget.replications)
+        .into_par_iter()
+        .filter_map(|r| {
+            evaluate_seeded_point(
+                &best_params,
+                sim_config,
+                budget.iterations,
+                derive_seed(replication_seed, r as u64),
+                intensity_model,
+            )
+            .ok()
+            .map(|res| objective.value(&res))
+        })
+        .collect();
+    let mut acc = Welford::default();
+    for score in scores {
+        acc.push(score);
+    }
+
+    Ok(OptResult {
+        best: best_params,
+        best_result,
+        objective: acc.mean(),
+        objective_se: acc.std_dev() / (acc.count() as f64).sqrt(),
+        history,
+    })
+}
+
+/// `(ln low, ln high)` of a validated range.
+fn log_range(name: &'static str, (low, high): (f64, f64)) -> Result<(f64, f64), OptError> {
+    if low > 0.0 && low <= high && high.is_finite() {
+        Ok((low.ln(), high.ln()))
+    } else {
+        Err(OptError::InvalidBounds { name, low, high })
+    }
+}
+
+/// `n >= 2` values spaced evenly in log space across the log window `(lo, hi)`, ends included.
+fn log_grid((lo, hi): (f64, f64), n: usize) -> impl Iterator<Item = f64> {
+    (0..n).map(move |i| (lo + (hi - lo) * i as f64 / (n - 1) as f64).exp())
+}
+
+/// The window of half-width `half` around `centre`, shifted to lie within `range`; the whole of
+/// `range` if it is narrower.
+fn recentre(centre: f64, half: f64, range: (f64, f64)) -> (f64, f64) {
+    if 2.0 * half >= range.1 - range.0 {
+        return range;
+    }
+    let lo = (centre - half).clamp(range.0, range.1 - 2.0 * half);
+    (lo, lo + 2.0 * half)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ExponentialIntensity, QuotingModel};
+
+    fn setup() -> (Parameters, SimConfig, ExponentialIntensity) {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let sim_config = SimConfig::builder().num_steps(100).build().unwrap();
+        (
+            params,
+            sim_config,
+            ExponentialIntensity { k: 1.5, a: 140.0 },
+        )
+    }
+
+    const BOUNDS: OptBounds = OptBounds {
+        gamma: (0.01, 1.0),
+        k: (0.5, 5.0),
+    };
+
+    #[test]
+    fn test_refinement_converges_on_known_optimum() {
+        let (params, sim_config, intensity) = setup();
+        // A smooth score peaking inside the bounds at gamma 0.3, k 2, away from any grid point.
+        let objective =
+            Objective::custom(|res| -(res.gamma / 0.3).ln().powi(2) - (res.k / 2.0).ln().powi(2));
+        let budget = OptBudget {
+            refinements: 6,
+            iterations: 2,
+            ..OptBudget::default()
+        };
+        let opt = optimize(
+            params,
+            &BOUNDS,
+            &objective,
+            &sim_config,
+            &budget,
+            1,
+            &intensity,
+        )
+        .unwrap();
+
+        assert!(
+            (opt.best.gamma / 0.3).ln().abs() < 0.05,
+            "{}",
+            opt.best.gamma
+        );
+        assert!((opt.best.k / 2.0).ln().abs() < 0.05, "{}", opt.best.k);
+        assert_eq!((opt.best.sigma, opt.best.a), (params.sigma, params.a));
+        // Deterministic score: every replication agrees with the search.
+        assert_eq!(opt.objective_se, 0.0);
+        assert_eq!(
+            Some(opt.objective),
+            opt.history.iter().find_map(|e| {
+                (e.gamma == opt.best.gamma && e.k == opt.best.k).then_some(e.objective.unwrap())
+            })
+        );
+
+        // The first round covers the bounds, including both corners.
+        let first: Vec<_> = opt.history.iter().filter(|e| e.round == 0).collect();
+        assert_eq!(first.len(), 25);
+        assert!((first[0].gamma - 0.01).abs() < 1e-12 && (first[0].k - 0.5).abs() < 1e-12);
+        assert!((first[24].gamma - 1.0).abs() < 1e-12 && (first[24].k - 5.0).abs() < 1e-12);
+        // Later rounds stay inside the bounds and skip points already evaluated.
+        assert!(opt.history.iter().all(|e| {
+            (0.01 - 1e-12..=1.0 + 1e-12).contains(&e.gamma)
+                && (0.5 - 1e-12..=5.0 + 1e-12).contains(&e.k)
+        }));
+        assert!(opt.history.iter().filter(|e| e.round > 0).count() < 6 * 25);
+    }
+
+    #[test]
+    fn test_optimize_is_deterministic_given_seed() {
+        let (params, sim_config, intensity) = setup();
+        let budget = OptBudget {
+            points_per_axis: 3,
+            refinements: 1,
+            iterations: 20,
+            replications: 3,
+            ..OptBudget::default()
+        };
+        let run = |seed, common_random_numbers| {
+            let budget = OptBudget {
+                common_random_numbers,
+                ..budget
+            };
+            let objective = Objective::CvarAdjusted { lambda: 0.5 };
+            let opt = optimize(
+                params,
+                &BOUNDS,
+                &objective,
+                &sim_config,
+                &budget,
+                seed,
+                &intensity,
+            )
+            .unwrap();
+            let trace: Vec<_> = opt
+                .history
+                .iter()
+                .map(|e| (e.round, e.gamma, e.k, e.objective))
+                .collect();
+            (opt.best, opt.objective, opt.objective_se, trace)
+        };
+
+        for crn in [true, false] {
+            let first = run(5, crn);
+            assert!(first.2 > 0.0);
+            assert_eq!(first, run(5, crn));
+            assert_ne!(first.3, run(6, crn).3);
+        }
+    }
+
+    #[test]
+    fn test_common_random_numbers_smooth_the_objective() {
+        let (params, sim_config, intensity) = setup();
+        // A single row of gamma values at a fixed k, evaluated once.
+        let bounds = OptBounds {
+            gamma: (0.05, 0.2),
+            k: (1.5, 1.5),
+        };
+        let roughness = |common_random_numbers| {
+            let budget = OptBudget {
+                points_per_axis: 6,
+                refinements: 0,
+                iterations: 100,
+                replications: 2,
+                common_random_numbers,
+                ..OptBudget::default()
+            };
+            let opt = optimize(
+                params,
+                &bounds,
+                &Objective::MeanPnl,
+                &sim_config,
+                &budget,
+                3,
+                &intensity,
+            )
+            .unwrap();
+            let scores: Vec<f64> = opt.history.iter().map(|e| e.objective.unwrap()).collect();
+            assert_eq!(scores.len(), 6);
+            scores.windows(2).map(|w| (w[1] - w[0]).abs()).sum::<f64>()
+        };
+
+        // Shared paths cancel most of the sampling noise between neighbouring candidates.
+        let (shared, independent) = (roughness(true), roughness(false));
+        assert!(shared < independent / 2.0, "{shared} vs {independent}");
+    }
+
+    #[test]
+    fn test_optimize_rejects_invalid_inputs() {
+        let (params, sim_config, intensity) = setup();
+        let budget = OptBudget::default();
+        let reversed = OptBounds {
+            gamma: (1.0, 0.1),
+            ..BOUNDS
+        };
+        let err = optimize(
+            params,
+            &reversed,
+            &Objective::Sharpe,
+            &sim_config,
+            &budget,
+            0,
+            &intensity,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            OptError::InvalidBounds {
+                name: "gamma",
+                low: 1.0,
+                high: 0.1
+            }
+        );
+
+        for budget in [
+            OptBudget {
+                points_per_axis: 1,
+                ..budget
+            },
+            OptBudget {
+                shrink: 1.0,
+                ..budget
+            },
+            OptBudget {
+                iterations: 0,
+                ..budget
+            },
+        ] {
+            let err = optimize(
+                params,
+                &BOUNDS,
+                &Objective::Sharpe,
+                &sim_config,
+                &budget,
+                0,
+                &intensity,
+            )
+            .unwrap_err();
+            assert!(matches!(err, OptError::InvalidBudget(_)), "{err}");
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//! Market making in two correlated instruments, such as an ETF and its largest constituent.
+//!
+//! Both mids follow GBM with correlated shocks, each leg is quoted with its own `Parameters`, and
+//! each leg's reservation price is skewed by the inventory of both, so a long position in one
+//! instrument is partly hedged by leaning the other's quotes short.
+
+use crate::error::ConfigError;
+use crate::model::{
+    IntensityModel, Parameters, drift_adjustment, inventory_skew, optimal_spread, quotable_spread,
+    quotes,
+};
+use crate::sim::{SimConfig, unseeded_rng};
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+/// State of both legs at the start of one step.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PairStep {
+    pub time: f64,
+    pub mid_prices: [f64; 2],
+    pub inventories: [f64; 2],
+    pub bid_prices: [f64; 2],
+    pub ask_prices: [f64; 2],
+    pub wealth: f64, // Cash plus both inventories marked to their mids
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PairResult {
+    pub trajectory: Vec<PairStep>,
+    pub final_pnl: f64,
+    /// Each leg's cash plus its terminal inventory at the final mid; they sum to `final_pnl`.
+    pub leg_pnl: [f64; 2],
+    pub bid_fills: [usize; 2],
+    pub ask_fills: [usize; 2],
+    /// Steps on which a leg's mid was held at `SimConfig::price_floor`, or at its previous value
+    /// when the update was not finite, summed over both legs.
+    pub price_floor_hits: usize,
+}
+
+/// Reservation prices of both legs with inventory risk netted across them.
+///
+/// Leg `i` with own skew `b_i = inventory_skew(params[i], t)` quotes around
+/// `s_i - b_i * q_i - rho * sqrt(b_i * b_j) * q_j`. Under Avellaneda-Stoikov with a common `gamma`
+/// this is the multi-asset reservation price `s - gamma * (T - t) * Sigma * q`, where `Sigma` is
+/// the covariance matrix of the two mids. With `rho = 0` each leg quotes as if alone; with
+/// `rho = 1` and matching legs, offsetting positions cancel and both quote at the mid. Each leg's
+/// own `drift_adjustment` under the common relative `drift` is added on top.
+pub fn pair_reservation_prices(
+    params: &[Parameters; 2],
+    drift: f64,
+    mids: [f64; 2],
+    inventories: [f64; 2],
+    t: f64,
+    rho: f64,
+) -> [f64; 2] {
+    let skews = params.map(|p| inventory_skew(&p, t));
+    let cross = rho * (skews[0].max(0.0) * skews[1].max(0.0)).sqrt();
+    [0, 1].map(|i| {
+        mids[i] + drift_adjustment(&params[i], drift, mids[i], t)
+            - skews[i] * inventories[i]
+            - cross * inventories[1 - i]
+    })
+}
+
+/// Runs a two-instrument trajectory, see `run_trajectory_pair_with_rng`.
+pub fn run_trajectory_pair<M: IntensityModel + ?Sized>(
+    agent_params: &[Parameters; 2],
+    config: &SimConfig,
+    rho: f64,
+    intensity_model: &M,
+) -> Result<PairResult, ConfigError> {
+    run_trajectory_pair_with_rng(
+        agent_params,
+        config,
+        rho,
+        intensity_model,
+        &mut unseeded_rng(),
+    )
+}
+
+/// Runs a two-instrument trajectory drawing all randomness from `rng`.
+///
+/// Both mids start at `config.s_0` and follow GBM with their legs' `sigma`, the common
+/// `config.drift`, and shocks of correlation `rho`. Each mid is held at `config.price_floor` as in
+/// a single-instrument run. Each leg is quoted with `optimal_spread` around
+/// `pair_reservation_prices`, clamped by the half-spread bounds, and filled by `intensity_model`
+/// with independent draws per side and leg. Fills trade `config.order_size`. The remaining
+/// `SimConfig` extensions (latency, regimes, Hawkes arrivals, stochastic liquidity, the
+/// kill-switch, and so on) apply to single-instrument runs only.
+///
+/// Every step consumes two normal and four uniform draws, so seeded runs are comparable across
+/// parameters.
+///
+/// Fails if either leg's parameters or `config` are invalid, or if `rho` is not in `[-1, 1]`,
+/// since the correlated shock would then not be a real number.
+pub fn run_trajectory_pair_with_rng<M: IntensityModel + ?Sized, R: Rng + ?Sized>(
+    agent_params: &[Parameters; 2],
+    config: &SimConfig,
+    rho: f64,
+    intensity_model: &M,
+    rng: &mut R,
+) -> Result<PairResult, ConfigError> {
+    for params in agent_params {
+        params.validate()?;
+    }
+    config.validate()?;
+    if !(-1.0..=1.0).contains(&rho) {
+        return Err(ConfigError::OutOfRange {
+            name: "rho",
+            value: rho,
+            expected: "in [-1, 1]",
+        });
+    }
+    let size = config.order_size;
+    let mut t = 0.0;
+    let mut s = [config.s_0; 2];
+    let mut q = [0.0; 2];
+    let mut cash = [0.0; 2];
+    let (mut bid_fills, mut ask_fills) = ([0usize; 2], [0usize; 2]);
+    let mut price_floor_hits = 0;
+    let mut trajectory = Vec::with_capacity(config.num_steps);
+
+    for _ in 0..config.num_steps {
+        let r = pair_reservation_prices(agent_params, config.drift, s, q, t, rho);
+        let mut bids = [0.0; 2];
+        let mut asks = [0.0; 2];
+        for i in 0..2 {
+            let spread = quotable_spread(optimal_spread(&agent_params[i], t));
+            let half_spread = config.clamp_half_spread(spread / 2.0).max(0.0);
+            (asks[i], bids[i]) = quotes(r[i], 2.0 * half_spread);
+        }
+
+        trajectory.push(PairStep {
+            time: t,
+            mid_prices: s,
+            inventories: q,
+            bid_prices: bids,
+            ask_prices: asks,
+            wealth: cash[0] + cash[1] + q[0] * s[0] + q[1] * s[1],
+        });
+
+        let z0: f64 = rng.sample(StandardNormal);
+        let independent: f64 = rng.sample(StandardNormal);
+        let shocks = [z0, rho * z0 + (1.0 - rho * rho).sqrt() * independent];
+        for i in 0..2 {
+            let innovation = agent_params[i].sigma * config.dt.sqrt() * shocks[i];
+            let proposed = s[i] * (1.0 + config.drift * config.dt + innovation);
+            // As in `sim::run_trajectory_with_rng`, a large negative move must not cross zero.
+            if !proposed.is_finite() {
+                price_floor_hits += 1;
+            } else if proposed < config.price_floor {
+                price_floor_hits += 1;
+                s[i] = config.price_floor;
+            } else {
+                s[i] = proposed;
+            }
+        }
+
+        for i in 0..2 {
+            let prob_bid = intensity_model.bid_intensity(s[i] - bids[i]) * config.dt;
+            let prob_ask = intensity_model.ask_intensity(asks[i] - s[i]) * config.dt;
+            let (bid_u, ask_u): (f64, f64) = (rng.random(), rng.random());
+            if bid_u < prob_bid {
+                q[i] += size;
+                cash[i] -= bids[i] * size;
+                bid_fills[i] += 1;
+            }
+            if ask_u < prob_ask {
+                q[i] -= size;
+                cash[i] += asks[i] * size;
+                ask_fills[i] += 1;
+            }
+        }
+
+        t += config.dt;
+    }
+
+    let leg_pnl = [0, 1].map(|i| cash[i] + q[i] * s[i]);
+    Ok(PairResult {
+        trajectory,
+        final_pnl: leg_pnl[0] + leg_pnl[1],
+        leg_pnl,
+        bid_fills,
+        ask_fills,
+        price_floor_hits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ExponentialIntensity, QuotingModel, reservation_price};
+    use crate::stats::correlation;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn legs() -> [Parameters; 2] {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        [params; 2]
+    }
+
+    #[test]
+    fn test_pair_reservation_nets_inventory() {
+        let params = legs();
+        let alone = reservation_price(&params[0], 0.0, 100.0, 2.0, 0.25);
+
+        let uncorrelated =
+            pair_reservation_prices(&params, 0.0, [100.0; 2], [2.0, -3.0], 0.25, 0.0);
+        assert_eq!(uncorrelated[0], alone);
+
+        // Perfectly correlated offsetting positions are fully hedged.
+        let hedged = pair_reservation_prices(&params, 0.0, [100.0; 2], [2.0, -2.0], 0.25, 1.0);
+        assert!(
+            hedged.iter().all(|r| (r - 100.0).abs() < 1e-12),
+            "{hedged:?}"
+        );
+
+        // A position in the other leg alone leans this leg's quotes the same way.
+        let leaned = pair_reservation_prices(&params, 0.0, [100.0; 2], [0.0, 2.0], 0.25, 0.5);
+        assert!(leaned[0] < 100.0 && leaned[1] < leaned[0]);
+    }
+
+    #[test]
+    fn test_inventories_co_move_only_when_correlated() {
+        let sim_config = SimConfig::builder().num_steps(200).build().unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let inventory_change_corr = |rho| {
+            let mut rng = StdRng::seed_from_u64(9);
+            let (mut first, mut second) = (Vec::new(), Vec::new());
+            for _ in 0..50 {
+                let res =
+                    run_trajectory_pair_with_rng(&legs(), &sim_config, rho, &intensity, &mut rng)
+                        .unwrap();
+                assert_eq!(res.final_pnl, res.leg_pnl[0] + res.leg_pnl[1]);
+                for pair in res.trajectory.windows(2) {
+                    first.push(pair[1].inventories[0] - pair[0].inventories[0]);
+                    second.push(pair[1].inventories[1] - pair[0].inventories[1]);
+                }
+            }
+            correlation(&first, &second)
+        };
+
+        let (correlated, independent) = (inventory_change_corr(1.0), inventory_change_corr(0.0));
+        assert!(correlated > 0.3, "{correlated}");
+        assert!(independent.abs() < 0.05, "{independent}");
+    }
+
+    #[test]
+    fn test_pair_mids_stay_above_the_floor() {
+        // A volatility this large pushes the multiplicative update below zero within a few steps.
+        let wild = legs().map(|p| Parameters { sigma: 20.0, ..p });
+        let sim_config = SimConfig::builder()
+            .num_steps(200)
+            .price_floor(1.0)
+            .build()
+            .unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let res = run_trajectory_pair_with_rng(
+            &wild,
+            &sim_config,
+            0.5,
+            &intensity,
+            &mut StdRng::seed_from_u64(3),
+        )
+        .unwrap();
+        assert!(res.price_floor_hits > 0);
+        assert!(
+            res.trajectory
+                .iter()
+                .all(|step| step.mid_prices.iter().all(|&s| s >= 1.0))
+        );
+    }
+
+    #[test]
+    fn test_pair_rejects_rho_outside_unit_interval() {
+        let sim_config = SimConfig::builder().num_steps(10).build().unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let err = run_trajectory_pair_with_rng(
+            &legs(),
+            &sim_config,
+            1.5,
+            &intensity,
+            &mut StdRng::seed_from_u64(0),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::OutOfRange { name: "rho", value, .. } if value == 1.5
+        ));
+        assert_eq!(err.to_string(), "`rho` must be in [-1, 1], got 1.5");
+    }
+}
@@ -0,0 +1,46 @@
+//! Parallel iteration for the Monte Carlo loops: rayon's with the `parallel` feature, and plain
+//! sequential iterators with the same method names without it.
+//!
+//! Only the adapters shared by both (`map`, `filter_map`, `enumerate`, `collect`, `sum`, ...) and
+//! `map_init` may follow `par_iter` and `into_par_iter`, so every loop compiles either way.
+
+#[cfg(feature = "parallel")]
+pub(crate) use rayon::prelude::*;
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) trait IntoParallelIterator: IntoIterator + Sized {
+    fn into_par_iter(self) -> Self::IntoIter {
+        self.into_iter()
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+impl<I: IntoIterator> IntoParallelIterator for I {}
+
+/// `map_init` for sequential iterators: one state from `init`, shared by every item.
+#[cfg(not(feature = "parallel"))]
+pub(crate) trait ParallelIterator: Iterator + Sized {
+    fn map_init<T, R, INIT, F>(self, init: INIT, f: F) -> impl Iterator<Item = R>
+    where
+        INIT: Fn() -> T,
+        F: Fn(&mut T, Self::Item) -> R,
+    {
+        let mut state = init();
+        self.map(move |item| f(&mut state, item))
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+impl<I: Iterator> ParallelIterator for I {}
+
+#[cfg(not(feature = "parallel"))]
+pub(crate) trait ParallelSlice<T> {
+    fn par_iter(&self) -> std::slice::Iter<'_, T>;
+}
+
+#[cfg(not(feature = "parallel"))]
+impl<T> ParallelSlice<T> for [T] {
+    fn par_iter(&self) -> std::slice::Iter<'_, T> {
+        self.iter()
+    }
+}
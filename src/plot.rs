@@ -0,0 +1,610 @@
+//! Figures of sweeps and single runs, drawn with `plotters`.
+//!
+//! Every function writes one file whose format follows its extension: `.png` for a bitmap or
+//! `.svg` for a vector image.
+
+use crate::analysis::{SweepMetric, SweepResult};
+use crate::sim::SimResult;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Pixel size of every figure.
+const FIGURE_SIZE: (u32, u32) = (960, 720);
+
+/// A swept input of `SweepResult` to lay out along a plot axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlotAxis {
+    Gamma,
+    Sigma,
+    K,
+    A,
+    THorizon,
+    Drift,
+    LatencySteps,
+}
+
+impl PlotAxis {
+    /// Axis label, the name of the `SweepResult` field.
+    pub fn name(self) -> &'static str {
+        match self {
+            PlotAxis::Gamma => "gamma",
+            PlotAxis::Sigma => "sigma",
+            PlotAxis::K => "k",
+            PlotAxis::A => "a",
+            PlotAxis::THorizon => "t_horizon",
+            PlotAxis::Drift => "drift",
+            PlotAxis::LatencySteps => "latency_steps",
+        }
+    }
+
+    pub fn value(self, result: &SweepResult) -> f64 {
+        match self {
+            PlotAxis::Gamma => result.gamma,
+            PlotAxis::Sigma => result.sigma,
+            PlotAxis::K => result.k,
+            PlotAxis::A => result.a,
+            PlotAxis::THorizon => result.t_horizon,
+            PlotAxis::Drift => result.drift,
+            PlotAxis::LatencySteps => result.latency_steps as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlotError {
+    /// The path has neither a `png` nor an `svg` extension.
+    UnsupportedFormat(PathBuf),
+    /// Nothing to draw: no results, or a trajectory without steps.
+    NoData,
+    /// Both heatmap axes take a single value across the results, so there is nothing to vary.
+    SingleValueAxes { x: PlotAxis, y: PlotAxis },
+    /// The backend failed to draw or to write the file.
+    Draw(String),
+}
+
+impl fmt::Display for PlotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlotError::UnsupportedFormat(path) => write!(
+                f,
+                "cannot tell the image format of {}; use a .png or .svg extension",
+                path.display()
+            ),
+            PlotError::NoData => write!(f, "nothing to plot"),
+            PlotError::SingleValueAxes { x, y } => write!(
+                f,
+                "`{}` and `{}` each take a single value; sweep at least one of them",
+                x.name(),
+                y.name()
+            ),
+            PlotError::Draw(message) => write!(f, "failed to draw: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for PlotError {}
+
+fn draw_err<E: std::error::Error + Send + Sync>(err: DrawingAreaErrorKind<E>) -> PlotError {
+    PlotError::Draw(err.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Png,
+    Svg,
+}
+
+fn format_of(path: &Path) -> Result<Format, PlotError> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("png") => Ok(Format::Png),
+        Some(ext) if ext.eq_ignore_ascii_case("svg") => Ok(Format::Svg),
+        _ => Err(PlotError::UnsupportedFormat(path.to_path_buf())),
+    }
+}
+
+/// What `plot_sweep_heatmap` draws once the results are laid out on the two axes.
+#[derive(Debug, Clone, PartialEq)]
+enum SweepLayout {
+    /// `cells[j][i]` is the metric at `(xs[i], ys[j])`, NaN where no result has a finite value.
+    Heatmap {
+        xs: Vec<f64>,
+        ys: Vec<f64>,
+        cells: Vec<Vec<f64>>,
+    },
+    /// The metric against the only axis that varies, as `(value, metric)` points in axis order.
+    Line {
+        axis: PlotAxis,
+        points: Vec<(f64, f64)>,
+    },
+}
+
+fn distinct(values: impl Iterator<Item = f64>) -> Vec<f64> {
+    let mut values: Vec<f64> = values.collect();
+    values.sort_by(f64::total_cmp);
+    values.dedup();
+    values
+}
+
+/// Averages the finite metric values of the results sharing each `(x, y)` pair; pairs without
+/// one come out NaN.
+fn cell_means(
+    results: &[SweepResult],
+    (x_axis, xs): (PlotAxis, &[f64]),
+    (y_axis, ys): (PlotAxis, &[f64]),
+    metric: SweepMetric,
+) -> Vec<Vec<f64>> {
+    let mut sums = vec![vec![(0.0, 0usize); xs.len()]; ys.len()];
+    for res in results {
+        let value = metric.value(res);
+        if !value.is_finite() {
+            continue;
+        }
+        let i = xs.iter().position(|&x| x == x_axis.value(res));
+        let j = ys.iter().position(|&y| y == y_axis.value(res));
+        if let (Some(i), Some(j)) = (i, j) {
+            sums[j][i].0 += value;
+            sums[j][i].1 += 1;
+        }
+    }
+    sums.into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|(sum, n)| if n == 0 { f64::NAN } else { sum / n as f64 })
+                .collect()
+        })
+        .collect()
+}
+
+fn layout(
+    results: &[SweepResult],
+    x_axis: PlotAxis,
+    y_axis: PlotAxis,
+    metric: SweepMetric,
+) -> Result<SweepLayout, PlotError> {
+    if results.is_empty() {
+        return Err(PlotError::NoData);
+    }
+    let xs = distinct(results.iter().map(|r| x_axis.value(r)));
+    let ys = distinct(results.iter().map(|r| y_axis.value(r)));
+    let cells = cell_means(results, (x_axis, &xs), (y_axis, &ys), metric);
+    match (xs.len(), ys.len()) {
+        (1, 1) => Err(PlotError::SingleValueAxes {
+            x: x_axis,
+            y: y_axis,
+        }),
+        (_, 1) => Ok(SweepLayout::Line {
+            axis: x_axis,
+            points: xs.into_iter().zip(cells[0].iter().copied()).collect(),
+        }),
+        (1, _) => Ok(SweepLayout::Line {
+            axis: y_axis,
+            points: ys.into_iter().zip(cells.iter().map(|row| row[0])).collect(),
+        }),
+        _ => Ok(SweepLayout::Heatmap { xs, ys, cells }),
+    }
+}
+
+/// Range covering the finite `values` with 5% headroom either side; a unit range around a single
+/// value, and `0..1` without any.
+fn padded_range(values: impl Iterator<Item = f64>) -> Range<f64> {
+    let (lo, hi) = values
+        .filter(|v| v.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        });
+    if lo > hi {
+        0.0..1.0
+    } else if lo == hi {
+        lo - 0.5..hi + 0.5
+    } else {
+        let pad = 0.05 * (hi - lo);
+        lo - pad..hi + pad
+    }
+}
+
+/// Plots `metric` over two swept axes as a heatmap, one labelled cell per `(x, y)` pair.
+///
+/// Results sharing a pair, because another axis or the intensity model varies too, are averaged,
+/// skipping NaN metrics; cells with no finite value are drawn grey. If only one of the axes takes
+/// more than one value, the metric is drawn as a line against it instead. If neither does, this
+/// returns `PlotError::SingleValueAxes`.
+pub fn plot_sweep_heatmap<P: AsRef<Path>>(
+    results: &[SweepResult],
+    x_axis: PlotAxis,
+    y_axis: PlotAxis,
+    metric: SweepMetric,
+    path: P,
+) -> Result<(), PlotError> {
+    let path = path.as_ref();
+    let format = format_of(path)?;
+    let layout = layout(results, x_axis, y_axis, metric)?;
+    let axes = (x_axis, y_axis);
+    match format {
+        Format::Png => draw_sweep(
+            &BitMapBackend::new(path, FIGURE_SIZE).into_drawing_area(),
+            &layout,
+            axes,
+            metric,
+        ),
+        Format::Svg => draw_sweep(
+            &SVGBackend::new(path, FIGURE_SIZE).into_drawing_area(),
+            &layout,
+            axes,
+            metric,
+        ),
+    }
+}
+
+fn draw_sweep<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    layout: &SweepLayout,
+    (x_axis, y_axis): (PlotAxis, PlotAxis),
+    metric: SweepMetric,
+) -> Result<(), PlotError> {
+    match layout {
+        SweepLayout::Heatmap { xs, ys, cells } => {
+            draw_heatmap(root, (x_axis, xs), (y_axis, ys), cells, metric)
+        }
+        SweepLayout::Line { axis, points } => draw_metric_line(root, *axis, points, metric),
+    }
+}
+
+fn draw_heatmap<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    (x_axis, xs): (PlotAxis, &[f64]),
+    (y_axis, ys): (PlotAxis, &[f64]),
+    cells: &[Vec<f64>],
+    metric: SweepMetric,
+) -> Result<(), PlotError> {
+    root.fill(&WHITE).map_err(draw_err)?;
+    let color_range = padded_range(cells.iter().flatten().copied());
+    // Cell `i` is centred on `i`; the tick labels map indices back to the swept values, so
+    // log-spaced grids get evenly sized cells.
+    let mut chart = ChartBuilder::on(root)
+        .caption(
+            format!(
+                "{} by {} and {}",
+                metric.name(),
+                x_axis.name(),
+                y_axis.name()
+            ),
+            ("sans-serif", 24),
+        )
+        .margin(15)
+        .x_label_area_size(50)
+        .y_label_area_size(70)
+        .build_cartesian_2d(-0.5..xs.len() as f64 - 0.5, -0.5..ys.len() as f64 - 0.5)
+        .map_err(draw_err)?;
+    let tick_label = |values: &[f64], v: f64| {
+        let i = v.round();
+        if (v - i).abs() < 1e-9 && i >= 0.0 && (i as usize) < values.len() {
+            format!("{}", values[i as usize])
+        } else {
+            String::new()
+        }
+    };
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .x_desc(x_axis.name())
+        .y_desc(y_axis.name())
+        .x_labels(xs.len())
+        .y_labels(ys.len())
+        .x_label_formatter(&|v| tick_label(xs, *v))
+        .y_label_formatter(&|v| tick_label(ys, *v))
+        .draw()
+        .map_err(draw_err)?;
+
+    let label_style =
+        TextStyle::from(("sans-serif", 14).into_font()).pos(Pos::new(HPos::Center, VPos::Center));
+    for (j, row) in cells.iter().enumerate() {
+        for (i, &value) in row.iter().enumerate() {
+            let (x, y) = (i as f64, j as f64);
+            let shade = (value - color_range.start) / (color_range.end - color_range.start);
+            let fill = if value.is_finite() {
+                ViridisRGB::get_color(shade)
+            } else {
+                RGBColor(200, 200, 200)
+            };
+            let text = if value.is_finite() {
+                format!("{value:.2}")
+            } else {
+                "n/a".to_string()
+            };
+            let text_color = if value.is_finite() && shade < 0.5 {
+                WHITE
+            } else {
+                BLACK
+            };
+            chart
+                .draw_series([Rectangle::new(
+                    [(x - 0.5, y - 0.5), (x + 0.5, y + 0.5)],
+                    fill.filled(),
+                )])
+                .map_err(draw_err)?;
+            chart
+                .draw_series([Text::new(text, (x, y), label_style.color(&text_color))])
+                .map_err(draw_err)?;
+        }
+    }
+    root.present().map_err(draw_err)
+}
+
+fn draw_metric_line<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    axis: PlotAxis,
+    points: &[(f64, f64)],
+    metric: SweepMetric,
+) -> Result<(), PlotError> {
+    root.fill(&WHITE).map_err(draw_err)?;
+    let mut chart = ChartBuilder::on(root)
+        .caption(
+            format!("{} by {}", metric.name(), axis.name()),
+            ("sans-serif", 24),
+        )
+        .margin(15)
+        .x_label_area_size(50)
+        .y_label_area_size(70)
+        .build_cartesian_2d(
+            padded_range(points.iter().map(|p| p.0)),
+            padded_range(points.iter().map(|p| p.1)),
+        )
+        .map_err(draw_err)?;
+    chart
+        .configure_mesh()
+        .x_desc(axis.name())
+        .y_desc(metric.name())
+        .draw()
+        .map_err(draw_err)?;
+    let finite: Vec<(f64, f64)> = points.iter().copied().filter(|p| p.1.is_finite()).collect();
+    chart
+        .draw_series(LineSeries::new(finite.iter().copied(), &BLUE))
+        .map_err(draw_err)?;
+    chart
+        .draw_series(finite.iter().map(|&p| Circle::new(p, 4, BLUE.filled())))
+        .map_err(draw_err)?;
+    root.present().map_err(draw_err)
+}
+
+/// Plots one run in two panels sharing the time axis: mid, bid, and ask prices above, inventory
+/// below. Quotes are left out once a kill-switch has halted quoting.
+pub fn plot_trajectory<P: AsRef<Path>>(result: &SimResult, path: P) -> Result<(), PlotError> {
+    let path = path.as_ref();
+    let format = format_of(path)?;
+    if result.trajectory.is_empty() {
+        return Err(PlotError::NoData);
+    }
+    match format {
+        Format::Png => draw_trajectory(
+            &BitMapBackend::new(path, FIGURE_SIZE).into_drawing_area(),
+            result,
+        ),
+        Format::Svg => draw_trajectory(
+            &SVGBackend::new(path, FIGURE_SIZE).into_drawing_area(),
+            result,
+        ),
+    }
+}
+
+fn draw_trajectory<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    result: &SimResult,
+) -> Result<(), PlotError> {
+    let steps = &result.trajectory;
+    root.fill(&WHITE).map_err(draw_err)?;
+    let (upper, lower) = root.split_vertically(FIGURE_SIZE.1 * 3 / 5);
+    let (start, end) = (steps[0].time, steps[steps.len() - 1].time);
+    let times = start..if end > start { end } else { start + 1.0 };
+
+    let prices = steps
+        .iter()
+        .flat_map(|s| [s.mid_price, s.bid_price, s.ask_price]);
+    let mut price_chart = ChartBuilder::on(&upper)
+        .caption("Quotes", ("sans-serif", 22))
+        .margin(15)
+        .x_label_area_size(30)
+        .y_label_area_size(70)
+        .build_cartesian_2d(times.clone(), padded_range(prices))
+        .map_err(draw_err)?;
+    price_chart
+        .configure_mesh()
+        .y_desc("price")
+        .draw()
+        .map_err(draw_err)?;
+    let price_lines = [
+        (
+            "mid",
+            BLACK,
+            steps.iter().map(|s| s.mid_price).collect::<Vec<_>>(),
+        ),
+        ("bid", BLUE, steps.iter().map(|s| s.bid_price).collect()),
+        ("ask", RED, steps.iter().map(|s| s.ask_price).collect()),
+    ];
+    for (label, color, prices) in price_lines {
+        let points = steps
+            .iter()
+            .zip(prices)
+            .map(|(s, price)| (s.time, price))
+            .filter(|p| p.1.is_finite());
+        price_chart
+            .draw_series(LineSeries::new(points, &color))
+            .map_err(draw_err)?
+            .label(label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+    price_chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .map_err(draw_err)?;
+
+    let mut inventory_chart = ChartBuilder::on(&lower)
+        .caption("Inventory", ("sans-serif", 22))
+        .margin(15)
+        .x_label_area_size(40)
+        .y_label_area_size(70)
+        .build_cartesian_2d(times, padded_range(steps.iter().map(|s| s.inventory)))
+        .map_err(draw_err)?;
+    inventory_chart
+        .configure_mesh()
+        .x_desc("time")
+        .y_desc("inventory")
+        .draw()
+        .map_err(draw_err)?;
+    inventory_chart
+        .draw_series(LineSeries::new(
+            steps.iter().map(|s| (s.time, s.inventory)),
+            &BLUE,
+        ))
+        .map_err(draw_err)?;
+    root.present().map_err(draw_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ExponentialIntensity, Parameters, QuotingModel};
+    use crate::sim::{SimConfig, run_trajectory_with_rng};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn result(gamma: f64, sigma: f64, drift: f64, mean_pnl: f64) -> SweepResult {
+        SweepResult {
+            gamma,
+            sigma,
+            drift,
+            mean_pnl,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_layout_averages_cells_and_falls_back_to_a_line() {
+        let results = [
+            result(0.1, 0.2, 0.0, 1.0),
+            result(0.1, 0.2, 0.05, 3.0),
+            result(1.0, 0.2, 0.0, f64::NAN),
+            result(0.1, 0.4, 0.0, 4.0),
+        ];
+        let heatmap = layout(
+            &results,
+            PlotAxis::Gamma,
+            PlotAxis::Sigma,
+            SweepMetric::MeanPnl,
+        );
+        let Ok(SweepLayout::Heatmap { xs, ys, cells }) = heatmap else {
+            panic!("{heatmap:?}");
+        };
+        assert_eq!((xs, ys), (vec![0.1, 1.0], vec![0.2, 0.4]));
+        assert_eq!(cells[0][0], 2.0);
+        assert!(cells[0][1].is_nan() && cells[1][1].is_nan());
+        assert_eq!(cells[1][0], 4.0);
+
+        let line = layout(
+            &results[..3],
+            PlotAxis::Gamma,
+            PlotAxis::Sigma,
+            SweepMetric::MeanPnl,
+        );
+        let Ok(SweepLayout::Line { axis, points }) = line else {
+            panic!("{line:?}");
+        };
+        assert_eq!(axis, PlotAxis::Gamma);
+        assert_eq!(points[0], (0.1, 2.0));
+        assert!(points[1].1.is_nan());
+
+        assert_eq!(
+            layout(
+                &results[..2],
+                PlotAxis::Gamma,
+                PlotAxis::Sigma,
+                SweepMetric::MeanPnl
+            ),
+            Err(PlotError::SingleValueAxes {
+                x: PlotAxis::Gamma,
+                y: PlotAxis::Sigma
+            })
+        );
+    }
+
+    #[test]
+    fn test_plots_write_svg() {
+        let dir = std::env::temp_dir();
+        let results = [
+            result(0.1, 0.2, 0.0, 1.0),
+            result(1.0, 0.2, 0.0, 2.0),
+            result(0.1, 0.4, 0.0, f64::NAN),
+            result(1.0, 0.4, 0.0, 4.0),
+        ];
+        let heatmap = dir.join(format!("as_heatmap_{}.svg", std::process::id()));
+        plot_sweep_heatmap(
+            &results,
+            PlotAxis::Gamma,
+            PlotAxis::Sigma,
+            SweepMetric::MeanPnl,
+            &heatmap,
+        )
+        .unwrap();
+        let svg = std::fs::read_to_string(&heatmap).unwrap();
+        std::fs::remove_file(&heatmap).unwrap();
+        assert!(svg.contains("<svg") && svg.contains("mean_pnl by gamma and sigma"));
+
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let sim_config = SimConfig::builder().num_steps(50).build().unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let run = run_trajectory_with_rng(
+            &params,
+            &sim_config,
+            &intensity,
+            &mut StdRng::seed_from_u64(3),
+        );
+        let trajectory = dir.join(format!("as_trajectory_{}.svg", std::process::id()));
+        plot_trajectory(&run, &trajectory).unwrap();
+        let svg = std::fs::read_to_string(&trajectory).unwrap();
+        std::fs::remove_file(&trajectory).unwrap();
+        assert!(svg.contains("Quotes") && svg.contains("Inventory"));
+
+        assert_eq!(
+            plot_trajectory(&run, dir.join("trajectory.jpg")),
+            Err(PlotError::UnsupportedFormat(dir.join("trajectory.jpg")))
+        );
+        assert_eq!(
+            plot_trajectory(&SimResult::default(), dir.join("empty.svg")),
+            Err(PlotError::NoData)
+        );
+    }
+
+    #[test]
+    fn test_trajectory_png_is_written() {
+        // A tight drawdown stop halts quoting part-way, leaving NaN quotes to skip.
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let sim_config = SimConfig::builder()
+            .num_steps(400)
+            .max_drawdown_stop(1.0)
+            .build()
+            .unwrap();
+        let intensity = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let run = run_trajectory_with_rng(
+            &params,
+            &sim_config,
+            &intensity,
+            &mut StdRng::seed_from_u64(5),
+        );
+        assert!(run.trajectory.iter().any(|s| s.bid_price.is_nan()));
+
+        let path = std::env::temp_dir().join(format!("as_trajectory_{}.png", std::process::id()));
+        plot_trajectory(&run, &path).unwrap();
+        let png = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"), "{} bytes", png.len());
+        assert!(png.len() > 1000);
+    }
+}
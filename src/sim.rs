@@ -1,75 +1,1282 @@
-use crate::model::{IntensityModel, Parameters, optimal_spread, quotes, reservation_price};
-use rand::Rng;
-use rand_distr::{Distribution, StandardNormal};
+use crate::model::{
+    ExponentialIntensity, IntensityModel, Parameters, decide_quotes, optimal_spread,
+    quotable_spread, quotes,
+};
+use crate::sizes::EmpiricalSizeDistribution;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::StandardNormal;
 use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Copy)]
+/// Prefer `SimConfig::builder()`, which validates what it builds. The fields stay public so
+/// existing struct literals keep compiling, but a literal is only checked if it is passed to
+/// `validate` (as `run_sweep` does).
+///
+/// With the `serde` feature, every optional model extension may be omitted from a config and
+/// defaults to disabled, while unknown keys are rejected.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
 pub struct SimConfig {
     pub dt: f64,
     pub num_steps: usize,
     pub s_0: f64,
     pub drift: f64,
     pub latency_steps: usize,
+    pub staleness_threshold: f64, // Quote deviation counted as stale (e.g. one tick)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub size_distribution: Option<Arc<EmpiricalSizeDistribution>>, // None = one lot per fill
+    /// Inventory units per lot: each fill trades the sampled lot count (one without a size
+    /// distribution) times this, so fractional values give fractional inventory.
+    #[cfg_attr(feature = "serde", serde(default = "default_order_size"))]
+    pub order_size: f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hawkes: Option<HawkesConfig>, // None = Poisson arrivals
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub regimes: Option<RegimeSwitching>, // None = constant `drift` and agent `sigma`
+    /// Relative price move per unit of net fill against the agent on the following step: a bid
+    /// fill of size n pushes the next return down by `adverse_selection * n`. Zero disables it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub adverse_selection: f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub liquidity: Option<StochasticLiquidity>, // None = static intensity model
+    /// Flatten and stop quoting once wealth falls this far below its running peak.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_drawdown_stop: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub liquidation_cost_per_unit: Option<f64>, // Charged per unit flattened; None = at the mid
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub price_process: PriceProcess,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fill_model: FillModel,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub simultaneous_fills: SimultaneousFills,
+    /// How held inventory is valued in `StepRecord::wealth`, the drawdown kill-switch, and
+    /// `SimResult::final_pnl`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub inventory_mark: InventoryMark,
+    /// Recompute quotes only every this many steps and re-send the previous ones in between, as a
+    /// system that does not cancel/replace on every tick. One refreshes every step.
+    /// `quote_staleness_*` still compares against fresh quotes, so it also counts the hold.
+    #[cfg_attr(feature = "serde", serde(default = "default_requote_interval"))]
+    pub requote_interval: usize,
+    /// Bounds on the half-spread each side of the reservation price, applied before `quotes`. A
+    /// positive minimum keeps the ask above the bid however the spread formula behaves.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub min_half_spread: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_half_spread: Option<f64>,
+    /// Inventory held at the start, as a book inherited at the opening mid (`s_0`, or the first
+    /// replayed price): cash starts at minus its value there under `inventory_mark`, so initial
+    /// wealth is zero and PnL counts only what happens after. The skew leans toward unwinding it
+    /// from the first step.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub initial_inventory: f64,
+    /// Lowest mid the price process may reach. A step that would take the mid below it, or to a
+    /// non-finite value, holds the mid at the floor (or at its previous value when non-finite) and
+    /// counts in `SimResult::price_floor_hits`, so a run contaminated by the guard is visible.
+    #[cfg_attr(feature = "serde", serde(default = "default_price_floor"))]
+    pub price_floor: f64,
+    /// Place in the queue at each quote, from 0 (front) to 1 (back): only the `1 - queue_position`
+    /// share of the arriving flow that gets past the orders ahead fills, so both sides' intensity
+    /// is scaled by it. Deterministic crossing fills ignore it, as the price trades through.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub queue_position: f64,
+    /// Standard deviation, in price units, of the error in the mid the strategy quotes from: each
+    /// step it sees `s + observation_noise * Z` for a fresh standard normal `Z`, while fills and
+    /// marking use the true mid. `Z` is drawn even at zero noise, so noisy and exact runs with the
+    /// same seed share a price path.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub observation_noise: f64,
+    /// Funding rate per unit time on the value of the position, as for a perpetual future: each
+    /// step cash falls by `carry_rate * q * s * dt` at the step's opening mid, so longs pay and
+    /// shorts receive when it is positive. Zero charges nothing.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub carry_rate: f64,
+    /// Closures cannot be serialised, so a deserialised config never has a signal.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub signal: Option<QuoteSignal>, // None = quotes from inventory skew alone
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Short-term price signal overlaid on the inventory skew, see `reservation_price_with_signal`.
+#[derive(Clone)]
+pub struct QuoteSignal {
+    pub weight: f64, // Reservation-price shift per unit of signal
+    /// Signal at `(t, s)` on each step, positive when the mid is expected to rise.
+    pub source: Arc<dyn Fn(f64, f64) -> f64 + Send + Sync>,
+}
+
+impl QuoteSignal {
+    pub fn new<F: Fn(f64, f64) -> f64 + Send + Sync + 'static>(weight: f64, source: F) -> Self {
+        QuoteSignal {
+            weight,
+            source: Arc::new(source),
+        }
+    }
+}
+
+impl fmt::Debug for QuoteSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QuoteSignal")
+            .field("weight", &self.weight)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Signals are equal when they share both the weight and the same closure allocation.
+impl PartialEq for QuoteSignal {
+    fn eq(&self, other: &Self) -> bool {
+        self.weight == other.weight && Arc::ptr_eq(&self.source, &other.source)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn default_order_size() -> f64 {
+    1.0
+}
+
+#[cfg(feature = "serde")]
+fn default_requote_interval() -> usize {
+    1
+}
+
+#[cfg(feature = "serde")]
+fn default_price_floor() -> f64 {
+    0.01
+}
+
+/// The defaults listed on `SimConfigBuilder`.
+impl Default for SimConfig {
+    fn default() -> Self {
+        SimConfig {
+            dt: 0.005,
+            num_steps: 600,
+            s_0: 100.0,
+            drift: 0.0,
+            latency_steps: 0,
+            staleness_threshold: 0.01,
+            size_distribution: None,
+            order_size: 1.0,
+            hawkes: None,
+            regimes: None,
+            adverse_selection: 0.0,
+            liquidity: None,
+            max_drawdown_stop: None,
+            liquidation_cost_per_unit: None,
+            price_process: PriceProcess::Gbm,
+            fill_model: FillModel::Stochastic,
+            simultaneous_fills: SimultaneousFills::BothAllowed,
+            inventory_mark: InventoryMark::Mid,
+            requote_interval: 1,
+            min_half_spread: None,
+            max_half_spread: None,
+            initial_inventory: 0.0,
+            price_floor: 0.01,
+            queue_position: 0.0,
+            observation_noise: 0.0,
+            carry_rate: 0.0,
+            signal: None,
+        }
+    }
+}
+
+impl SimConfig {
+    /// Starts a builder with the defaults listed on `SimConfigBuilder`.
+    pub fn builder() -> SimConfigBuilder {
+        SimConfigBuilder::default()
+    }
+
+    /// Checks `dt > 0`, `num_steps > 0`, `s_0 > 0`, `latency_steps < num_steps`, `order_size > 0`,
+    /// `requote_interval > 0`, `0 <= min_half_spread <= max_half_spread` with `max_half_spread`
+    /// positive, a finite `initial_inventory`, `0 < price_floor < s_0`,
+    /// `0 <= queue_position <= 1`, a finite, non-negative `InventoryMark::Conservative`
+    /// haircut, a finite, non-negative `observation_noise`, and a finite `carry_rate`.
+    pub fn validate(&self) -> Result<(), SimError> {
+        if self.dt.is_nan() || self.dt <= 0.0 {
+            return Err(SimError::NonPositiveDt(self.dt));
+        }
+        if self.num_steps == 0 {
+            return Err(SimError::NoSteps);
+        }
+        if !(self.s_0.is_finite() && self.s_0 > 0.0) {
+            return Err(SimError::NonPositiveS0(self.s_0));
+        }
+        if self.latency_steps >= self.num_steps {
+            return Err(SimError::LatencyTooLong {
+                latency_steps: self.latency_steps,
+                num_steps: self.num_steps,
+            });
+        }
+        if !(self.order_size.is_finite() && self.order_size > 0.0) {
+            return Err(SimError::NonPositiveOrderSize(self.order_size));
+        }
+        if self.requote_interval == 0 {
+            return Err(SimError::ZeroRequoteInterval);
+        }
+        let min_ok = self
+            .min_half_spread
+            .is_none_or(|min| min.is_finite() && min >= 0.0);
+        let max_ok = self
+            .max_half_spread
+            .is_none_or(|max| max.is_finite() && max > 0.0);
+        let ordered = match (self.min_half_spread, self.max_half_spread) {
+            (Some(min), Some(max)) => min <= max,
+            _ => true,
+        };
+        if !(min_ok && max_ok && ordered) {
+            return Err(SimError::InvalidHalfSpreadBounds {
+                min: self.min_half_spread,
+                max: self.max_half_spread,
+            });
+        }
+        if !self.initial_inventory.is_finite() {
+            return Err(SimError::NonFiniteInitialInventory(self.initial_inventory));
+        }
+        if !(self.price_floor > 0.0 && self.price_floor < self.s_0) {
+            return Err(SimError::InvalidPriceFloor {
+                price_floor: self.price_floor,
+                s_0: self.s_0,
+            });
+        }
+        if !(0.0..=1.0).contains(&self.queue_position) {
+            return Err(SimError::InvalidQueuePosition(self.queue_position));
+        }
+        if let InventoryMark::Conservative { haircut } = self.inventory_mark
+            && !(haircut.is_finite() && haircut >= 0.0)
+        {
+            return Err(SimError::NegativeMarkHaircut(haircut));
+        }
+        if !(self.observation_noise.is_finite() && self.observation_noise >= 0.0) {
+            return Err(SimError::InvalidObservationNoise(self.observation_noise));
+        }
+        if !self.carry_rate.is_finite() {
+            return Err(SimError::NonFiniteCarryRate(self.carry_rate));
+        }
+        Ok(())
+    }
+
+    /// Checks that `intensity_model` fills the opening quotes of `agent_params`, the optimal
+    /// half-spread either side of the mid after the half-spread bounds, with probability
+    /// `lambda * dt` at most one per step. Above that the stochastic fill draw clips and the
+    /// simulated fill rate no longer follows the model. Deterministic crossing fills never draw,
+    /// so they always pass.
+    pub fn validate_fill_rate<M: IntensityModel + ?Sized>(
+        &self,
+        agent_params: &Parameters,
+        intensity_model: &M,
+    ) -> Result<(), SimError> {
+        if self.fill_model == FillModel::CrossDeterministic {
+            return Ok(());
+        }
+        let half_spread = self
+            .clamp_half_spread(optimal_spread(agent_params, 0.0) / 2.0)
+            .max(0.0);
+        let (bid, ask) = reference_fill_probs(intensity_model, half_spread, self.dt);
+        let probability = bid.max(ask);
+        if probability > 1.0 {
+            return Err(SimError::FillProbabilityAboveOne { probability });
+        }
+        Ok(())
+    }
+
+    /// Clamps `half_spread` into `[min_half_spread, max_half_spread]`, leaving an unset side open.
+    pub fn clamp_half_spread(&self, half_spread: f64) -> f64 {
+        let half_spread = self
+            .min_half_spread
+            .map_or(half_spread, |min| half_spread.max(min));
+        self.max_half_spread
+            .map_or(half_spread, |max| half_spread.min(max))
+    }
+
+    /// Per-unit distance from the mid at which `inventory_mark` values inventory.
+    pub fn mark_haircut(&self) -> f64 {
+        match self.inventory_mark {
+            InventoryMark::Mid => 0.0,
+            InventoryMark::Conservative { haircut } => haircut,
+            InventoryMark::Exit => self.liquidation_cost_per_unit.unwrap_or(0.0),
+        }
+    }
+
+    /// Value of inventory `q` at mid `s` under `inventory_mark`.
+    pub fn mark_inventory(&self, q: f64, s: f64) -> f64 {
+        q * s - q.abs() * self.mark_haircut()
+    }
+}
+
+/// Fluent constructor for `SimConfig`.
+///
+/// Defaults: `dt = 0.005`, `num_steps = 600`, `s_0 = 100`, zero drift and latency, a staleness
+/// threshold of one cent, unit order size, quotes refreshed every step, an
+/// unbounded half-spread, a flat starting book, a one-cent price floor, the front of the queue,
+/// and every optional model extension disabled.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SimConfigBuilder {
+    config: SimConfig,
+}
+
+impl SimConfigBuilder {
+    pub fn dt(mut self, dt: f64) -> Self {
+        self.config.dt = dt;
+        self
+    }
+
+    pub fn num_steps(mut self, num_steps: usize) -> Self {
+        self.config.num_steps = num_steps;
+        self
+    }
+
+    pub fn s_0(mut self, s_0: f64) -> Self {
+        self.config.s_0 = s_0;
+        self
+    }
+
+    pub fn drift(mut self, drift: f64) -> Self {
+        self.config.drift = drift;
+        self
+    }
+
+    pub fn latency_steps(mut self, latency_steps: usize) -> Self {
+        self.config.latency_steps = latency_steps;
+        self
+    }
+
+    pub fn staleness_threshold(mut self, staleness_threshold: f64) -> Self {
+        self.config.staleness_threshold = staleness_threshold;
+        self
+    }
+
+    pub fn size_distribution(mut self, dist: Arc<EmpiricalSizeDistribution>) -> Self {
+        self.config.size_distribution = Some(dist);
+        self
+    }
+
+    pub fn order_size(mut self, order_size: f64) -> Self {
+        self.config.order_size = order_size;
+        self
+    }
+
+    pub fn hawkes(mut self, hawkes: HawkesConfig) -> Self {
+        self.config.hawkes = Some(hawkes);
+        self
+    }
+
+    pub fn regimes(mut self, regimes: RegimeSwitching) -> Self {
+        self.config.regimes = Some(regimes);
+        self
+    }
+
+    pub fn adverse_selection(mut self, adverse_selection: f64) -> Self {
+        self.config.adverse_selection = adverse_selection;
+        self
+    }
+
+    pub fn liquidity(mut self, liquidity: StochasticLiquidity) -> Self {
+        self.config.liquidity = Some(liquidity);
+        self
+    }
+
+    pub fn max_drawdown_stop(mut self, max_drawdown: f64) -> Self {
+        self.config.max_drawdown_stop = Some(max_drawdown);
+        self
+    }
+
+    pub fn liquidation_cost_per_unit(mut self, cost: f64) -> Self {
+        self.config.liquidation_cost_per_unit = Some(cost);
+        self
+    }
+
+    pub fn price_process(mut self, price_process: PriceProcess) -> Self {
+        self.config.price_process = price_process;
+        self
+    }
+
+    pub fn fill_model(mut self, fill_model: FillModel) -> Self {
+        self.config.fill_model = fill_model;
+        self
+    }
+
+    pub fn simultaneous_fills(mut self, simultaneous_fills: SimultaneousFills) -> Self {
+        self.config.simultaneous_fills = simultaneous_fills;
+        self
+    }
+
+    pub fn inventory_mark(mut self, inventory_mark: InventoryMark) -> Self {
+        self.config.inventory_mark = inventory_mark;
+        self
+    }
+
+    pub fn requote_interval(mut self, requote_interval: usize) -> Self {
+        self.config.requote_interval = requote_interval;
+        self
+    }
+
+    pub fn min_half_spread(mut self, min_half_spread: f64) -> Self {
+        self.config.min_half_spread = Some(min_half_spread);
+        self
+    }
+
+    pub fn max_half_spread(mut self, max_half_spread: f64) -> Self {
+        self.config.max_half_spread = Some(max_half_spread);
+        self
+    }
+
+    pub fn initial_inventory(mut self, initial_inventory: f64) -> Self {
+        self.config.initial_inventory = initial_inventory;
+        self
+    }
+
+    pub fn price_floor(mut self, price_floor: f64) -> Self {
+        self.config.price_floor = price_floor;
+        self
+    }
+
+    pub fn queue_position(mut self, queue_position: f64) -> Self {
+        self.config.queue_position = queue_position;
+        self
+    }
+
+    pub fn observation_noise(mut self, observation_noise: f64) -> Self {
+        self.config.observation_noise = observation_noise;
+        self
+    }
+
+    pub fn carry_rate(mut self, carry_rate: f64) -> Self {
+        self.config.carry_rate = carry_rate;
+        self
+    }
+
+    pub fn signal(mut self, signal: QuoteSignal) -> Self {
+        self.config.signal = Some(signal);
+        self
+    }
+
+    /// Returns the configuration if it passes `SimConfig::validate`.
+    pub fn build(self) -> Result<SimConfig, SimError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Error returned when a `SimConfig` cannot produce a meaningful trajectory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimError {
+    /// `dt` must be strictly positive (NaN is rejected too).
+    NonPositiveDt(f64),
+    /// `num_steps` must be at least one.
+    NoSteps,
+    /// `s_0` must be finite and strictly positive.
+    NonPositiveS0(f64),
+    /// Quotes would never leave the latency queue before the horizon ends.
+    LatencyTooLong {
+        latency_steps: usize,
+        num_steps: usize,
+    },
+    /// `order_size` must be finite and strictly positive.
+    NonPositiveOrderSize(f64),
+    /// `requote_interval` must be at least one.
+    ZeroRequoteInterval,
+    /// The half-spread bounds must be finite, non-negative (the maximum positive), and ordered.
+    InvalidHalfSpreadBounds { min: Option<f64>, max: Option<f64> },
+    /// `initial_inventory` must be finite.
+    NonFiniteInitialInventory(f64),
+    /// `price_floor` must be strictly positive and below `s_0`.
+    InvalidPriceFloor { price_floor: f64, s_0: f64 },
+    /// `queue_position` must lie in `[0, 1]`.
+    InvalidQueuePosition(f64),
+    /// The `InventoryMark::Conservative` haircut must be finite and non-negative.
+    NegativeMarkHaircut(f64),
+    /// `observation_noise` must be finite and non-negative.
+    InvalidObservationNoise(f64),
+    /// `carry_rate` must be finite.
+    NonFiniteCarryRate(f64),
+    /// The intensity at the opening quotes times `dt` exceeds one, so fill probabilities would be
+    /// clipped.
+    FillProbabilityAboveOne { probability: f64 },
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimError::NonPositiveDt(dt) => write!(f, "`dt` must be > 0, got {dt}"),
+            SimError::NoSteps => write!(f, "`num_steps` must be > 0"),
+            SimError::NonPositiveS0(s_0) => write!(f, "`s_0` must be > 0, got {s_0}"),
+            SimError::LatencyTooLong {
+                latency_steps,
+                num_steps,
+            } => write!(
+                f,
+                "`latency_steps` ({latency_steps}) must be less than `num_steps` ({num_steps})"
+            ),
+            SimError::NonPositiveOrderSize(size) => {
+                write!(f, "`order_size` must be > 0, got {size}")
+            }
+            SimError::ZeroRequoteInterval => write!(f, "`requote_interval` must be > 0"),
+            SimError::InvalidHalfSpreadBounds { min, max } => write!(
+                f,
+                "half-spread bounds must satisfy 0 <= min <= max and max > 0, got min {min:?}, \
+                 max {max:?}"
+            ),
+            SimError::NonFiniteInitialInventory(q) => {
+                write!(f, "`initial_inventory` must be finite, got {q}")
+            }
+            SimError::InvalidPriceFloor { price_floor, s_0 } => write!(
+                f,
+                "`price_floor` must satisfy 0 < price_floor < s_0 ({s_0}), got {price_floor}"
+            ),
+            SimError::InvalidQueuePosition(position) => {
+                write!(f, "`queue_position` must lie in [0, 1], got {position}")
+            }
+            SimError::NegativeMarkHaircut(haircut) => {
+                write!(f, "the inventory mark haircut must be >= 0, got {haircut}")
+            }
+            SimError::InvalidObservationNoise(noise) => {
+                write!(
+                    f,
+                    "`observation_noise` must be finite and >= 0, got {noise}"
+                )
+            }
+            SimError::NonFiniteCarryRate(rate) => {
+                write!(f, "`carry_rate` must be finite, got {rate}")
+            }
+            SimError::FillProbabilityAboveOne { probability } => write!(
+                f,
+                "fill probability per step at the opening quotes must be <= 1, got {probability}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SimError {}
+
+/// How resting quotes are filled once the mid price has moved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum FillModel {
+    /// Poisson fills with probability `intensity(delta) * dt` per step.
+    #[default]
+    Stochastic,
+    /// A bid fills exactly when the new mid is at or below it, and an ask when the new mid is at
+    /// or above it. No randomness, which makes single paths reproducible for debugging.
+    CrossDeterministic,
+}
+
+/// Price at which held inventory is valued when marking wealth to market.
+///
+/// Every mode marks within a fixed per-unit distance of the mid, longs below it and shorts above
+/// it, so it only changes how much of the position's value is counted, not when.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum InventoryMark {
+    /// At the mid, which overstates what the position would fetch if it had to be sold.
+    #[default]
+    Mid,
+    /// Long inventory at `mid - haircut` and shorts at `mid + haircut`.
+    Conservative { haircut: f64 },
+    /// At the price the position could be closed at: the mid less
+    /// `SimConfig::liquidation_cost_per_unit` per unit, the cost the drawdown kill-switch pays to
+    /// flatten. The same as `Mid` without a liquidation cost.
+    Exit,
+}
+
+/// What happens when the bid and the ask are both hit in the same step.
+///
+/// Filling both books the whole spread with no change in inventory. In an event-ordered market
+/// the two arrivals come one after the other and the first can move the price away from the
+/// second, so at small spreads or large `dt` `BothAllowed` overstates PnL.
+/// `SimResult::simultaneous_fill_steps` counts the steps where the choice mattered.
+///
+/// Where one side has to come first, it is the bid with probability
+/// `lambda_bid / (lambda_bid + lambda_ask)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SimultaneousFills {
+    /// Both quotes fill at their resting prices.
+    #[default]
+    BothAllowed,
+    /// Only the side that comes first fills.
+    FirstOnly,
+    /// The first side fills and moves the mid by `SimConfig::adverse_selection` per unit, as it
+    /// would the next step's return. The second side then fills only if it still would against
+    /// that mid. Without adverse selection this matches `BothAllowed`.
+    Sequential,
+}
+
+/// Volatility dynamics of the simulated mid price.
+///
+/// The strategy always quotes with its static `sigma`; under `Heston` that is only a forecast of
+/// the realised volatility.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
+pub enum PriceProcess {
+    /// Constant volatility: the agent's `sigma`, or the active regime's under regime switching.
+    #[default]
+    Gbm,
+    /// Instantaneous variance `v` follows the CIR process
+    /// `dv = kappa * (theta - v) dt + xi * sqrt(v) dW_v`, starting at `v0`, with `corr(dW, dW_v) =
+    /// rho`. The price uses `sqrt(v)` as its volatility, overriding any regime `sigma`.
+    Heston {
+        kappa: f64,
+        theta: f64,
+        xi: f64,
+        rho: f64,
+        v0: f64,
+    },
+    /// Historical mid prices replayed verbatim: step `i` sees `prices[i]`, and the path holds its
+    /// last value after the series ends. `num_steps` is capped at `prices.len()`, and `s_0`,
+    /// drift, and adverse selection are ignored. Fills stay stochastic.
+    Replay { prices: Arc<[f64]> },
+}
+
+/// Which fills excite a side's arrival rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum HawkesExcitation {
+    /// A fill excites only the side it occurred on.
+    SameSide,
+    /// A fill on either side excites both sides.
+    BothSides,
+}
+
+/// Self-exciting order arrivals: each side's rate is `baseline(delta) * (1 + state)`, where the
+/// state jumps by `alpha` on every exciting fill and decays as `exp(-beta * dt)` per step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HawkesConfig {
+    pub alpha: f64,
+    pub beta: f64,
+    pub excitation: HawkesExcitation,
+}
+
+/// State of the two-state regime-switching price process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Regime {
+    #[default]
+    Calm,
+    Stressed,
+}
+
+/// Two-state Markov switching of the price dynamics.
+///
+/// Each trajectory starts calm. While set, the active regime's `sigma` and `drift` drive the mid
+/// price instead of the agent's `sigma` and `SimConfig::drift`. With `oracle` the strategy quotes
+/// with the active regime's `sigma` and, under `QuotingModel::DriftAdjusted`, its `drift`;
+/// otherwise it keeps using its own `sigma` and `SimConfig::drift`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RegimeSwitching {
+    pub calm_sigma: f64,
+    pub calm_drift: f64,
+    pub stressed_sigma: f64,
+    pub stressed_drift: f64,
+    pub p_calm_to_stressed: f64, // Per-step transition probability
+    pub p_stressed_to_calm: f64, // Per-step transition probability
+    pub oracle: bool,
+}
+
+impl RegimeSwitching {
+    /// `(sigma, drift)` of the price process in `regime`.
+    pub fn dynamics(&self, regime: Regime) -> (f64, f64) {
+        match regime {
+            Regime::Calm => (self.calm_sigma, self.calm_drift),
+            Regime::Stressed => (self.stressed_sigma, self.stressed_drift),
+        }
+    }
+}
+
+/// Dynamics of the market's liquidity multiplier `m`, which scales the intensity model's `k`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename_all = "snake_case")
+)]
+pub enum LiquidityProcess {
+    /// `ln m` mean-reverts to zero: `d ln m = -reversion * ln m * dt + vol * dW`.
+    MeanReverting { reversion: f64, vol: f64 },
+    /// `m` switches between 1 (normal) and `dry_multiplier` with per-step probabilities.
+    TwoState {
+        dry_multiplier: f64,
+        p_dry: f64,
+        p_recover: f64,
+    },
+}
+
+impl LiquidityProcess {
+    /// Advances the multiplier by one step; each call consumes exactly one draw. A normal draw is
+    /// multiplied by `shock_sign`.
+    fn next<R: Rng + ?Sized>(&self, m: f64, dt: f64, shock_sign: f64, rng: &mut R) -> f64 {
+        match *self {
+            LiquidityProcess::MeanReverting { reversion, vol } => {
+                let z = shock_sign * rng.sample::<f64, _>(StandardNormal);
+                let x = m.ln();
+                (x - reversion * x * dt + vol * dt.sqrt() * z).exp()
+            }
+            LiquidityProcess::TwoState {
+                dry_multiplier,
+                p_dry,
+                p_recover,
+            } => {
+                let u: f64 = rng.random();
+                let dry = m != 1.0;
+                match (dry, u) {
+                    (false, u) if u < p_dry => dry_multiplier,
+                    (true, u) if u < p_recover => 1.0,
+                    _ => m,
+                }
+            }
+        }
+    }
+}
+
+/// Time-varying market liquidity, independent of the `k` the strategy assumes.
+///
+/// Each trajectory starts at `m = 1`. The market fills a quote at distance `delta` with rate
+/// `intensity(m * delta)`, which for `ExponentialIntensity` is exactly `k -> m * k`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StochasticLiquidity {
+    pub process: LiquidityProcess,
+    pub scale_a: bool, // Also divide arrival rates by `m`, so thin books see fewer orders
+    /// Refit the strategy's `k` and `a` with `ExponentialIntensity::fit` on its own quotes and fills
+    /// every this many steps. A failed fit keeps the previous values.
+    pub recalibration_window: Option<usize>,
+}
+
+/// Side of the agent's book that traded: a `Bid` fill buys, an `Ask` fill sells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+/// One fill of the agent's quotes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FillEvent {
+    pub step: usize, // Index of the `StepRecord` whose quotes traded
+    pub time: f64,   // End of that step, `trajectory[step].time + dt`
+    pub side: Side,
+    pub price: f64, // Quote that traded
+    pub size: f64,
+    pub mid_at_fill: f64, // Mid after the step's price move, which the fill was decided against
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StepRecord {
     pub time: f64,
     pub mid_price: f64,
-    pub inventory: i32,
+    pub inventory: f64,
     pub cash: f64,
     pub wealth: f64,
-    pub bid_price: f64,
+    #[cfg_attr(feature = "serde", serde(with = "nan_as_null"))]
+    pub bid_price: f64, // NaN once the kill-switch has halted quoting
+    #[cfg_attr(feature = "serde", serde(with = "nan_as_null"))]
     pub ask_price: f64,
+    /// Reservation price the strategy quoted around this step, before any latency or requote
+    /// hold delayed the quotes reaching the market. NaN once halted.
+    #[cfg_attr(feature = "serde", serde(default, with = "nan_as_null"))]
+    pub reservation_price: f64,
+    pub regime: Regime,  // Regime in force over [time, time + dt)
+    pub liquidity: f64,  // Market `k` multiplier over [time, time + dt)
+    pub volatility: f64, // Volatility driving the price over [time, time + dt)
 }
 
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SimResult {
     pub trajectory: Vec<StepRecord>,
     pub final_pnl: f64,
+    pub running_penalty: f64, // Accumulated phi * q^2 * dt (zero unless RunningPenalty)
+    /// Funding paid on held inventory under `SimConfig::carry_rate`, negative when it was
+    /// received. Already taken out of the cash, and counted in `inventory_pnl`.
+    pub carry_paid: f64,
+    pub quote_staleness_mean: f64,
+    pub quote_staleness_exceed_frac: f64,
+    /// Squared coefficient of variation of the times between fills: about 1 for Poisson arrivals,
+    /// above 1 when fills cluster. NaN when fewer than two inter-fill intervals were observed.
+    #[cfg_attr(feature = "serde", serde(with = "nan_as_null"))]
+    pub fill_time_dispersion: f64,
+    /// Fraction of steps spent in `Regime::Stressed` (zero without regime switching).
+    pub stressed_fraction: f64,
+    /// Wealth change over calm steps; `calm_pnl + stressed_pnl == final_pnl`.
+    pub calm_pnl: f64,
+    pub stressed_pnl: f64,
+    /// Edge captured against the mid at fill time, `(mid_at_fill - price) * size` for a bid fill
+    /// and `(price - mid_at_fill) * size` for an ask fill.
+    pub spread_pnl: f64,
+    /// Sum over steps of the inventory carried into the step times the mid's move, less any
+    /// kill-switch liquidation cost and `carry_paid`. `spread_pnl + inventory_pnl == final_pnl` up to rounding.
+    pub inventory_pnl: f64,
+    pub bid_fills: usize, // Steps on which the bid was hit
+    pub ask_fills: usize, // Steps on which the ask was lifted
+    /// Steps on which `optimal_spread` was zero, negative, or not finite, which takes parameters
+    /// outside the model's domain. Those quotes were widened to
+    /// `SimConfig::min_half_spread` each side, or to zero without one, so the book never crossed.
+    pub crossed_quote_steps: usize,
+    pub fills: Vec<FillEvent>, // Every fill in time order, bid before ask within a step
+    pub mean_liquidity: f64,   // Mean market `k` multiplier over the trajectory
+    pub strategy_k: f64,       // `k` the strategy quoted with at the end (after any recalibration)
+    /// Spread a zero-inventory reference quoter would have captured on this run's fill draws. Its
+    /// mean is known from `expected_spread_capture`, so it serves as a control variate for PnL.
+    pub spread_capture_control: f64,
+    /// Step at which the drawdown kill-switch flattened the book; later rows are flat and unquoted.
+    pub stopped_at: Option<usize>,
+    /// Steps on which both quotes were hit, before `SimConfig::simultaneous_fills` was applied.
+    pub simultaneous_fill_steps: usize,
+    /// Steps on which the price update was caught by `SimConfig::price_floor`. Non-zero means the
+    /// price process left its domain and the run should be read with care.
+    pub price_floor_hits: usize,
+    /// The books at the end of the run: `final_pnl` is `final_cash` plus `final_inventory` marked
+    /// at `final_mid` under `SimConfig::inventory_mark`.
+    pub final_cash: f64,
+    pub final_inventory: f64,
+    pub final_mid: f64,
+    /// Cash the kill-switch received for flattening the book, net of the liquidation cost; zero
+    /// if it never fired.
+    pub liquidation_cash: f64,
+}
+
+impl SimResult {
+    /// Sum of `price * size` over every fill.
+    pub fn traded_notional(&self) -> f64 {
+        self.fills.iter().map(|fill| fill.price * fill.size).sum()
+    }
+
+    /// Net cash the fills moved: received for asks less paid for bids.
+    pub fn fill_cash_flow(&self) -> f64 {
+        self.fills
+            .iter()
+            .map(|fill| match fill.side {
+                Side::Bid => -fill.price * fill.size,
+                Side::Ask => fill.price * fill.size,
+            })
+            .sum()
+    }
+
+    /// Net inventory the fills added: bought on the bid less sold on the ask.
+    pub fn filled_inventory(&self) -> f64 {
+        self.fills
+            .iter()
+            .map(|fill| match fill.side {
+                Side::Bid => fill.size,
+                Side::Ask => -fill.size,
+            })
+            .sum()
+    }
+
+    /// Average distance `|price - mid_at_fill|` between a fill and the mid, per fill and
+    /// regardless of size. Unlike `spread_pnl` it ignores the sign, so it measures how far out the
+    /// quotes were rather than what they earned. Zero without fills.
+    pub fn mean_spread_capture(&self) -> f64 {
+        if self.fills.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = self
+            .fills
+            .iter()
+            .map(|fill| (fill.price - fill.mid_at_fill).abs())
+            .sum();
+        total / self.fills.len() as f64
+    }
+}
+
+#[cfg(feature = "serde")]
+impl SimResult {
+    /// Writes the run as JSON Lines: one `StepRecord` object per line, then a final
+    /// `{"summary": {...}}` line with the remaining `SimResult` fields plus the `params` and
+    /// `config` that produced the run, so the file is self-describing.
+    ///
+    /// Each line is a complete JSON value, so large trajectories can be stream-parsed.
+    pub fn to_json_lines<W: std::io::Write>(
+        &self,
+        params: &Parameters,
+        config: &SimConfig,
+        mut w: W,
+    ) -> serde_json::Result<()> {
+        for step in &self.trajectory {
+            serde_json::to_writer(&mut w, step)?;
+            w.write_all(b"\n").map_err(serde_json::Error::io)?;
+        }
+
+        let summary = SimResult {
+            trajectory: Vec::new(),
+            ..self.clone()
+        };
+        let serde_json::Value::Object(mut fields) = serde_json::to_value(summary)? else {
+            unreachable!("SimResult serialises as a JSON object");
+        };
+        fields.remove("trajectory");
+        fields.insert("params".into(), serde_json::to_value(params)?);
+        fields.insert("config".into(), serde_json::to_value(config)?);
+
+        serde_json::to_writer(&mut w, &serde_json::json!({ "summary": fields }))?;
+        w.write_all(b"\n").map_err(serde_json::Error::io)
+    }
+}
+
+/// Writes NaN as `null` and reads `null` back as NaN, for fields where NaN means "undefined".
+#[cfg(feature = "serde")]
+pub(crate) mod nan_as_null {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if value.is_nan() {
+            serializer.serialize_none()
+        } else {
+            serializer.serialize_some(value)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::NAN))
+    }
+}
+
+/// Buffers a run is simulated into: the result with its step records and fills, the latency
+/// queue, and the recalibration samples.
+///
+/// `run_trajectory_into` clears and refills them, so a loop over many runs allocates once instead
+/// of once per run. Each run's output is identical to `run_trajectory_with_rng` on the same RNG.
+#[derive(Debug, Default)]
+pub struct TrajectoryWorkspace {
+    result: SimResult,
+    quote_queue: VecDeque<(f64, f64)>,
+    fit_samples: Vec<(f64, bool)>,
+}
+
+impl TrajectoryWorkspace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The most recent run simulated into the workspace; empty before the first.
+    pub fn result(&self) -> &SimResult {
+        &self.result
+    }
+
+    pub fn into_result(self) -> SimResult {
+        self.result
+    }
+}
+
+/// Generic over the intensity model so concrete models are inlined into the step loop; a
+/// `&dyn IntensityModel` still works through the `?Sized` bound.
+pub fn run_trajectory<M: IntensityModel + ?Sized>(
+    agent_params: &Parameters,
+    config: &SimConfig,
+    intensity_model: &M,
+) -> SimResult {
+    run_trajectory_with_rng(agent_params, config, intensity_model, &mut unseeded_rng())
+}
+
+/// RNG for a run without a seed, from OS entropy through the thread RNG.
+///
+/// Without the `os-rng` feature, as on `wasm32-unknown-unknown` where there is no entropy source,
+/// successive calls are instead seeded 0, 1, 2, ... from process start, so unseeded runs differ
+/// from each other but repeat from one process to the next.
+pub(crate) fn unseeded_rng() -> StdRng {
+    #[cfg(feature = "os-rng")]
+    {
+        StdRng::from_rng(&mut rand::rng())
+    }
+    #[cfg(not(feature = "os-rng"))]
+    {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        static NEXT_SEED: AtomicU64 = AtomicU64::new(0);
+        StdRng::seed_from_u64(NEXT_SEED.fetch_add(1, Ordering::Relaxed))
+    }
 }
 
-pub fn run_trajectory(
+/// Runs a trajectory drawing all randomness from `rng`.
+///
+/// Every step consumes exactly two normal draws (the observation error, even at zero noise, and
+/// the price shock) and two uniform draws (plus one per side for the order size when a multi-size
+/// distribution is configured, one normal for the variance under Heston dynamics, one for the
+/// regime transition when regime switching is enabled, and one for the liquidity process when it
+/// is enabled), so two runs seeded identically see the same price path even when the agent
+/// parameters or the observation noise differ (common random numbers).
+pub fn run_trajectory_with_rng<M: IntensityModel + ?Sized, R: Rng + ?Sized>(
     agent_params: &Parameters,
     config: &SimConfig,
-    intensity_model: &dyn IntensityModel,
+    intensity_model: &M,
+    rng: &mut R,
 ) -> SimResult {
-    let mut rng = rand::rng();
+    let mut workspace = TrajectoryWorkspace::new();
+    simulate(
+        &mut workspace,
+        agent_params,
+        config,
+        intensity_model,
+        rng,
+        1.0,
+    );
+    workspace.into_result()
+}
+
+/// `run_trajectory_with_rng` into the reused buffers of `workspace`, overwriting its previous run.
+pub fn run_trajectory_into<'w, M: IntensityModel + ?Sized, R: Rng + ?Sized>(
+    workspace: &'w mut TrajectoryWorkspace,
+    agent_params: &Parameters,
+    config: &SimConfig,
+    intensity_model: &M,
+    rng: &mut R,
+) -> &'w SimResult {
+    simulate(workspace, agent_params, config, intensity_model, rng, 1.0);
+    &workspace.result
+}
+
+/// Runs a trajectory and its antithetic mirror from one seed drawn from `rng`.
+///
+/// The mirror negates every standard normal draw (price, Heston variance, and mean-reverting
+/// liquidity shocks), so its price path reflects the original's. The uniform draws for fills,
+/// regime transitions, and two-state liquidity are reused unchanged rather than mirrored: fill
+/// decisions already depend on the mirrored price through the quote distances, and keeping them
+/// common leaves the price shock as the only difference within the pair.
+pub fn run_antithetic_pair<M: IntensityModel + ?Sized, R: Rng + ?Sized>(
+    agent_params: &Parameters,
+    config: &SimConfig,
+    intensity_model: &M,
+    rng: &mut R,
+) -> (SimResult, SimResult) {
+    let (mut run, mut mirror) = (TrajectoryWorkspace::new(), TrajectoryWorkspace::new());
+    run_antithetic_pair_into(
+        &mut run,
+        &mut mirror,
+        agent_params,
+        config,
+        intensity_model,
+        rng,
+    );
+    (run.into_result(), mirror.into_result())
+}
+
+/// `run_antithetic_pair` into the reused buffers of `run` and `mirror`.
+pub fn run_antithetic_pair_into<'w, M: IntensityModel + ?Sized, R: Rng + ?Sized>(
+    run: &'w mut TrajectoryWorkspace,
+    mirror: &'w mut TrajectoryWorkspace,
+    agent_params: &Parameters,
+    config: &SimConfig,
+    intensity_model: &M,
+    rng: &mut R,
+) -> (&'w SimResult, &'w SimResult) {
+    let seed: u64 = rng.random();
+    for (workspace, shock_sign) in [(&mut *run, 1.0), (&mut *mirror, -1.0)] {
+        let mut pair_rng = StdRng::seed_from_u64(seed);
+        simulate(
+            workspace,
+            agent_params,
+            config,
+            intensity_model,
+            &mut pair_rng,
+            shock_sign,
+        );
+    }
+    (&run.result, &mirror.result)
+}
+
+/// The simulation loop, writing the run into `workspace`; every standard normal draw is
+/// multiplied by `shock_sign`.
+fn simulate<M: IntensityModel + ?Sized, R: Rng + ?Sized>(
+    workspace: &mut TrajectoryWorkspace,
+    agent_params: &Parameters,
+    config: &SimConfig,
+    intensity_model: &M,
+    rng: &mut R,
+    shock_sign: f64,
+) {
+    let TrajectoryWorkspace {
+        result,
+        quote_queue,
+        fit_samples,
+    } = workspace;
+    let replay = match &config.price_process {
+        PriceProcess::Replay { prices } => Some(prices),
+        _ => None,
+    };
+    let num_steps = effective_num_steps(config);
 
     let mut t = 0.0;
-    let mut s = config.s_0;
-    let mut q = 0;
-    let mut w = 0.0;
+    let mut s = replay
+        .and_then(|prices| prices.first().copied())
+        .unwrap_or(config.s_0);
+    // An inherited position is booked at its opening mark, so wealth starts at zero.
+    let mut q = config.initial_inventory;
+    let mut w = -config.mark_inventory(q, s);
+    let opening_cash = w;
+    let mut liquidation_cash = 0.0;
+    let mut running_penalty = 0.0;
+    let mut carry_paid = 0.0;
+    let phi = agent_params.quoting_model.running_penalty_rate();
+    let mut staleness_sum = 0.0;
+    let mut stale_steps = 0usize;
+
+    // Hawkes excitation per side, and running moments of inter-fill times.
+    let (mut hawkes_bid, mut hawkes_ask) = (0.0, 0.0);
+    let hawkes_decay = config.hawkes.map_or(1.0, |h| (-h.beta * config.dt).exp());
+    let mut last_fill_time: Option<f64> = None;
+    let (mut gap_count, mut gap_sum, mut gap_sq_sum) = (0usize, 0.0, 0.0);
+
+    let mut regime = Regime::Calm;
+    let mut stressed_steps = 0usize;
+    let (mut calm_pnl, mut stressed_pnl) = (0.0, 0.0);
+    let (mut spread_pnl, mut inventory_pnl) = (0.0, 0.0);
+    let mut spread_capture_control = 0.0;
+    let (mut bid_fills, mut ask_fills) = (0usize, 0usize);
+    let mut crossed_quote_steps = 0usize;
+    let mut price_floor_hits = 0usize;
+    let mut simultaneous_fill_steps = 0usize;
+    let mut fills = std::mem::take(&mut result.fills);
+    fills.clear();
+    // Return impact of the previous step's fills under adverse selection.
+    let mut adverse_move = 0.0;
+
+    let mut liquidity = 1.0;
+    let mut liquidity_sum = 0.0;
+    let (mut strategy_k, mut strategy_a) = (agent_params.k, agent_params.a);
+    let recalibration_window = config
+        .liquidity
+        .and_then(|liq| liq.recalibration_window)
+        .filter(|&window| window > 0);
+    fit_samples.clear();
+
+    let mut variance = match config.price_process {
+        PriceProcess::Heston { v0, .. } => v0.max(0.0),
+        PriceProcess::Gbm | PriceProcess::Replay { .. } => 0.0,
+    };
 
-    let mut trajectory = Vec::with_capacity(config.num_steps);
+    let mut peak_wealth: f64 = 0.0;
+    let mut stopped_at = None;
+
+    let mut trajectory = std::mem::take(&mut result.trajectory);
+    trajectory.clear();
+    trajectory.reserve(num_steps);
 
     // Low-level latency queue: stores (ask, bid) quotes sent by agent
     // These quotes will be available to the 'Market' after Latency steps.
-    let mut quote_queue: VecDeque<(f64, f64)> = VecDeque::new();
-
-    for _ in 0..config.num_steps {
-        let r = reservation_price(agent_params, s, q, t);
-        let spread = optimal_spread(agent_params, t);
-        let (ask, bid) = quotes(r, spread);
+    quote_queue.clear();
+    // (ask, bid) from the most recent refresh, see `SimConfig::requote_interval`.
+    let mut sent_quotes = (f64::NAN, f64::NAN);
 
-        quote_queue.push_back((ask, bid));
+    for step in 0..num_steps {
+        let (sigma, drift) = match &config.regimes {
+            Some(regimes) => regimes.dynamics(regime),
+            None => (agent_params.sigma, config.drift),
+        };
+        let price_sigma = match config.price_process {
+            PriceProcess::Gbm | PriceProcess::Replay { .. } => sigma,
+            PriceProcess::Heston { .. } => variance.sqrt(),
+        };
+        let oracle = matches!(&config.regimes, Some(regimes) if regimes.oracle);
+        let quoting_params = Parameters {
+            sigma: if oracle { sigma } else { agent_params.sigma },
+            k: strategy_k,
+            a: strategy_a,
+            ..*agent_params
+        };
+        let quoting_drift = if oracle { drift } else { config.drift };
 
-        // 2. Market State Determination (Latency)
-        // If we have more quotes than latency, the one at the front (oldest) is what reached the market L steps ago
-        // and is now active.
-        let (effective_ask, effective_bid) = if config.latency_steps == 0 {
-            (ask, bid)
+        let halted = stopped_at.is_some();
+        // The mid as the strategy sees it. The draw happens even when halted, keeping the RNG
+        // stream aligned.
+        // It is drawn every step, scaled by a zero noise, so runs with and without noise share a
+        // price path.
+        let observed =
+            s + config.observation_noise * shock_sign * rng.sample::<f64, _>(StandardNormal);
+        let (effective_ask, effective_bid, reservation) = if halted {
+            // The kill-switch has fired: no quotes rest in the market.
+            (f64::NAN, f64::NAN, f64::NAN)
         } else {
-            // Buffer needs to hold `latency_steps + 1` items to peek at T-L.
-            // Example: Latency=1. T=0 push Q0. Len=1. Effective=Q0 (optimistic start) or None?
-            // Let's assume optimistic start for simplicity: current quotes apply until buffer fills.
-            if quote_queue.len() > config.latency_steps {
-                quote_queue.pop_front().unwrap()
+            let decision = decide_quotes(&quoting_params, quoting_drift, observed, q, t);
+            // As `reservation_price_with_signal`.
+            let r = match &config.signal {
+                Some(signal) => {
+                    decision.reservation_price + signal.weight * (signal.source)(t, observed)
+                }
+                None => decision.reservation_price,
+            };
+            // For parameters outside the model's domain the formula spread can reach zero, go
+            // negative, or stop being finite. Quoting it would cross the book, so after the
+            // configured bounds the quotes collapse onto the reservation price at worst.
+            let spread = decision.spread;
+            if !(spread > 0.0 && spread.is_finite()) {
+                crossed_quote_steps += 1;
+            }
+            let half_spread = config
+                .clamp_half_spread(quotable_spread(spread) / 2.0)
+                .max(0.0);
+            // A long skew on a low mid can push the bid below zero. Both quotes then move up
+            // together until the bid sits at zero, keeping the spread within its bounds.
+            let (ask, bid) = quotes(r, 2.0 * half_spread);
+            let lift = (-bid).max(0.0);
+            let (ask, bid) = (ask + lift, bid + lift);
+
+            // Between refreshes the last quotes sent are re-sent unchanged.
+            if step % config.requote_interval == 0 {
+                sent_quotes = (ask, bid);
+            }
+            let (sent_ask, sent_bid) = sent_quotes;
+            quote_queue.push_back(sent_quotes);
+
+            // 2. Market State Determination (Latency)
+            // Once the queue holds more than `latency_steps` quotes, the front (oldest) one is what
+            // reached the market L steps ago and is now active. Until the buffer fills we assume an
+            // optimistic start where the current quotes apply. With zero latency the quote just
+            // pushed is popped straight back, so the queue never grows.
+            let (effective_ask, effective_bid) = if quote_queue.len() > config.latency_steps {
+                quote_queue.pop_front().unwrap_or(sent_quotes)
             } else {
-                *quote_queue.back().unwrap()
+                (sent_ask, sent_bid)
+            };
+
+            // Economic staleness: distance between the active quotes and what the strategy would
+            // quote right now, taking the worse of the two sides.
+            let staleness = (effective_bid - bid).abs().max((effective_ask - ask).abs());
+            staleness_sum += staleness;
+            if staleness > config.staleness_threshold {
+                stale_steps += 1;
             }
+
+            (effective_ask, effective_bid, r)
         };
 
-        let wealth = w + (q as f64 * s);
+        let wealth = w + config.mark_inventory(q, s);
 
         trajectory.push(StepRecord {
             time: t,
@@ -79,44 +1286,1829 @@ pub fn run_trajectory(
             wealth,
             bid_price: effective_bid,
             ask_price: effective_ask,
+            reservation_price: reservation,
+            regime,
+            liquidity,
+            volatility: price_sigma,
         });
 
         // 3. Market Evolution
-        let norm_sample: f64 = StandardNormal.sample(&mut rng);
-        let return_innovation = agent_params.sigma * config.dt.sqrt() * norm_sample;
-        let drift_component = config.drift * config.dt;
-        s *= 1.0 + drift_component + return_innovation;
+        let norm_sample = shock_sign * rng.sample::<f64, _>(StandardNormal);
+        let return_innovation = price_sigma * config.dt.sqrt() * norm_sample;
+        let drift_component = drift * config.dt;
+        // The normal is drawn under replay too, keeping the fill draws aligned with a GBM run.
+        let mid_before = s;
+        let proposed = match replay {
+            Some(prices) => prices.get(step + 1).copied().unwrap_or(s),
+            None => s * (1.0 + drift_component + return_innovation + adverse_move),
+        };
+        // Guard the multiplicative update: with a large negative move it crosses zero, and an
+        // exploding variance can overflow it.
+        s = if !proposed.is_finite() {
+            price_floor_hits += 1;
+            s
+        } else if proposed < config.price_floor {
+            price_floor_hits += 1;
+            config.price_floor
+        } else {
+            proposed
+        };
+        // The inventory held into the step is marked to the new mid; the fills below trade
+        // against that same mid, so each step's wealth change splits exactly into the two parts.
+        inventory_pnl += q * (s - mid_before);
+
+        if let PriceProcess::Heston {
+            kappa,
+            theta,
+            xi,
+            rho,
+            ..
+        } = config.price_process
+        {
+            // Euler step with truncation at zero, driven by a shock correlated with the price's.
+            let independent = shock_sign * rng.sample::<f64, _>(StandardNormal);
+            let shock = rho * norm_sample + (1.0 - rho * rho).sqrt() * independent;
+            variance = (variance
+                + kappa * (theta - variance) * config.dt
+                + xi * variance.sqrt() * config.dt.sqrt() * shock)
+                .max(0.0);
+        }
 
         // 4. Order Fill Logic (using Effective Quotes vs New Price)
         let delta_bid = s - effective_bid;
         let delta_ask = effective_ask - s;
 
-        let lambda_bid = intensity_model.calculate_intensity(delta_bid);
-        let lambda_ask = intensity_model.calculate_intensity(delta_ask);
+        let arrival_scale = match &config.liquidity {
+            Some(liq) if liq.scale_a => 1.0 / liquidity,
+            _ => 1.0,
+        } * (1.0 - config.queue_position);
+        let lambda_bid = intensity_model.bid_intensity(delta_bid * liquidity)
+            * arrival_scale
+            * (1.0 + hawkes_bid);
+        let lambda_ask = intensity_model.ask_intensity(delta_ask * liquidity)
+            * arrival_scale
+            * (1.0 + hawkes_ask);
 
         let prob_bid_fill = lambda_bid * config.dt;
         let prob_ask_fill = lambda_ask * config.dt;
 
-        let bid_hit = rng.random::<f64>() < prob_bid_fill;
-        let ask_hit = rng.random::<f64>() < prob_ask_fill;
+        // Both uniforms are drawn even when halted or crossing deterministically, to keep the RNG
+        // stream aligned.
+        let (bid_u, ask_u): (f64, f64) = (rng.random(), rng.random());
+        let (bid_hit, ask_hit) = match config.fill_model {
+            FillModel::Stochastic => (bid_u < prob_bid_fill, ask_u < prob_ask_fill),
+            FillModel::CrossDeterministic => (s <= effective_bid, s >= effective_ask),
+        };
+        let (bid_hit, ask_hit) = (bid_hit && !halted, ask_hit && !halted);
+
+        let reference_half_spread = optimal_spread(agent_params, t) / 2.0;
+        let (reference_bid, reference_ask) =
+            reference_fill_probs(intensity_model, reference_half_spread, config.dt);
+        let reference_fills = (bid_u < reference_bid) as u8 + (ask_u < reference_ask) as u8;
+        spread_capture_control += reference_half_spread * reference_fills as f64;
+
+        // Sizes are drawn every step regardless of fills to keep the RNG stream aligned.
+        let (bid_size, ask_size) = match &config.size_distribution {
+            Some(dist) => (
+                dist.sample(rng) as f64 * config.order_size,
+                dist.sample(rng) as f64 * config.order_size,
+            ),
+            None => (config.order_size, config.order_size),
+        };
+
+        let (bid_hit, ask_hit) = if bid_hit && ask_hit {
+            simultaneous_fill_steps += 1;
+            // Given the bid was hit, its uniform over the fill probability is uniform again, so it
+            // orders the sides without another draw.
+            let order_u = match config.fill_model {
+                FillModel::Stochastic => bid_u / prob_bid_fill.min(1.0),
+                FillModel::CrossDeterministic => bid_u,
+            };
+            let bid_first = order_u < lambda_bid / (lambda_bid + lambda_ask);
+            match config.simultaneous_fills {
+                SimultaneousFills::BothAllowed => (true, true),
+                SimultaneousFills::FirstOnly => (bid_first, !bid_first),
+                SimultaneousFills::Sequential => {
+                    let first_fill = if bid_first { bid_size } else { -ask_size };
+                    let impacted = s * (1.0 - config.adverse_selection * first_fill);
+                    let second_fills = if bid_first {
+                        match config.fill_model {
+                            FillModel::Stochastic => {
+                                let lambda = intensity_model
+                                    .ask_intensity((effective_ask - impacted) * liquidity)
+                                    * arrival_scale
+                                    * (1.0 + hawkes_ask);
+                                ask_u < lambda * config.dt
+                            }
+                            FillModel::CrossDeterministic => impacted >= effective_ask,
+                        }
+                    } else {
+                        match config.fill_model {
+                            FillModel::Stochastic => {
+                                let lambda = intensity_model
+                                    .bid_intensity((impacted - effective_bid) * liquidity)
+                                    * arrival_scale
+                                    * (1.0 + hawkes_bid);
+                                bid_u < lambda * config.dt
+                            }
+                            FillModel::CrossDeterministic => impacted <= effective_bid,
+                        }
+                    };
+                    (bid_first || second_fills, !bid_first || second_fills)
+                }
+            }
+        } else {
+            (bid_hit, ask_hit)
+        };
 
+        // Penalise the inventory held over [t, t + dt), before this step's fills.
+        running_penalty += phi * q.powi(2) * config.dt;
+        // Skipped at a zero rate, where subtracting a signed zero could flip the sign of `w`.
+        if config.carry_rate != 0.0 {
+            let carry = config.carry_rate * q * mid_before * config.dt;
+            w -= carry;
+            inventory_pnl -= carry;
+            carry_paid += carry;
+        }
+
+        let fill_time = t + config.dt;
+        let mut net_fill = 0.0;
         if bid_hit {
-            q += 1;
-            w -= effective_bid;
+            debug_assert!(effective_bid >= 0.0, "bid filled at {effective_bid}");
+            q += bid_size;
+            w -= effective_bid * bid_size;
+            spread_pnl += (s - effective_bid) * bid_size;
+            net_fill += bid_size;
+            bid_fills += 1;
+            fills.push(FillEvent {
+                step,
+                time: fill_time,
+                side: Side::Bid,
+                price: effective_bid,
+                size: bid_size,
+                mid_at_fill: s,
+            });
         }
 
         if ask_hit {
-            q -= 1;
-            w += effective_ask;
+            q -= ask_size;
+            w += effective_ask * ask_size;
+            spread_pnl += (effective_ask - s) * ask_size;
+            net_fill -= ask_size;
+            ask_fills += 1;
+            fills.push(FillEvent {
+                step,
+                time: fill_time,
+                side: Side::Ask,
+                price: effective_ask,
+                size: ask_size,
+                mid_at_fill: s,
+            });
+        }
+        adverse_move = -config.adverse_selection * net_fill;
+
+        if let Some(hawkes) = config.hawkes {
+            hawkes_bid *= hawkes_decay;
+            hawkes_ask *= hawkes_decay;
+            let (bid_jump, ask_jump) = match hawkes.excitation {
+                HawkesExcitation::SameSide => (bid_hit, ask_hit),
+                HawkesExcitation::BothSides => (bid_hit || ask_hit, bid_hit || ask_hit),
+            };
+            if bid_jump {
+                hawkes_bid += hawkes.alpha;
+            }
+            if ask_jump {
+                hawkes_ask += hawkes.alpha;
+            }
+        }
+
+        for _ in 0..(bid_hit as usize + ask_hit as usize) {
+            if let Some(last) = last_fill_time {
+                let gap = fill_time - last;
+                gap_count += 1;
+                gap_sum += gap;
+                gap_sq_sum += gap * gap;
+            }
+            last_fill_time = Some(fill_time);
+        }
+
+        // Kill-switch: flatten at the mid, less any liquidation cost, on a drawdown breach.
+        if let Some(stop) = config.max_drawdown_stop
+            && !halted
+        {
+            let marked = w + config.mark_inventory(q, s);
+            peak_wealth = peak_wealth.max(marked);
+            if peak_wealth - marked > stop {
+                let cost = config.liquidation_cost_per_unit.unwrap_or(0.0);
+                liquidation_cash = q * s - q.abs() * cost;
+                w += liquidation_cash;
+                inventory_pnl -= q.abs() * cost;
+                q = 0.0;
+                stopped_at = Some(step);
+            }
+        }
+
+        // Attribute this step's wealth change to the regime it was spent in, then transition.
+        let step_pnl = w + config.mark_inventory(q, s) - wealth;
+        match regime {
+            Regime::Calm => calm_pnl += step_pnl,
+            Regime::Stressed => {
+                stressed_pnl += step_pnl;
+                stressed_steps += 1;
+            }
+        }
+        if let Some(regimes) = &config.regimes {
+            let u: f64 = rng.random();
+            regime = match regime {
+                Regime::Calm if u < regimes.p_calm_to_stressed => Regime::Stressed,
+                Regime::Stressed if u < regimes.p_stressed_to_calm => Regime::Calm,
+                current => current,
+            };
+        }
+
+        liquidity_sum += liquidity;
+        if let Some(liq) = &config.liquidity {
+            liquidity = liq.process.next(liquidity, config.dt, shock_sign, rng);
+        }
+
+        if let Some(window) = recalibration_window
+            && !halted
+        {
+            fit_samples.push((delta_bid, bid_hit));
+            fit_samples.push((delta_ask, ask_hit));
+            if (step + 1) % window == 0 {
+                if let Ok(fit) = ExponentialIntensity::fit(fit_samples, config.dt) {
+                    strategy_k = fit.k;
+                    strategy_a = fit.a;
+                }
+                fit_samples.clear();
+            }
         }
 
         t += config.dt;
     }
 
-    let final_wealth = w + (q as f64 * s);
+    let final_wealth = w + config.mark_inventory(q, s);
+    // The marks of the opening and closing books sit off the mid, which the step-by-step
+    // inventory PnL does not see.
+    inventory_pnl += (config.initial_inventory.abs() - q.abs()) * config.mark_haircut();
+    let steps = num_steps.max(1) as f64;
+
+    let fill_time_dispersion = if gap_count >= 2 {
+        let n = gap_count as f64;
+        let mean = gap_sum / n;
+        let variance = (gap_sq_sum - n * mean * mean) / (n - 1.0);
+        variance / (mean * mean)
+    } else {
+        f64::NAN
+    };
 
-    SimResult {
+    *result = SimResult {
         trajectory,
         final_pnl: final_wealth,
+        running_penalty,
+        carry_paid,
+        quote_staleness_mean: staleness_sum / steps,
+        quote_staleness_exceed_frac: stale_steps as f64 / steps,
+        fill_time_dispersion,
+        stressed_fraction: stressed_steps as f64 / steps,
+        calm_pnl,
+        stressed_pnl,
+        spread_pnl,
+        inventory_pnl,
+        bid_fills,
+        ask_fills,
+        crossed_quote_steps,
+        fills,
+        mean_liquidity: liquidity_sum / steps,
+        strategy_k,
+        spread_capture_control,
+        stopped_at,
+        simultaneous_fill_steps,
+        price_floor_hits,
+        final_cash: w,
+        final_inventory: q,
+        final_mid: s,
+        liquidation_cash,
+    };
+
+    // Debug builds rebuild the books from the recorded fills, so a change that moves cash or
+    // inventory without recording it fails here rather than as a quietly wrong PnL.
+    if cfg!(debug_assertions) {
+        reconcile(result, opening_cash, config);
+    }
+}
+
+/// Asserts that the cash and inventory of `result` follow from its opening cash, its fills, the
+/// carry, and any kill-switch liquidation, and that `final_pnl` marks the closing books.
+fn reconcile(result: &SimResult, opening_cash: f64, config: &SimConfig) {
+    // The running totals add in a different order, so allow rounding relative to the flows.
+    let scale = 1.0
+        + opening_cash.abs()
+        + result.traded_notional()
+        + result.carry_paid.abs()
+        + result.liquidation_cash.abs();
+    let cash = opening_cash + result.fill_cash_flow() - result.carry_paid + result.liquidation_cash;
+    assert!(
+        (cash - result.final_cash).abs() <= 1e-9 * scale,
+        "cash account {} does not reconcile with the cash flows {cash}",
+        result.final_cash
+    );
+    // The kill-switch flattens whatever is held, and nothing fills after it.
+    let inventory = if result.stopped_at.is_some() {
+        0.0
+    } else {
+        config.initial_inventory + result.filled_inventory()
+    };
+    let size_scale = 1.0 + config.initial_inventory.abs() + result.fills.len() as f64;
+    assert!(
+        (inventory - result.final_inventory).abs() <= 1e-9 * size_scale,
+        "inventory {} does not reconcile with the fills {inventory}",
+        result.final_inventory
+    );
+    assert_eq!(
+        result.final_pnl,
+        result.final_cash + config.mark_inventory(result.final_inventory, result.final_mid),
+        "final PnL is not the marked closing books"
+    );
+}
+
+/// Steps actually simulated: a replayed series caps `num_steps` at its length.
+fn effective_num_steps(config: &SimConfig) -> usize {
+    match &config.price_process {
+        PriceProcess::Replay { prices } => config.num_steps.min(prices.len()),
+        _ => config.num_steps,
+    }
+}
+
+/// Per-step fill probabilities of the reference quoter behind `spread_capture_control`: unit
+/// orders resting `half_spread` either side of the mid with zero inventory, under the base
+/// intensity alone (no liquidity, Hawkes, queue, or latency effects).
+fn reference_fill_probs<M: IntensityModel + ?Sized>(
+    intensity_model: &M,
+    half_spread: f64,
+    dt: f64,
+) -> (f64, f64) {
+    (
+        intensity_model.bid_intensity(half_spread) * dt,
+        intensity_model.ask_intensity(half_spread) * dt,
+    )
+}
+
+/// Expected value of `SimResult::spread_capture_control`: expected reference fills times the
+/// half-spread, summed over the steps, with no simulation.
+pub fn expected_spread_capture<M: IntensityModel + ?Sized>(
+    agent_params: &Parameters,
+    config: &SimConfig,
+    intensity_model: &M,
+) -> f64 {
+    let mut t = 0.0;
+    let mut total = 0.0;
+    for _ in 0..effective_num_steps(config) {
+        let half_spread = optimal_spread(agent_params, t) / 2.0;
+        let (bid, ask) = reference_fill_probs(intensity_model, half_spread, config.dt);
+        total += half_spread * (bid.clamp(0.0, 1.0) + ask.clamp(0.0, 1.0));
+        t += config.dt;
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ExponentialIntensity, QuotingModel};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_builder_defaults_and_setters() {
+        let config = SimConfig::builder()
+            .num_steps(200)
+            .latency_steps(3)
+            .adverse_selection(0.01)
+            .build()
+            .unwrap();
+        assert_eq!(config.dt, 0.005);
+        assert_eq!(config.num_steps, 200);
+        assert_eq!(config.s_0, 100.0);
+        assert_eq!(config.latency_steps, 3);
+        assert_eq!(config.adverse_selection, 0.01);
+        assert!(config.hawkes.is_none());
+        assert_eq!(config.price_process, PriceProcess::Gbm);
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_configs() {
+        for dt in [0.0, -0.1, f64::NAN] {
+            let err = SimConfig::builder().dt(dt).build().unwrap_err();
+            assert!(matches!(err, SimError::NonPositiveDt(_)));
+        }
+
+        let err = SimConfig::builder().num_steps(0).build().unwrap_err();
+        assert_eq!(err, SimError::NoSteps);
+
+        let err = SimConfig::builder()
+            .num_steps(10)
+            .latency_steps(10)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            SimError::LatencyTooLong {
+                latency_steps: 10,
+                num_steps: 10
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "`latency_steps` (10) must be less than `num_steps` (10)"
+        );
+
+        for s_0 in [0.0, -5.0, f64::NAN] {
+            let err = SimConfig::builder().s_0(s_0).build().unwrap_err();
+            assert!(matches!(err, SimError::NonPositiveS0(_)));
+        }
+
+        let err = SimConfig::builder()
+            .initial_inventory(f64::INFINITY)
+            .build()
+            .unwrap_err();
+        assert_eq!(err, SimError::NonFiniteInitialInventory(f64::INFINITY));
+
+        for floor in [0.0, -1.0, 100.0, f64::NAN] {
+            let err = SimConfig::builder().price_floor(floor).build().unwrap_err();
+            assert!(matches!(err, SimError::InvalidPriceFloor { .. }));
+        }
+    }
+
+    #[test]
+    fn test_price_floor_guards_extreme_moves() {
+        let params =
+            Parameters::new(0.1, 0.4, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        // A drift of -300 moves the mid by -150% a step, so the raw update goes negative at once.
+        let config = SimConfig::builder()
+            .s_0(1.0)
+            .drift(-300.0)
+            .price_floor(0.05)
+            .build()
+            .unwrap();
+        let res = run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(3));
+        assert!(res.price_floor_hits > 0);
+        assert!(res.final_pnl.is_finite());
+        for step in &res.trajectory {
+            assert!(step.mid_price >= 0.05);
+            assert!(step.bid_price >= 0.0 && step.ask_price >= 0.0);
+        }
+
+        let calm = SimConfig::builder().num_steps(200).build().unwrap();
+        let res = run_trajectory_with_rng(&params, &calm, &model, &mut StdRng::seed_from_u64(3));
+        assert_eq!(res.price_floor_hits, 0);
+    }
+
+    #[test]
+    fn test_conservative_mark_discounts_held_inventory() {
+        // A weak skew against a strong trend leaves a large position at the end.
+        let params =
+            Parameters::new(0.01, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let run = |inventory_mark| {
+            let config = SimConfig::builder()
+                .num_steps(200)
+                .drift(20.0)
+                .liquidation_cost_per_unit(0.25)
+                .inventory_mark(inventory_mark)
+                .build()
+                .unwrap();
+            run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(8))
+        };
+        let mid = run(InventoryMark::Mid);
+        let conservative = run(InventoryMark::Conservative { haircut: 0.5 });
+        // The last row is the inventory going into the final step, so add up the fills instead.
+        let terminal: f64 = conservative
+            .fills
+            .iter()
+            .map(|fill| match fill.side {
+                Side::Bid => fill.size,
+                Side::Ask => -fill.size,
+            })
+            .sum();
+        assert!(terminal.abs() >= 10.0, "{terminal}");
+
+        // Marking never changes the quotes or fills, only what the held position counts for.
+        assert_eq!(conservative.fills, mid.fills);
+        assert!((mid.final_pnl - conservative.final_pnl - 0.5 * terminal.abs()).abs() < 1e-9);
+        for (m, c) in mid.trajectory.iter().zip(&conservative.trajectory) {
+            assert!((m.wealth - c.wealth - 0.5 * c.inventory.abs()).abs() < 1e-9);
+        }
+        for res in [&conservative, &run(InventoryMark::Exit)] {
+            assert!((res.spread_pnl + res.inventory_pnl - res.final_pnl).abs() < 1e-9);
+            assert!((res.calm_pnl + res.stressed_pnl - res.final_pnl).abs() < 1e-9);
+        }
+        let exit = run(InventoryMark::Exit);
+        assert!((mid.final_pnl - exit.final_pnl - 0.25 * terminal.abs()).abs() < 1e-9);
+
+        let err = SimConfig::builder()
+            .inventory_mark(InventoryMark::Conservative { haircut: -0.1 })
+            .build()
+            .unwrap_err();
+        assert_eq!(err, SimError::NegativeMarkHaircut(-0.1));
+    }
+
+    #[test]
+    fn test_books_reconcile_with_fills() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let sizes = Arc::new(EmpiricalSizeDistribution::from_table(&[(1, 0.5), (3, 0.5)]).unwrap());
+        let configs = [
+            SimConfig::builder().num_steps(200).build().unwrap(),
+            SimConfig::builder()
+                .num_steps(200)
+                .size_distribution(sizes)
+                .order_size(0.5)
+                .initial_inventory(4.0)
+                .carry_rate(0.05)
+                .inventory_mark(InventoryMark::Conservative { haircut: 0.3 })
+                .build()
+                .unwrap(),
+            SimConfig::builder()
+                .num_steps(200)
+                .max_drawdown_stop(2.0)
+                .liquidation_cost_per_unit(0.05)
+                .inventory_mark(InventoryMark::Exit)
+                .build()
+                .unwrap(),
+        ];
+
+        let mut liquidated = false;
+        for config in &configs {
+            for seed in 0..20 {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let res = run_trajectory_with_rng(&params, config, &model, &mut rng);
+                let opening_cash = -config.mark_inventory(config.initial_inventory, config.s_0);
+                let cash =
+                    opening_cash + res.fill_cash_flow() - res.carry_paid + res.liquidation_cash;
+                assert!(
+                    (cash - res.final_cash).abs() < 1e-6,
+                    "{cash} vs {}",
+                    res.final_cash
+                );
+                let inventory = match res.stopped_at {
+                    Some(_) => 0.0,
+                    None => config.initial_inventory + res.filled_inventory(),
+                };
+                assert!((inventory - res.final_inventory).abs() < 1e-9);
+                let marked = config.mark_inventory(res.final_inventory, res.final_mid);
+                assert_eq!(res.final_pnl, res.final_cash + marked);
+                liquidated |= res.liquidation_cash != 0.0;
+            }
+        }
+        assert!(
+            liquidated,
+            "the kill-switch case should flatten at least once"
+        );
+    }
+
+    #[test]
+    fn test_carry_is_charged_on_held_inventory() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let run = |carry_rate, initial_inventory| {
+            let config = SimConfig::builder()
+                .num_steps(200)
+                .initial_inventory(initial_inventory)
+                .carry_rate(carry_rate)
+                .build()
+                .unwrap();
+            run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(5))
+        };
+
+        // A zero rate charges nothing.
+        let free = run(0.0, 5.0);
+        assert_eq!(free.carry_paid, 0.0);
+
+        // The charge never changes the quotes, so the fills match and only the cash differs.
+        let rate = 0.02;
+        let long = run(rate, 5.0);
+        assert_eq!(long.fills, free.fills);
+        let expected: f64 = long
+            .trajectory
+            .iter()
+            .map(|step| rate * step.inventory * step.mid_price * 0.005)
+            .sum();
+        assert!((long.carry_paid - expected).abs() < 1e-9);
+        assert!((free.final_pnl - long.final_pnl - long.carry_paid).abs() < 1e-9);
+        assert!((long.spread_pnl + long.inventory_pnl - long.final_pnl).abs() < 1e-9);
+
+        // Longs pay a positive rate and shorts receive it. At the back of the queue nothing fills,
+        // so the opening position is held throughout.
+        let held = |initial_inventory| {
+            let config = SimConfig::builder()
+                .num_steps(200)
+                .initial_inventory(initial_inventory)
+                .queue_position(1.0)
+                .carry_rate(rate)
+                .build()
+                .unwrap();
+            run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(5))
+        };
+        assert!(held(5.0).carry_paid > 0.0);
+        assert!(held(-5.0).carry_paid < 0.0);
+
+        let err = SimConfig::builder()
+            .carry_rate(f64::NAN)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, SimError::NonFiniteCarryRate(_)));
+    }
+
+    #[test]
+    fn test_observation_noise_degrades_pnl() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        // A mid of 10 keeps the price risk small next to the spread, so the quoting error shows.
+        let run = |observation_noise, seed| {
+            let config = SimConfig::builder()
+                .num_steps(200)
+                .s_0(10.0)
+                .observation_noise(observation_noise)
+                .build()
+                .unwrap();
+            let mut rng = StdRng::seed_from_u64(seed);
+            run_trajectory_with_rng(&params, &config, &model, &mut rng)
+        };
+        let mids = |result: &SimResult| -> Vec<f64> {
+            result.trajectory.iter().map(|r| r.mid_price).collect()
+        };
+
+        let runs = 100;
+        let noises = [0.0, 0.5, 1.0];
+        let mut pnls = [0.0; 3];
+        for seed in 0..runs {
+            let results: Vec<SimResult> = noises.iter().map(|&noise| run(noise, seed)).collect();
+            // Common random numbers: the noise moves the quotes, never the price path.
+            for result in &results[1..] {
+                assert_eq!(mids(result), mids(&results[0]), "seed {seed}");
+            }
+            for (pnl, result) in pnls.iter_mut().zip(&results) {
+                *pnl += result.final_pnl / runs as f64;
+            }
+        }
+
+        // Quotes centred off the true mid fill more on the side where they give edge away.
+        assert!(pnls.windows(2).all(|pair| pair[0] > pair[1]), "{pnls:?}");
+        assert!(pnls[2] < pnls[0] - 20.0, "{pnls:?}");
+
+        let err = SimConfig::builder()
+            .observation_noise(-1.0)
+            .build()
+            .unwrap_err();
+        assert_eq!(err, SimError::InvalidObservationNoise(-1.0));
+        let err = SimConfig::builder()
+            .observation_noise(f64::NAN)
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, SimError::InvalidObservationNoise(_)));
+    }
+
+    #[test]
+    fn test_simultaneous_fill_policies() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let run = |policy, adverse_selection, seed| {
+            let config = SimConfig::builder()
+                .num_steps(200)
+                .adverse_selection(adverse_selection)
+                .simultaneous_fills(policy)
+                .build()
+                .unwrap();
+            run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(seed))
+        };
+        let same_step_pairs = |res: &SimResult| {
+            res.fills
+                .windows(2)
+                .filter(|pair| pair[0].step == pair[1].step)
+                .count()
+        };
+
+        let (mut both_fills, mut first_fills) = (0, 0);
+        let (mut simultaneous_steps, mut sequential_pairs) = (0, 0);
+        for seed in 0..20 {
+            let both = run(SimultaneousFills::BothAllowed, 0.0, seed);
+            assert_eq!(same_step_pairs(&both), both.simultaneous_fill_steps);
+            both_fills += both.fills.len();
+
+            let first = run(SimultaneousFills::FirstOnly, 0.0, seed);
+            assert_eq!(same_step_pairs(&first), 0);
+            first_fills += first.fills.len();
+
+            // Without impact the second side sees the same mid, so nothing changes.
+            let sequential = run(SimultaneousFills::Sequential, 0.0, seed);
+            assert_eq!(format!("{sequential:?}"), format!("{both:?}"));
+            // A 0.5% move per unit, close to the half-spread at s = 100, pushes the second quote
+            // about twice as far from the mid, so some of the second fills no longer happen.
+            let sequential = run(SimultaneousFills::Sequential, 0.005, seed);
+            simultaneous_steps += sequential.simultaneous_fill_steps;
+            sequential_pairs += same_step_pairs(&sequential);
+        }
+        assert!(first_fills < both_fills);
+        assert!(0 < sequential_pairs && sequential_pairs < simultaneous_steps);
+    }
+
+    #[test]
+    fn test_queue_position_reduces_fills() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let total_fills = |queue_position| {
+            let config = SimConfig::builder()
+                .num_steps(200)
+                .queue_position(queue_position)
+                .build()
+                .unwrap();
+            (0..50)
+                .map(|seed| {
+                    let mut rng = StdRng::seed_from_u64(seed);
+                    let res = run_trajectory_with_rng(&params, &config, &model, &mut rng);
+                    res.bid_fills + res.ask_fills
+                })
+                .sum::<usize>()
+        };
+
+        let fills: Vec<usize> = [0.0, 0.25, 0.5, 0.75, 1.0]
+            .into_iter()
+            .map(total_fills)
+            .collect();
+        assert!(fills.windows(2).all(|pair| pair[0] > pair[1]), "{fills:?}");
+        assert_eq!(fills[4], 0);
+
+        let err = SimConfig::builder()
+            .queue_position(1.5)
+            .build()
+            .unwrap_err();
+        assert_eq!(err, SimError::InvalidQueuePosition(1.5));
+    }
+
+    #[test]
+    fn test_first_step_quotes_match_decide_quotes() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let config = SimConfig::builder()
+            .num_steps(10)
+            .initial_inventory(3.0)
+            .build()
+            .unwrap();
+        let res = run_trajectory_with_rng(
+            &params,
+            &config,
+            &ExponentialIntensity { k: 1.5, a: 140.0 },
+            &mut StdRng::seed_from_u64(1),
+        );
+        let first = &res.trajectory[0];
+        let decision = decide_quotes(&params, config.drift, config.s_0, 3.0, 0.0);
+        assert_eq!(
+            (first.reservation_price, first.bid_price, first.ask_price),
+            (decision.reservation_price, decision.bid, decision.ask)
+        );
+    }
+
+    #[test]
+    fn test_initial_inventory_unwinds() {
+        let params =
+            Parameters::new(2.0, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let start = 10.0;
+        // 200 steps of 0.005 end at the horizon, after which the skew vanishes.
+        let config = SimConfig::builder()
+            .num_steps(200)
+            .initial_inventory(start)
+            .build()
+            .unwrap();
+
+        let runs = 100;
+        let mut terminal = 0.0;
+        for seed in 0..runs {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let res = run_trajectory_with_rng(&params, &config, &model, &mut rng);
+            // The inherited book is marked at s_0 against matching cash, and skews the quotes down.
+            let first = &res.trajectory[0];
+            assert_eq!((first.inventory, first.wealth), (start, 0.0));
+            assert_eq!(first.cash, -start * config.s_0);
+            assert!(first.reservation_price < first.mid_price);
+            terminal += res.trajectory.last().unwrap().inventory / runs as f64;
+        }
+        assert!(
+            terminal.abs() < 0.5 * start,
+            "mean terminal inventory {terminal}"
+        );
+    }
+
+    fn staleness_setup(drift: f64, latency_steps: usize) -> SimResult {
+        // Zero volatility makes the price path a deterministic drift and removes the inventory skew.
+        let params = Parameters {
+            gamma: 0.1,
+            sigma: 0.0,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+        let config = SimConfig::builder()
+            .num_steps(200)
+            .drift(drift)
+            .latency_steps(latency_steps)
+            .build()
+            .unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(7))
+    }
+
+    #[test]
+    fn test_no_staleness_without_latency() {
+        let res = staleness_setup(1.0, 0);
+        assert_eq!(res.quote_staleness_mean, 0.0);
+        assert_eq!(res.quote_staleness_exceed_frac, 0.0);
+    }
+
+    #[test]
+    fn test_staleness_grows_with_drift_under_latency() {
+        let slow = staleness_setup(0.5, 10);
+        let fast = staleness_setup(2.0, 10);
+
+        assert!(slow.quote_staleness_mean > 0.0);
+        assert!(fast.quote_staleness_mean > slow.quote_staleness_mean);
+        assert!(fast.quote_staleness_exceed_frac >= slow.quote_staleness_exceed_frac);
+    }
+
+    #[test]
+    fn test_single_size_table_matches_fixed_size() {
+        let params = Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::SymmetricMid,
+        };
+        let unit_config = SimConfig::builder().num_steps(200).build().unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let run = |config: &SimConfig| {
+            run_trajectory_with_rng(&params, config, &model, &mut StdRng::seed_from_u64(5))
+        };
+
+        let unit_table = EmpiricalSizeDistribution::from_table(&[(1, 1.0)]).unwrap();
+        let table_config = SimConfig {
+            size_distribution: Some(Arc::new(unit_table)),
+            ..unit_config.clone()
+        };
+        let fixed = run(&unit_config);
+        let tabled = run(&table_config);
+        assert_eq!(fixed.final_pnl, tabled.final_pnl);
+        for (a, b) in fixed.trajectory.iter().zip(&tabled.trajectory) {
+            assert_eq!(a.inventory, b.inventory);
+            assert_eq!(a.cash, b.cash);
+        }
+
+        // A single size of 3 turns every unit fill into a block of 3. Without inventory skew the
+        // larger position leaves the quotes, and hence the fills, unchanged.
+        let block_table = EmpiricalSizeDistribution::from_table(&[(3, 1.0)]).unwrap();
+        let block_config = SimConfig {
+            size_distribution: Some(Arc::new(block_table)),
+            ..unit_config.clone()
+        };
+        let blocks = run(&block_config);
+        for (a, b) in fixed.trajectory.iter().zip(&blocks.trajectory) {
+            assert_eq!(3.0 * a.inventory, b.inventory);
+        }
+    }
+
+    #[test]
+    fn test_fractional_order_size_scales_inventory() {
+        // Without inventory skew the quotes, and hence the fill decisions, ignore `q`.
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::SymmetricMid).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let run = |order_size: f64| {
+            let config = SimConfig::builder()
+                .num_steps(200)
+                .order_size(order_size)
+                .build()
+                .unwrap();
+            run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(5))
+        };
+
+        let unit = run(1.0);
+        let quarter = run(0.25);
+        assert_eq!(
+            (unit.bid_fills, unit.ask_fills),
+            (quarter.bid_fills, quarter.ask_fills)
+        );
+        for (a, b) in unit.trajectory.iter().zip(&quarter.trajectory) {
+            assert_eq!(0.25 * a.inventory, b.inventory);
+            assert_eq!(0.25 * a.cash, b.cash);
+        }
+        assert!(
+            quarter
+                .trajectory
+                .iter()
+                .any(|step| step.inventory.fract() != 0.0)
+        );
+
+        let err = SimConfig::builder().order_size(0.0).build().unwrap_err();
+        assert_eq!(err, SimError::NonPositiveOrderSize(0.0));
+    }
+
+    #[test]
+    fn test_hawkes_excitation_clusters_fills() {
+        let params = Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 10.0,
+            k: 1.5,
+            a: 20.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+        let poisson_config = SimConfig::builder().num_steps(2000).build().unwrap();
+        let hawkes_config = SimConfig {
+            hawkes: Some(HawkesConfig {
+                // Each fill spawns on average 2 * alpha * lambda / beta ~ 0.45 further fills, so
+                // the process stays subcritical and fill probabilities stay well below one.
+                alpha: 1.5,
+                beta: 50.0,
+                excitation: HawkesExcitation::BothSides,
+            }),
+            ..poisson_config.clone()
+        };
+        let model = ExponentialIntensity { k: 1.5, a: 20.0 };
+
+        let mean_dispersion = |config: &SimConfig| {
+            (0..20)
+                .map(|seed| {
+                    let mut rng = StdRng::seed_from_u64(seed);
+                    run_trajectory_with_rng(&params, config, &model, &mut rng).fill_time_dispersion
+                })
+                .sum::<f64>()
+                / 20.0
+        };
+
+        let poisson = mean_dispersion(&poisson_config);
+        let hawkes = mean_dispersion(&hawkes_config);
+        assert!((poisson - 1.0).abs() < 0.3, "poisson dispersion {poisson}");
+        assert!(
+            hawkes > poisson + 0.3,
+            "hawkes {hawkes} vs poisson {poisson}"
+        );
+    }
+
+    #[test]
+    fn test_regime_switching_splits_pnl_and_informs_oracle() {
+        let params = Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+        let regimes = RegimeSwitching {
+            calm_sigma: 0.2,
+            calm_drift: 0.0,
+            stressed_sigma: 2.0,
+            stressed_drift: -1.0,
+            p_calm_to_stressed: 0.02,
+            p_stressed_to_calm: 0.02,
+            oracle: false,
+        };
+        let config = SimConfig::builder()
+            .num_steps(200)
+            .regimes(regimes)
+            .build()
+            .unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let run = |config: &SimConfig| {
+            run_trajectory_with_rng(&params, config, &model, &mut StdRng::seed_from_u64(3))
+        };
+
+        let blind = run(&config);
+        let stressed = blind
+            .trajectory
+            .iter()
+            .filter(|step| step.regime == Regime::Stressed)
+            .count();
+        assert!(stressed > 0 && stressed < config.num_steps);
+        assert_eq!(blind.stressed_fraction, stressed as f64 / 200.0);
+        assert!((blind.calm_pnl + blind.stressed_pnl - blind.final_pnl).abs() < 1e-9);
+
+        // Same seed, so the regime path is identical; only the oracle widens its stressed quotes.
+        let oracle = run(&SimConfig {
+            regimes: Some(RegimeSwitching {
+                oracle: true,
+                ..regimes
+            }),
+            ..config.clone()
+        });
+        for (b, o) in blind.trajectory.iter().zip(&oracle.trajectory) {
+            assert_eq!(b.regime, o.regime);
+            let (blind_width, oracle_width) =
+                (b.ask_price - b.bid_price, o.ask_price - o.bid_price);
+            match b.regime {
+                Regime::Calm => assert!((blind_width - oracle_width).abs() < 1e-9),
+                Regime::Stressed => assert!(oracle_width > blind_width),
+            }
+        }
+    }
+
+    #[test]
+    fn test_adverse_selection_eats_spread_pnl() {
+        let params = Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+        // A low price level keeps per-step noise well inside the half-spread, so the quotes earn
+        // their edge when fills are uninformed.
+        let neutral = SimConfig::builder()
+            .num_steps(200)
+            .s_0(10.0)
+            .build()
+            .unwrap();
+        let toxic = SimConfig {
+            adverse_selection: 0.04,
+            ..neutral.clone()
+        };
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+
+        let mean_split = |config: &SimConfig| {
+            let (mut spread, mut inventory) = (0.0, 0.0);
+            for seed in 0..50 {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let res = run_trajectory_with_rng(&params, config, &model, &mut rng);
+                assert!((res.spread_pnl + res.inventory_pnl - res.final_pnl).abs() < 1e-9);
+                spread += res.spread_pnl / 50.0;
+                inventory += res.inventory_pnl / 50.0;
+            }
+            (spread, inventory)
+        };
+
+        let (neutral_spread, neutral_inventory) = mean_split(&neutral);
+        let (toxic_spread, toxic_inventory) = mean_split(&toxic);
+        assert!(neutral_spread > 0.0 && toxic_spread > 0.0);
+        assert!(neutral_inventory.abs() < 0.25 * neutral_spread);
+        assert!(
+            toxic_inventory < -0.25 * toxic_spread,
+            "spread {toxic_spread} vs inventory {toxic_inventory}"
+        );
+    }
+
+    #[test]
+    fn test_dry_liquidity_is_recorded_and_recalibrated() {
+        let params = Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 15.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+        // The market turns dry after the first step and never recovers: its k triples.
+        let liquidity = StochasticLiquidity {
+            process: LiquidityProcess::TwoState {
+                dry_multiplier: 3.0,
+                p_dry: 1.0,
+                p_recover: 0.0,
+            },
+            scale_a: false,
+            recalibration_window: None,
+        };
+        let config = SimConfig::builder()
+            .num_steps(3000)
+            .s_0(10.0)
+            .liquidity(liquidity)
+            .build()
+            .unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let run = |config: &SimConfig| {
+            run_trajectory_with_rng(&params, config, &model, &mut StdRng::seed_from_u64(9))
+        };
+
+        let fixed = run(&config);
+        assert_eq!(fixed.trajectory[0].liquidity, 1.0);
+        assert!(
+            fixed.trajectory[1..]
+                .iter()
+                .all(|step| step.liquidity == 3.0)
+        );
+        assert_eq!(fixed.strategy_k, 1.5);
+
+        let adaptive = run(&SimConfig {
+            liquidity: Some(StochasticLiquidity {
+                recalibration_window: Some(1000),
+                ..liquidity
+            }),
+            ..config.clone()
+        });
+        assert!(
+            (adaptive.strategy_k - 4.5).abs() < 0.2 * 4.5,
+            "recalibrated k {}",
+            adaptive.strategy_k
+        );
+    }
+
+    #[test]
+    fn test_drawdown_stop_flattens_and_halts() {
+        let params = Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+        let config = SimConfig::builder()
+            .num_steps(200)
+            .max_drawdown_stop(2.0)
+            .liquidation_cost_per_unit(0.1)
+            .build()
+            .unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let res = run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(1));
+
+        let stop = res
+            .stopped_at
+            .expect("a 2.0 drawdown limit should be breached");
+        let halted = &res.trajectory[stop + 1..];
+        assert!(!halted.is_empty());
+        for step in halted {
+            assert_eq!(step.inventory, 0.0);
+            assert_eq!(step.wealth, res.final_pnl);
+            assert!(step.bid_price.is_nan() && step.ask_price.is_nan());
+        }
+
+        // The book was flattened only after wealth fell more than the limit below its peak.
+        let peak = res.trajectory[..=stop]
+            .iter()
+            .map(|step| step.wealth)
+            .fold(0.0, f64::max);
+        assert!(res.final_pnl < peak - 2.0);
+    }
+
+    #[test]
+    fn test_closure_intensity_fills() {
+        let params = Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 1.0,
+            k: 2.0,
+            a: 100.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+        let config = SimConfig::builder().num_steps(200).build().unwrap();
+        let intensity = |d: f64| 100.0 * (-2.0 * d).exp();
+        let res =
+            run_trajectory_with_rng(&params, &config, &intensity, &mut StdRng::seed_from_u64(2));
+        assert!(res.trajectory.iter().any(|step| step.inventory != 0.0));
+    }
+
+    #[test]
+    fn test_dyn_intensity_matches_concrete() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let config = SimConfig::builder().num_steps(200).build().unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let dyn_model: &dyn IntensityModel = &model;
+
+        // Same seeds, same model: dispatch must not change the result.
+        for seed in 0..5 {
+            let rng = || StdRng::seed_from_u64(seed);
+            let concrete = run_trajectory_with_rng(&params, &config, &model, &mut rng());
+            let dynamic = run_trajectory_with_rng(&params, &config, dyn_model, &mut rng());
+            assert_eq!(concrete.final_pnl, dynamic.final_pnl);
+            assert_eq!(concrete.fills, dynamic.fills);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_lines_export() {
+        let config = SimConfig::builder().num_steps(50).build().unwrap();
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let res = run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(9));
+
+        let mut buf = Vec::new();
+        res.to_json_lines(&params, &config, &mut buf).unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), res.trajectory.len() + 1);
+
+        for (line, step) in lines.iter().zip(&res.trajectory) {
+            let record: serde_json::Value = serde_json::from_str(line).unwrap();
+            for field in [
+                "time",
+                "mid_price",
+                "inventory",
+                "wealth",
+                "bid_price",
+                "ask_price",
+            ] {
+                assert!(record.get(field).is_some(), "missing step field {field}");
+            }
+            assert_eq!(record["inventory"], step.inventory);
+        }
+
+        let summary: serde_json::Value = serde_json::from_str(lines[lines.len() - 1]).unwrap();
+        let summary = &summary["summary"];
+        assert!(summary.get("trajectory").is_none());
+        assert_eq!(summary["final_pnl"].as_f64().unwrap(), res.final_pnl);
+        assert_eq!(summary["params"]["gamma"].as_f64().unwrap(), 0.1);
+        assert_eq!(summary["config"]["num_steps"].as_u64().unwrap(), 50);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip_config_and_result() {
+        let config = SimConfig::builder()
+            .num_steps(200)
+            .latency_steps(2)
+            .size_distribution(Arc::new(
+                EmpiricalSizeDistribution::from_table(&[(1, 0.5), (2, 0.5)]).unwrap(),
+            ))
+            .hawkes(HawkesConfig {
+                alpha: 1.0,
+                beta: 50.0,
+                excitation: HawkesExcitation::SameSide,
+            })
+            .regimes(RegimeSwitching {
+                calm_sigma: 0.2,
+                calm_drift: 0.0,
+                stressed_sigma: 0.6,
+                stressed_drift: -0.5,
+                p_calm_to_stressed: 0.01,
+                p_stressed_to_calm: 0.05,
+                oracle: true,
+            })
+            .adverse_selection(0.001)
+            .liquidity(StochasticLiquidity {
+                process: LiquidityProcess::MeanReverting {
+                    reversion: 2.0,
+                    vol: 0.5,
+                },
+                scale_a: true,
+                recalibration_window: Some(100),
+            })
+            .max_drawdown_stop(2.0)
+            .liquidation_cost_per_unit(0.05)
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(serde_json::from_str::<SimConfig>(&json).unwrap(), config);
+
+        // Optional extensions can be left out entirely.
+        let minimal: SimConfig = serde_json::from_str(
+            r#"{"dt": 0.005, "num_steps": 200, "s_0": 100.0, "drift": 0.0,
+                "latency_steps": 0, "staleness_threshold": 0.01}"#,
+        )
+        .unwrap();
+        assert!(minimal.hawkes.is_none() && minimal.max_drawdown_stop.is_none());
+        assert_eq!(minimal.adverse_selection, 0.0);
+
+        // The halted rows carry NaN quotes, which must survive as `null`.
+        let params = Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::Stationary).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let res = run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(4));
+        let json = serde_json::to_string(&res).unwrap();
+        let back: SimResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        assert_eq!(back.trajectory.len(), res.trajectory.len());
+
+        assert!(res.stopped_at.is_some());
+        let step = res.trajectory[res.trajectory.len() - 1];
+        let json = serde_json::to_string(&step).unwrap();
+        let back: StepRecord = serde_json::from_str(&json).unwrap();
+        assert!(back.bid_price.is_nan() && back.ask_price.is_nan());
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+    }
+
+    #[test]
+    fn test_heston_returns_have_fat_tails() {
+        let params = Parameters {
+            gamma: 0.1,
+            sigma: 0.2,
+            t_horizon: 10.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+        let gbm = SimConfig::builder().num_steps(2000).build().unwrap();
+        // Same long-run variance as the constant 0.2 volatility, but strongly clustered.
+        let heston = SimConfig {
+            price_process: PriceProcess::Heston {
+                kappa: 2.0,
+                theta: 0.04,
+                xi: 0.8,
+                rho: -0.5,
+                v0: 0.04,
+            },
+            ..gbm.clone()
+        };
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+
+        let kurtosis = |config: &SimConfig| {
+            let returns: Vec<f64> = (0..20)
+                .flat_map(|seed| {
+                    let mut rng = StdRng::seed_from_u64(seed);
+                    let res = run_trajectory_with_rng(&params, config, &model, &mut rng);
+                    res.trajectory
+                        .windows(2)
+                        .map(|w| (w[1].mid_price / w[0].mid_price).ln())
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            let n = returns.len() as f64;
+            let mean = returns.iter().sum::<f64>() / n;
+            let m2 = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n;
+            let m4 = returns.iter().map(|r| (r - mean).powi(4)).sum::<f64>() / n;
+            m4 / (m2 * m2)
+        };
+
+        let constant = kurtosis(&gbm);
+        let stochastic = kurtosis(&heston);
+        assert!((constant - 3.0).abs() < 0.2, "gbm kurtosis {constant}");
+        assert!(
+            stochastic > constant + 0.5,
+            "heston {stochastic} vs gbm {constant}"
+        );
+    }
+
+    #[test]
+    fn test_replay_follows_price_series() {
+        let prices: Vec<f64> = (0..50).map(|i| 100.0 + 0.1 * i as f64).collect();
+        let config = SimConfig::builder()
+            .num_steps(80)
+            .s_0(1.0)
+            .drift(5.0)
+            .price_process(PriceProcess::Replay {
+                prices: prices.clone().into(),
+            })
+            .build()
+            .unwrap();
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let res = run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(2));
+
+        let mids: Vec<f64> = res.trajectory.iter().map(|step| step.mid_price).collect();
+        assert_eq!(mids, prices);
+    }
+
+    #[test]
+    fn test_requote_interval_holds_quotes() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let run = |requote_interval, latency_steps| {
+            let config = SimConfig::builder()
+                .num_steps(200)
+                .requote_interval(requote_interval)
+                .latency_steps(latency_steps)
+                .build()
+                .unwrap();
+            run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(2))
+        };
+
+        for latency_steps in [0, 3] {
+            let res = run(10, latency_steps);
+            let quotes: Vec<(f64, f64)> = res
+                .trajectory
+                .iter()
+                .map(|step| (step.bid_price, step.ask_price))
+                .collect();
+            // Only the refresh sent on steps 0, 10, 20, ... can change the active quotes, which
+            // reach the market `latency_steps` later.
+            for (i, pair) in quotes.windows(2).enumerate() {
+                if (i + 1) % 10 != latency_steps % 10 {
+                    assert_eq!(pair[0], pair[1], "step {}", i + 1);
+                }
+            }
+            assert!(!res.fills.is_empty());
+            assert!(res.quote_staleness_mean > run(1, latency_steps).quote_staleness_mean);
+        }
+
+        let err = SimConfig::builder()
+            .requote_interval(0)
+            .build()
+            .unwrap_err();
+        assert_eq!(err, SimError::ZeroRequoteInterval);
+    }
+
+    #[test]
+    fn test_half_spread_bounds_clamp_quotes() {
+        // sigma = 2 gives a half-spread falling from about 0.85 at t = 0 to 0.65 at the horizon.
+        let params =
+            Parameters::new(0.1, 2.0, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let config = SimConfig::builder()
+            .num_steps(200)
+            .min_half_spread(0.7)
+            .max_half_spread(0.8)
+            .build()
+            .unwrap();
+        let res = run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(4));
+
+        let spreads: Vec<f64> = res
+            .trajectory
+            .iter()
+            .map(|step| step.ask_price - step.bid_price)
+            .collect();
+        for &spread in &spreads {
+            assert!((1.4 - 1e-9..=1.6 + 1e-9).contains(&spread), "{spread}");
+        }
+        // Both bounds bind at some point.
+        assert!((spreads[0] - 1.6).abs() < 1e-9);
+        assert!((spreads[spreads.len() - 1] - 1.4).abs() < 1e-9);
+
+        let unbounded = SimConfig::builder().num_steps(200).build().unwrap();
+        assert_eq!(unbounded.clamp_half_spread(-0.5), -0.5);
+        assert_eq!(config.clamp_half_spread(-0.5), 0.7);
+
+        for (min, max) in [(0.5, 0.4), (-0.1, 1.0), (0.0, 0.0), (f64::NAN, 1.0)] {
+            let err = SimConfig::builder()
+                .min_half_spread(min)
+                .max_half_spread(max)
+                .build()
+                .unwrap_err();
+            assert!(
+                matches!(err, SimError::InvalidHalfSpreadBounds { .. }),
+                "{err}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_crossed_quotes_are_guarded() {
+        // A negative gamma is outside the model's domain: gamma * sigma^2 * (T - t) falls from -4
+        // at t = 0, below the liquidity term of about 2.2, so the formula spread starts negative.
+        let params = Parameters {
+            gamma: -1.0,
+            sigma: 2.0,
+            t_horizon: 1.0,
+            k: 1.5,
+            a: 140.0,
+            quoting_model: QuotingModel::AvellanedaStoikov,
+        };
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let crossing_steps = (0..200)
+            .filter(|&step| optimal_spread(&params, step as f64 * 0.005) <= 0.0)
+            .count();
+        assert!(crossing_steps > 0 && crossing_steps < 200);
+
+        let run = |config: &SimConfig| {
+            run_trajectory_with_rng(&params, config, &model, &mut StdRng::seed_from_u64(6))
+        };
+        let config = SimConfig::builder().num_steps(200).build().unwrap();
+        let res = run(&config);
+        assert_eq!(res.crossed_quote_steps, crossing_steps);
+        for step in &res.trajectory {
+            assert!(step.ask_price >= step.bid_price, "{step:?}");
+        }
+        // A fill on each side in the same step never trades through the other quote.
+        for pair in res.fills.windows(2) {
+            if pair[0].step == pair[1].step {
+                assert!(pair[1].price >= pair[0].price);
+            }
+        }
+
+        let config = SimConfig::builder()
+            .num_steps(200)
+            .min_half_spread(0.1)
+            .build()
+            .unwrap();
+        let res = run(&config);
+        assert_eq!(res.crossed_quote_steps, crossing_steps);
+        for step in &res.trajectory {
+            assert!(step.ask_price - step.bid_price >= 0.2 - 1e-9, "{step:?}");
+        }
+    }
+
+    #[test]
+    fn test_quotes_hold_past_the_horizon() {
+        // The default 600 steps run to t = 3, three times the horizon.
+        let params =
+            Parameters::new(1.0, 2.0, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let config = SimConfig::builder().build().unwrap();
+        let res = run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(6));
+        assert_eq!(res.crossed_quote_steps, 0);
+        let at_horizon = optimal_spread(&params, params.t_horizon);
+        for step in res.trajectory.iter().filter(|step| step.time > 1.0) {
+            assert!((step.ask_price - step.bid_price - at_horizon).abs() < 1e-9);
+            // With no time left the reservation price carries no inventory skew.
+            assert_eq!(step.reservation_price, step.mid_price);
+        }
+    }
+
+    #[test]
+    fn test_pnl_decomposition_sums_to_wealth() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let sizes = Arc::new(EmpiricalSizeDistribution::from_table(&[(1, 0.5), (3, 0.5)]).unwrap());
+        let configs = [
+            SimConfig::builder().num_steps(300).build().unwrap(),
+            SimConfig::builder()
+                .num_steps(300)
+                .drift(0.5)
+                .latency_steps(3)
+                .size_distribution(sizes)
+                .order_size(0.5)
+                .adverse_selection(0.001)
+                .build()
+                .unwrap(),
+            SimConfig::builder()
+                .num_steps(300)
+                .max_drawdown_stop(2.0)
+                .liquidation_cost_per_unit(0.05)
+                .build()
+                .unwrap(),
+        ];
+
+        for config in &configs {
+            for seed in 0..20 {
+                let res = run_trajectory_with_rng(
+                    &params,
+                    config,
+                    &model,
+                    &mut StdRng::seed_from_u64(seed),
+                );
+                let total = res.spread_pnl + res.inventory_pnl;
+                assert!(
+                    (total - res.final_pnl).abs() < 1e-9,
+                    "{total} vs {}",
+                    res.final_pnl
+                );
+
+                // Step by step, the wealth change is the carried inventory times the mid's move
+                // plus the edge of that step's fills, except where the kill-switch flattened.
+                for (i, rows) in res.trajectory.windows(2).enumerate() {
+                    if res.stopped_at.is_some_and(|stop| stop <= i) {
+                        break;
+                    }
+                    let edge: f64 = res
+                        .fills
+                        .iter()
+                        .filter(|fill| fill.step == i)
+                        .map(|fill| match fill.side {
+                            Side::Bid => (fill.mid_at_fill - fill.price) * fill.size,
+                            Side::Ask => (fill.price - fill.mid_at_fill) * fill.size,
+                        })
+                        .sum();
+                    let carried = rows[0].inventory * (rows[1].mid_price - rows[0].mid_price);
+                    let change = rows[1].wealth - rows[0].wealth;
+                    assert!((change - carried - edge).abs() < 1e-9, "step {i}");
+                }
+            }
+        }
+        assert!(
+            (0..20).any(|seed| {
+                run_trajectory_with_rng(
+                    &params,
+                    &configs[2],
+                    &model,
+                    &mut StdRng::seed_from_u64(seed),
+                )
+                .stopped_at
+                .is_some()
+            }),
+            "the kill-switch case should flatten at least once"
+        );
+    }
+
+    #[test]
+    fn test_drift_adjusted_maker_accumulates_inventory_earlier() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let adjusted = Parameters {
+            quoting_model: QuotingModel::DriftAdjusted,
+            ..params
+        };
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let config = SimConfig::builder()
+            .num_steps(200)
+            .drift(0.05)
+            .build()
+            .unwrap();
+
+        // Mean inventory over the first quarter of the horizon, on common random numbers.
+        let early_inventory = |params: &Parameters| {
+            (0..100)
+                .map(|seed| {
+                    let mut rng = StdRng::seed_from_u64(seed);
+                    let res = run_trajectory_with_rng(params, &config, &model, &mut rng);
+                    res.trajectory[..50]
+                        .iter()
+                        .map(|s| s.inventory)
+                        .sum::<f64>()
+                        / 50.0
+                })
+                .sum::<f64>()
+                / 100.0
+        };
+
+        let (plain, leaning) = (early_inventory(&params), early_inventory(&adjusted));
+        assert!(leaning > plain + 0.5, "{leaning} vs {plain}");
+    }
+
+    #[test]
+    fn test_look_ahead_signal_improves_pnl() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let dt: f64 = 0.005;
+
+        // Replayed random walks make the future known; the same fill seed on both legs keeps the
+        // comparison on common random numbers.
+        let (mut plain, mut signalled) = (0.0, 0.0);
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let prices: Arc<[f64]> = (0..200)
+                .scan(100.0, |s: &mut f64, _| {
+                    let current = *s;
+                    *s += 20.0 * dt.sqrt() * rng.sample::<f64, _>(StandardNormal);
+                    Some(current)
+                })
+                .collect();
+            let replay =
+                SimConfig::builder()
+                    .num_steps(prices.len())
+                    .price_process(PriceProcess::Replay {
+                        prices: prices.clone(),
+                    });
+            // The signal is the next move, so the quotes centre on where the mid is going.
+            let next_move = move |t: f64, s: f64| {
+                let step = (t / dt).round() as usize;
+                prices.get(step + 1).map_or(0.0, |next| next - s)
+            };
+            let run = |config: &SimConfig| {
+                let mut fill_rng = StdRng::seed_from_u64(1000 + seed);
+                run_trajectory_with_rng(&params, config, &model, &mut fill_rng).final_pnl
+            };
+
+            plain += run(&replay.clone().build().unwrap());
+            signalled += run(&replay
+                .signal(QuoteSignal::new(1.0, next_move))
+                .build()
+                .unwrap());
+        }
+
+        assert!(
+            signalled > plain,
+            "with signal {signalled}, without {plain}"
+        );
+    }
+
+    #[test]
+    fn test_fill_events_match_fills() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let config = SimConfig::builder().num_steps(300).build().unwrap();
+        let res = run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(11));
+
+        assert!(!res.fills.is_empty());
+        assert_eq!(res.fills.len(), res.bid_fills + res.ask_fills);
+        assert!(res.fills.windows(2).all(|w| w[0].time <= w[1].time));
+
+        let mut spread_pnl = 0.0;
+        for fill in &res.fills {
+            let step = &res.trajectory[fill.step];
+            assert!((fill.time - (step.time + config.dt)).abs() < 1e-12);
+            let (quote, side_sign) = match fill.side {
+                Side::Bid => (step.bid_price, 1.0),
+                Side::Ask => (step.ask_price, -1.0),
+            };
+            assert_eq!(fill.price, quote);
+            assert_eq!(fill.size, 1.0);
+
+            // Inventory moves by the fill, unless the other side also traded on the same step.
+            if let Some(next) = res.trajectory.get(fill.step + 1) {
+                assert_eq!(next.mid_price, fill.mid_at_fill);
+                let same_step = res.fills.iter().filter(|f| f.step == fill.step).count();
+                if same_step == 1 {
+                    assert_eq!(next.inventory - step.inventory, side_sign * fill.size);
+                }
+            }
+            spread_pnl += side_sign * (fill.mid_at_fill - fill.price) * fill.size;
+        }
+        assert!((spread_pnl - res.spread_pnl).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cross_deterministic_fills_at_crossings() {
+        // Half-spread is about 0.65, so the drop to 99 crosses the bid and the rally to 101
+        // crosses the ask; the flat steps cross nothing.
+        let prices = [100.0, 100.0, 99.0, 99.0, 101.0, 101.0];
+        let config = SimConfig::builder()
+            .num_steps(prices.len())
+            .price_process(PriceProcess::Replay {
+                prices: prices.as_slice().into(),
+            })
+            .fill_model(FillModel::CrossDeterministic)
+            .build()
+            .unwrap();
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+
+        let runs: Vec<SimResult> = (0..3)
+            .map(|seed| {
+                run_trajectory_with_rng(&params, &config, &model, &mut StdRng::seed_from_u64(seed))
+            })
+            .collect();
+        let res = &runs[0];
+
+        let inventory: Vec<f64> = res.trajectory.iter().map(|step| step.inventory).collect();
+        assert_eq!(inventory, [0.0, 0.0, 1.0, 1.0, 0.0, 0.0]);
+        assert_eq!((res.bid_fills, res.ask_fills), (1, 1));
+
+        // Each fill happens at the quote that was crossed.
+        let bid = res.trajectory[1].bid_price;
+        let ask = res.trajectory[3].ask_price;
+        assert_eq!(res.trajectory[2].cash, -bid);
+        assert_eq!(res.trajectory[4].cash, ask - bid);
+        assert_eq!(res.traded_notional(), bid + ask);
+        // Both quotes were crossed, so each fill sits on the near side of the new mid.
+        let capture = ((bid - 99.0) + (101.0 - ask)) / 2.0;
+        assert!((res.mean_spread_capture() - capture).abs() < 1e-12);
+
+        // No randomness reaches the fills, so every seed gives the same path.
+        for other in &runs[1..] {
+            assert_eq!(other.final_pnl, res.final_pnl);
+        }
+    }
+
+    #[test]
+    fn test_workspace_reuse_matches_fresh_runs() {
+        let params =
+            Parameters::new(0.1, 0.2, 1.0, 1.5, 140.0, QuotingModel::AvellanedaStoikov).unwrap();
+        let model = ExponentialIntensity { k: 1.5, a: 140.0 };
+        let recalibrating = SimConfig::builder()
+            .num_steps(300)
+            .latency_steps(3)
+            .liquidity(StochasticLiquidity {
+                process: LiquidityProcess::TwoState {
+                    dry_multiplier: 2.0,
+                    p_dry: 0.5,
+                    p_recover: 0.5,
+                },
+                scale_a: false,
+                recalibration_window: Some(50),
+            })
+            .build()
+            .unwrap();
+        let short = SimConfig::builder().num_steps(80).build().unwrap();
+        // Debug output spells out every field, NaN quotes included.
+        let fresh = |config: &SimConfig, seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            format!(
+                "{:?}",
+                run_trajectory_with_rng(&params, config, &model, &mut rng)
+            )
+        };
+
+        // A long run with a latency queue and recalibration samples leaves nothing behind.
+        let mut workspace = TrajectoryWorkspace::new();
+        for (config, seed) in [(&recalibrating, 1), (&short, 2), (&recalibrating, 3)] {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let reused = run_trajectory_into(&mut workspace, &params, config, &model, &mut rng);
+            assert_eq!(format!("{reused:?}"), fresh(config, seed));
+        }
+        assert_eq!(workspace.into_result().trajectory.len(), 300);
+
+        let (mut run, mut mirror) = (TrajectoryWorkspace::new(), TrajectoryWorkspace::new());
+        for seed in [4, 5] {
+            let pair = run_antithetic_pair(
+                &params,
+                &recalibrating,
+                &model,
+                &mut StdRng::seed_from_u64(seed),
+            );
+            let reused = run_antithetic_pair_into(
+                &mut run,
+                &mut mirror,
+                &params,
+                &recalibrating,
+                &model,
+                &mut StdRng::seed_from_u64(seed),
+            );
+            assert_eq!(format!("{reused:?}"), format!("{pair:?}"));
+        }
     }
 }
@@ -0,0 +1,327 @@
+use rand::Rng;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// Tolerance on the total probability of a `(size, probability)` table.
+const PROBABILITY_TOLERANCE: f64 = 1e-6;
+
+/// Discrete distribution of aggressor order sizes, sampled once per fill.
+///
+/// Loaded once and shared across trajectories (e.g. behind an `Arc` in `SimConfig`); sampling only
+/// reads the table and draws from the caller's RNG, so results are reproducible per seed.
+/// Serialised as its `(size, probability)` table and re-validated on deserialisation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "Vec<(u32, f64)>", into = "Vec<(u32, f64)>")
+)]
+pub struct EmpiricalSizeDistribution {
+    sizes: Vec<u32>,
+    cumulative: Vec<f64>,
+}
+
+#[derive(Debug)]
+pub enum SizeDistError {
+    Io(io::Error),
+    /// The file has no data rows.
+    Empty,
+    /// A row could not be parsed; `row` is the 1-based line number.
+    Malformed {
+        row: usize,
+        message: String,
+    },
+    /// Sizes must be strictly positive.
+    NonPositiveSize {
+        row: usize,
+    },
+    /// Probabilities must be finite and non-negative.
+    InvalidProbability {
+        row: usize,
+        probability: f64,
+    },
+    /// Table probabilities must sum to one within tolerance.
+    ProbabilitySum {
+        total: f64,
+    },
+}
+
+impl fmt::Display for SizeDistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SizeDistError::Io(err) => write!(f, "failed to read size distribution: {err}"),
+            SizeDistError::Empty => write!(f, "size distribution has no rows"),
+            SizeDistError::Malformed { row, message } => write!(f, "row {row}: {message}"),
+            SizeDistError::NonPositiveSize { row } => {
+                write!(f, "row {row}: order size must be positive")
+            }
+            SizeDistError::InvalidProbability { row, probability } => {
+                write!(f, "row {row}: invalid probability {probability}")
+            }
+            SizeDistError::ProbabilitySum { total } => {
+                write!(f, "probabilities sum to {total}, expected 1")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SizeDistError {}
+
+impl From<io::Error> for SizeDistError {
+    fn from(err: io::Error) -> Self {
+        SizeDistError::Io(err)
+    }
+}
+
+impl EmpiricalSizeDistribution {
+    /// Builds the distribution from `(size, probability)` pairs.
+    pub fn from_table(table: &[(u32, f64)]) -> Result<Self, SizeDistError> {
+        let rows: Vec<(usize, u32, f64)> = table
+            .iter()
+            .enumerate()
+            .map(|(i, &(size, p))| (i + 1, size, p))
+            .collect();
+        Self::from_rows(&rows)
+    }
+
+    /// Builds the distribution from the empirical frequencies of raw size observations.
+    pub fn from_samples(samples: &[u32]) -> Result<Self, SizeDistError> {
+        if samples.is_empty() {
+            return Err(SizeDistError::Empty);
+        }
+        if let Some(i) = samples.iter().position(|&size| size == 0) {
+            return Err(SizeDistError::NonPositiveSize { row: i + 1 });
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let n = sorted.len() as f64;
+        let table: Vec<(u32, f64)> = sorted
+            .chunk_by(|a, b| a == b)
+            .map(|run| (run[0], run.len() as f64 / n))
+            .collect();
+        Self::from_table(&table)
+    }
+
+    /// Reads a CSV of either `size,probability` rows or single-column raw size samples.
+    ///
+    /// Blank lines and lines starting with `#` are ignored, and a leading non-numeric line is
+    /// treated as a header. Errors report the 1-based line number of the offending row.
+    pub fn from_csv<R: BufRead>(reader: R) -> Result<Self, SizeDistError> {
+        let mut table = Vec::new();
+        let mut samples = Vec::new();
+        let mut seen_data = false;
+
+        for (i, line) in reader.lines().enumerate() {
+            let row = i + 1;
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            if !seen_data && fields[0].parse::<f64>().is_err() {
+                // Header row
+                seen_data = true;
+                continue;
+            }
+            seen_data = true;
+
+            let size = parse_size(fields[0], row)?;
+            match fields.len() {
+                1 if table.is_empty() => samples.push(size),
+                2 if samples.is_empty() => {
+                    let p = fields[1]
+                        .parse::<f64>()
+                        .map_err(|_| SizeDistError::Malformed {
+                            row,
+                            message: format!("invalid probability `{}`", fields[1]),
+                        })?;
+                    table.push((row, size, p));
+                }
+                _ => {
+                    return Err(SizeDistError::Malformed {
+                        row,
+                        message: "expected `size,probability` or a single size per row".into(),
+                    });
+                }
+            }
+        }
+
+        if !table.is_empty() {
+            Self::from_rows(&table)
+        } else {
+            Self::from_samples(&samples)
+        }
+    }
+
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, SizeDistError> {
+        Self::from_csv(BufReader::new(File::open(path)?))
+    }
+
+    fn from_rows(rows: &[(usize, u32, f64)]) -> Result<Self, SizeDistError> {
+        if rows.is_empty() {
+            return Err(SizeDistError::Empty);
+        }
+
+        let mut sizes = Vec::with_capacity(rows.len());
+        let mut cumulative = Vec::with_capacity(rows.len());
+        let mut total = 0.0;
+        for &(row, size, p) in rows {
+            if size == 0 {
+                return Err(SizeDistError::NonPositiveSize { row });
+            }
+            if !p.is_finite() || p < 0.0 {
+                return Err(SizeDistError::InvalidProbability {
+                    row,
+                    probability: p,
+                });
+            }
+            total += p;
+            sizes.push(size);
+            cumulative.push(total);
+        }
+
+        if (total - 1.0).abs() > PROBABILITY_TOLERANCE {
+            return Err(SizeDistError::ProbabilitySum { total });
+        }
+
+        // Renormalise away the tolerated rounding so the last bucket always ends at exactly 1.
+        for c in &mut cumulative {
+            *c /= total;
+        }
+
+        Ok(Self { sizes, cumulative })
+    }
+
+    /// Draws one order size. A single-size table returns it without touching `rng`.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> u32 {
+        if self.sizes.len() == 1 {
+            return self.sizes[0];
+        }
+        let u: f64 = rng.random();
+        let idx = self.cumulative.partition_point(|&c| c <= u);
+        self.sizes[idx.min(self.sizes.len() - 1)]
+    }
+
+    pub fn sizes(&self) -> &[u32] {
+        &self.sizes
+    }
+
+    /// Probability of each entry in `sizes()`.
+    pub fn probabilities(&self) -> Vec<f64> {
+        let mut prev = 0.0;
+        self.cumulative
+            .iter()
+            .map(|&c| {
+                let p = c - prev;
+                prev = c;
+                p
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<Vec<(u32, f64)>> for EmpiricalSizeDistribution {
+    type Error = SizeDistError;
+
+    fn try_from(table: Vec<(u32, f64)>) -> Result<Self, Self::Error> {
+        Self::from_table(&table)
+    }
+}
+
+impl From<EmpiricalSizeDistribution> for Vec<(u32, f64)> {
+    fn from(dist: EmpiricalSizeDistribution) -> Self {
+        dist.sizes
+            .iter()
+            .copied()
+            .zip(dist.probabilities())
+            .collect()
+    }
+}
+
+fn parse_size(field: &str, row: usize) -> Result<u32, SizeDistError> {
+    let value: f64 = field.parse().map_err(|_| SizeDistError::Malformed {
+        row,
+        message: format!("invalid size `{field}`"),
+    })?;
+    if value <= 0.0 {
+        return Err(SizeDistError::NonPositiveSize { row });
+    }
+    if value.fract() != 0.0 || value > u32::MAX as f64 {
+        return Err(SizeDistError::Malformed {
+            row,
+            message: format!("size `{field}` is not a whole number of units"),
+        });
+    }
+    Ok(value as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_sample_frequencies_match_table() {
+        let dist = EmpiricalSizeDistribution::from_csv(
+            "size,probability\n1,0.5\n2,0.3\n5,0.2\n".as_bytes(),
+        )
+        .unwrap();
+
+        let draws = 100_000;
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut counts = [0usize; 3];
+        for _ in 0..draws {
+            let size = dist.sample(&mut rng);
+            let idx = dist.sizes().iter().position(|&s| s == size).unwrap();
+            counts[idx] += 1;
+        }
+
+        for (count, p) in counts.iter().zip(dist.probabilities()) {
+            let freq = *count as f64 / draws as f64;
+            let std_err = (p * (1.0 - p) / draws as f64).sqrt();
+            assert!((freq - p).abs() < 5.0 * std_err, "freq {freq} vs p {p}");
+        }
+    }
+
+    #[test]
+    fn test_raw_samples_become_frequencies() {
+        let dist = EmpiricalSizeDistribution::from_csv("1\n1\n3\n1\n".as_bytes()).unwrap();
+        assert_eq!(dist.sizes(), &[1, 3]);
+        assert_eq!(dist.probabilities(), vec![0.75, 0.25]);
+    }
+
+    #[test]
+    fn test_malformed_rows_are_named() {
+        let err =
+            EmpiricalSizeDistribution::from_csv("size,p\n1,0.5\n2,abc\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, SizeDistError::Malformed { row: 3, .. }));
+        assert!(err.to_string().starts_with("row 3"));
+
+        let err = EmpiricalSizeDistribution::from_csv("1,0.5\n-2,0.5\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, SizeDistError::NonPositiveSize { row: 2 }));
+
+        let err = EmpiricalSizeDistribution::from_csv("1,0.5\n2,0.4\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, SizeDistError::ProbabilitySum { .. }));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip_validates_table() {
+        let dist = EmpiricalSizeDistribution::from_table(&[(1, 0.75), (3, 0.25)]).unwrap();
+        let json = serde_json::to_string(&dist).unwrap();
+        assert_eq!(json, "[[1,0.75],[3,0.25]]");
+        assert_eq!(
+            serde_json::from_str::<EmpiricalSizeDistribution>(&json).unwrap(),
+            dist
+        );
+
+        let err = serde_json::from_str::<EmpiricalSizeDistribution>("[[1,0.5],[2,0.4]]");
+        assert!(err.unwrap_err().to_string().contains("probabilities sum"));
+    }
+}
@@ -0,0 +1,486 @@
+//! Summary statistics over Monte Carlo samples, shared by the sweep aggregation.
+//!
+//! Ratios follow one convention when their denominator is zero: a positive excess gives
+//! `f64::INFINITY`, and anything else (including an empty sample) gives zero. The exceptions are
+//! `sharpe_ratio`, which stays zero on a sample with no variation, as it always has, and
+//! `calmar_ratio`, which follows it so that a run set that never draws down does not rank first.
+
+/// Arithmetic mean; zero when empty.
+pub fn mean(xs: &[f64]) -> f64 {
+    if xs.is_empty() {
+        return 0.0;
+    }
+    xs.iter().sum::<f64>() / xs.len() as f64
+}
+
+/// Sample variance with Bessel's correction; zero when fewer than two samples exist.
+pub fn sample_variance(xs: &[f64]) -> f64 {
+    let n = xs.len() as f64;
+    if n < 2.0 {
+        return 0.0;
+    }
+    let mean = mean(xs);
+    xs.iter().map(|&x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0)
+}
+
+/// Pearson correlation of paired samples; zero when either side has no variation.
+pub fn correlation(xs: &[f64], ys: &[f64]) -> f64 {
+    let n = xs.len().min(ys.len()) as f64;
+    if n < 2.0 {
+        return 0.0;
+    }
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let cov = xs
+        .iter()
+        .zip(ys)
+        .map(|(&x, &y)| (x - mean_x) * (y - mean_y))
+        .sum::<f64>()
+        / (n - 1.0);
+    let scale = (sample_variance(xs) * sample_variance(ys)).sqrt();
+    if scale == 0.0 { 0.0 } else { cov / scale }
+}
+
+/// Percentile `p` in `[0, 1]` of ascending `sorted` samples, interpolating linearly between the
+/// closest ranks: position `p * (n - 1)`, as in NumPy's default and R's type 7. Zero when empty.
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let Some(last) = sorted.len().checked_sub(1) else {
+        return 0.0;
+    };
+    let pos = p.clamp(0.0, 1.0) * last as f64;
+    let (lo, frac) = (pos.floor() as usize, pos.fract());
+    match sorted.get(lo + 1) {
+        Some(&next) => sorted[lo] + frac * (next - sorted[lo]),
+        None => sorted[lo],
+    }
+}
+
+/// Mean over sample standard deviation; zero with fewer than two samples or no variation.
+pub fn sharpe_ratio(xs: &[f64]) -> f64 {
+    if xs.len() < 2 {
+        return 0.0;
+    }
+    let std_dev = sample_variance(xs).sqrt();
+    if std_dev == 0.0 {
+        0.0
+    } else {
+        mean(xs) / std_dev
+    }
+}
+
+/// Mean and variance accumulated one sample at a time with Welford's update, which needs no
+/// stored samples and avoids the cancellation of the naive sum-of-squares formula. The results
+/// follow the conventions of `mean`, `sample_variance`, and `sharpe_ratio`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Welford {
+    count: usize,
+    mean: f64,
+    m2: f64, // Sum of squared deviations from the running mean
+}
+
+impl Welford {
+    pub fn push(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    /// Folds in the samples of `other` as if they had been pushed after this accumulator's own
+    /// (Chan et al.'s pairwise update).
+    pub fn merge(&mut self, other: &Welford) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        let weight = self.count as f64 * other.count as f64 / count as f64;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2 + delta * delta * weight;
+        self.count = count;
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub fn sample_variance(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        self.m2 / (self.count - 1) as f64
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        self.sample_variance().sqrt()
+    }
+
+    pub fn sharpe_ratio(&self) -> f64 {
+        let std_dev = self.std_dev();
+        if std_dev == 0.0 {
+            0.0
+        } else {
+            self.mean / std_dev
+        }
+    }
+}
+
+/// Root mean square of the shortfalls below the minimum acceptable return `mar`.
+///
+/// Samples at or above `mar` count as zero shortfall but still count toward `n`, so the result is
+/// `sqrt(sum(min(x - mar, 0)^2) / n)`. Zero when empty.
+pub fn downside_deviation(xs: &[f64], mar: f64) -> f64 {
+    if xs.is_empty() {
+        return 0.0;
+    }
+    let sum_sq = xs.iter().map(|&x| (x - mar).min(0.0).powi(2)).sum::<f64>();
+    (sum_sq / xs.len() as f64).sqrt()
+}
+
+/// Sortino ratio `(mean - mar) / downside_deviation(xs, mar)`.
+///
+/// With no sample below `mar` the downside deviation is zero: the ratio is then infinite if the
+/// mean exceeds `mar` and zero otherwise (see the module docs).
+pub fn sortino_ratio(xs: &[f64], mar: f64) -> f64 {
+    ratio(mean(xs) - mar, downside_deviation(xs, mar))
+}
+
+/// Largest peak-to-trough fall along `path`, as a non-negative amount; zero when the path never
+/// falls below an earlier value.
+pub fn max_drawdown<'a>(path: impl IntoIterator<Item = &'a f64>) -> f64 {
+    let mut peak = f64::NEG_INFINITY;
+    path.into_iter().fold(0.0, |worst: f64, &x| {
+        peak = peak.max(x);
+        worst.max(peak - x)
+    })
+}
+
+/// Calmar ratio `mean_pnl / mean_max_drawdown`; zero without a drawdown, as `sharpe_ratio` is
+/// without variation (see the module docs).
+pub fn calmar_ratio(mean_pnl: f64, mean_max_drawdown: f64) -> f64 {
+    if mean_max_drawdown > 0.0 {
+        mean_pnl / mean_max_drawdown
+    } else {
+        0.0
+    }
+}
+
+/// Counts of samples in equal-width bins: bin `i` covers `[edges[i], edges[i + 1])`, except that
+/// the last bin also includes its upper edge.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Histogram {
+    pub edges: Vec<f64>, // `counts.len() + 1` ascending edges
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    /// Number of samples binned.
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+}
+
+/// Histogram of the finite values of `pnls` in `num_bins` equal-width bins spanning their minimum
+/// to maximum. NaN and infinite values are left out.
+///
+/// When every value is equal the bins span one unit centred on it, so the single populated bin
+/// still has a width. Without bins or finite values the histogram is empty.
+pub fn pnl_histogram(pnls: &[f64], num_bins: usize) -> Histogram {
+    let finite = || pnls.iter().copied().filter(|x| x.is_finite());
+    let Some((lo, hi)) = finite().fold(None, |range: Option<(f64, f64)>, x| {
+        Some(range.map_or((x, x), |(lo, hi)| (lo.min(x), hi.max(x))))
+    }) else {
+        return Histogram::default();
+    };
+    if num_bins == 0 {
+        return Histogram::default();
+    }
+    let (lo, hi) = if lo == hi {
+        (lo - 0.5, hi + 0.5)
+    } else {
+        (lo, hi)
+    };
+
+    let width = (hi - lo) / num_bins as f64;
+    let edges = (0..=num_bins)
+        .map(|i| {
+            if i == num_bins {
+                hi
+            } else {
+                lo + width * i as f64
+            }
+        })
+        .collect();
+    let mut counts = vec![0; num_bins];
+    for x in finite() {
+        let bin = ((x - lo) / width) as usize;
+        counts[bin.min(num_bins - 1)] += 1;
+    }
+    Histogram { edges, counts }
+}
+
+/// CDF of Student's t distribution with `df` degrees of freedom at `t`.
+pub fn student_t_cdf(t: f64, df: f64) -> f64 {
+    if t.is_nan() || df.is_nan() {
+        return f64::NAN;
+    }
+    // P(|T| > |t|) = I_x(df / 2, 1 / 2) with x = df / (df + t^2).
+    let tail = 0.5 * regularized_beta(df / (df + t * t), 0.5 * df, 0.5);
+    if t > 0.0 { 1.0 - tail } else { tail }
+}
+
+/// Inverse of `student_t_cdf` in `t`, found by bisection; infinite at `p` of 0 or 1.
+pub fn student_t_quantile(p: f64, df: f64) -> f64 {
+    if !(0.0..=1.0).contains(&p) || df.is_nan() {
+        return f64::NAN;
+    }
+    if p == 0.0 || p == 1.0 {
+        return if p == 0.0 {
+            f64::NEG_INFINITY
+        } else {
+            f64::INFINITY
+        };
+    }
+    let (mut lo, mut hi) = (-1.0, 1.0);
+    while student_t_cdf(lo, df) > p {
+        lo *= 2.0;
+    }
+    while student_t_cdf(hi, df) < p {
+        hi *= 2.0;
+    }
+    for _ in 0..200 {
+        let mid = 0.5 * (lo + hi);
+        if student_t_cdf(mid, df) < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+        if hi - lo <= 1e-12 * mid.abs().max(1.0) {
+            break;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+/// Natural log of the gamma function for `x > 0`, by the Lanczos approximation (g = 7).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFS[0], |acc, (i, &c)| acc + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`, from its continued fraction (Lentz).
+fn regularized_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    // The fraction converges fast below the mean; use the symmetry I_x(a, b) = 1 - I_{1-x}(b, a)
+    // above it.
+    if x > (a + 1.0) / (a + b + 2.0) {
+        return 1.0 - regularized_beta(1.0 - x, b, a);
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp() / a;
+
+    const TINY: f64 = 1e-300;
+    let (mut c, mut d) = (1.0, 1.0 - (a + b) * x / (a + 1.0));
+    d = 1.0 / if d.abs() < TINY { TINY } else { d };
+    let mut f = d;
+    for m in 1..300 {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        for coeff in [even, odd] {
+            d = 1.0 + coeff * d;
+            d = 1.0 / if d.abs() < TINY { TINY } else { d };
+            c = 1.0 + coeff / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            f *= c * d;
+        }
+        if (c * d - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+    front * f
+}
+
+/// `excess / risk` under the module's zero-denominator convention.
+fn ratio(excess: f64, risk: f64) -> f64 {
+    if risk > 0.0 {
+        excess / risk
+    } else if excess > 0.0 {
+        f64::INFINITY
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_interpolates() {
+        assert_eq!(percentile(&[1.0, 2.0, 3.0, 4.0], 0.5), 2.5);
+        assert_eq!(percentile(&[1.0, 2.0, 3.0, 4.0, 5.0], 0.25), 2.0);
+        assert_eq!(percentile(&[7.0], 0.75), 7.0);
+        assert_eq!(percentile(&[], 0.5), 0.0);
+    }
+
+    #[test]
+    fn test_sharpe_and_variance() {
+        // Mean 2.5, sample variance 5/3.
+        let xs = [1.0, 2.0, 3.0, 4.0];
+        assert!((sample_variance(&xs) - 5.0 / 3.0).abs() < 1e-12);
+        assert!((sharpe_ratio(&xs) - 2.5 / (5.0f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert_eq!(sharpe_ratio(&[3.0, 3.0, 3.0]), 0.0);
+        assert_eq!(sharpe_ratio(&[3.0]), 0.0);
+        assert!((correlation(&xs, &[2.0, 4.0, 6.0, 8.0]) - 1.0).abs() < 1e-12);
+        assert_eq!(correlation(&xs, &[1.0; 4]), 0.0);
+    }
+
+    #[test]
+    fn test_welford_matches_two_pass() {
+        let xs = [3.5, -1.25, 8.0, 0.0, 2.75, -6.5, 4.0];
+        let mut acc = Welford::default();
+        assert_eq!(
+            (acc.mean(), acc.sample_variance(), acc.sharpe_ratio()),
+            (0.0, 0.0, 0.0)
+        );
+        for &x in &xs {
+            acc.push(x);
+        }
+        assert_eq!(acc.count(), xs.len());
+        assert!((acc.mean() - mean(&xs)).abs() < 1e-12);
+        assert!((acc.sample_variance() - sample_variance(&xs)).abs() < 1e-12);
+        assert!((acc.sharpe_ratio() - sharpe_ratio(&xs)).abs() < 1e-12);
+
+        // A large common offset leaves the single-pass variance intact: the spread of 1, 2, 3.
+        let mut offset = Welford::default();
+        for x in [1.0, 2.0, 3.0] {
+            offset.push(1e9 + x);
+        }
+        assert!((offset.sample_variance() - 1.0).abs() < 1e-6);
+
+        // Merging split halves agrees with pushing every sample into one accumulator.
+        let (mut left, mut right) = (Welford::default(), Welford::default());
+        xs[..3].iter().for_each(|&x| left.push(x));
+        xs[3..].iter().for_each(|&x| right.push(x));
+        left.merge(&right);
+        assert_eq!(left.count(), xs.len());
+        assert!((left.mean() - acc.mean()).abs() < 1e-12);
+        assert!((left.sample_variance() - acc.sample_variance()).abs() < 1e-12);
+        let mut empty = Welford::default();
+        empty.merge(&acc);
+        assert_eq!((empty.mean(), empty.m2), (acc.mean(), acc.m2));
+    }
+
+    #[test]
+    fn test_sortino_ratio() {
+        // Shortfalls below zero are -2 and -4 over 4 samples: sqrt((4 + 16) / 4) = sqrt(5).
+        let xs = [-2.0, -4.0, 3.0, 7.0];
+        assert!((downside_deviation(&xs, 0.0) - 5.0f64.sqrt()).abs() < 1e-12);
+        assert!((sortino_ratio(&xs, 0.0) - 1.0 / 5.0f64.sqrt()).abs() < 1e-12);
+
+        // Raising the MAR to 3 adds the sample at 3 as a zero shortfall: sqrt((25 + 49) / 4).
+        let expected = (1.0 - 3.0) / (74.0f64 / 4.0).sqrt();
+        assert!((sortino_ratio(&xs, 3.0) - expected).abs() < 1e-12);
+
+        // Upside volatility is not penalised, unlike Sharpe.
+        let skewed = [-1.0, 1.0, 1.0, 20.0];
+        assert!(sortino_ratio(&skewed, 0.0) > sharpe_ratio(&skewed));
+    }
+
+    #[test]
+    fn test_sortino_without_downside() {
+        assert_eq!(sortino_ratio(&[1.0, 2.0, 5.0], 0.0), f64::INFINITY);
+        assert_eq!(sortino_ratio(&[1.0, 2.0, 5.0], 1.0), f64::INFINITY);
+        assert_eq!(sortino_ratio(&[0.0, 0.0], 0.0), 0.0);
+        assert_eq!(sortino_ratio(&[], 0.0), 0.0);
+        // A single loss makes it finite again.
+        assert!(sortino_ratio(&[1.0, 2.0, 5.0, -0.1], 0.0).is_finite());
+    }
+
+    #[test]
+    fn test_max_drawdown_and_calmar() {
+        // Peaks at 3 and then 4; the worst fall is 4 -> -1.
+        let path = [0.0, 3.0, 1.0, 4.0, -1.0, 2.0];
+        assert_eq!(max_drawdown(&path), 5.0);
+        assert_eq!(max_drawdown(&[0.0, 1.0, 2.0]), 0.0);
+        assert_eq!(max_drawdown(&[]), 0.0);
+        assert_eq!(max_drawdown(&[2.0, 1.0]), 1.0);
+
+        assert_eq!(calmar_ratio(2.0, 5.0), 0.4);
+        assert_eq!(calmar_ratio(-2.0, 5.0), -0.4);
+        assert_eq!(calmar_ratio(2.0, 0.0), 0.0);
+        assert_eq!(calmar_ratio(0.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn test_pnl_histogram() {
+        // Symmetric about zero, with an odd bin count so no sample sits on the middle edge.
+        let pnls: Vec<f64> = (-50..=50).map(|i| (i as f64 / 10.0).powi(3)).collect();
+        let hist = pnl_histogram(&pnls, 7);
+        assert_eq!(hist.total(), pnls.len());
+        assert_eq!(hist.edges.len(), 8);
+        assert_eq!((hist.edges[0], hist.edges[7]), (-125.0, 125.0));
+        let mirrored: Vec<usize> = hist.counts.iter().rev().copied().collect();
+        assert_eq!(hist.counts, mirrored);
+        assert!(hist.counts[3] > hist.counts[0]);
+
+        // Non-finite values are skipped, and equal values get a unit-wide range.
+        let flat = pnl_histogram(&[2.0, 2.0, f64::NAN, 2.0], 4);
+        assert_eq!(flat.counts, vec![0, 0, 3, 0]);
+        assert_eq!((flat.edges[0], flat.edges[4]), (1.5, 2.5));
+
+        assert_eq!(pnl_histogram(&[1.0, 2.0], 0), Histogram::default());
+        assert_eq!(pnl_histogram(&[f64::NAN], 3), Histogram::default());
+    }
+
+    #[test]
+    fn test_student_t_distribution() {
+        // Reference values from standard t tables.
+        assert!((student_t_cdf(2.0, 10.0) - 0.963_306).abs() < 1e-6);
+        assert!((student_t_cdf(-2.0, 10.0) - 0.036_694).abs() < 1e-6);
+        assert_eq!(student_t_cdf(0.0, 5.0), 0.5);
+        // One degree of freedom is the Cauchy distribution.
+        assert!((student_t_cdf(1.0, 1.0) - 0.75).abs() < 1e-12);
+        assert_eq!(student_t_cdf(f64::INFINITY, 3.0), 1.0);
+
+        assert!((student_t_quantile(0.975, 10.0) - 2.228_139).abs() < 1e-6);
+        assert!((student_t_quantile(0.975, 1.0) - 12.706_205).abs() < 1e-5);
+        assert!((student_t_quantile(0.025, 30.0) + 2.042_272).abs() < 1e-6);
+        // Approaches the normal quantile as the degrees of freedom grow.
+        assert!((student_t_quantile(0.975, 1e6) - 1.959_966).abs() < 1e-5);
+        assert_eq!(student_t_quantile(1.0, 4.0), f64::INFINITY);
+    }
+}